@@ -5,14 +5,21 @@ pub mod tari;
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        .plugin(tauri_plugin_log::Builder::new().build())
         .plugin(tauri_plugin_opener::init())
         .invoke_handler(tauri::generate_handler![
             tari::coins_load,
             tari::transactions_load,
             exchange::add_liquidity,
             exchange::remove_liquidity,
+            exchange::zap_in,
+            exchange::zap_out,
             exchange::swap,
-            exchange::exchange_state
+            exchange::swap_for_exact,
+            exchange::swap_route,
+            exchange::quote_swap,
+            exchange::exchange_state,
+            exchange::order_book
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -1,15 +1,18 @@
 use std::str::FromStr as _;
 
-use tari_all_in_one_rust_sdk::{indexer, wallet_daemon};
+use serde::de::DeserializeOwned;
+use tari_all_in_one_rust_sdk::{exchange, indexer, wallet_daemon};
 use tari_exchange::{
-    Exchange, LIQUIDITY_PROVIDER, TEX_COMPONENT_INSTANCE_ADDRESS_STR, TEX_TEMPLATE_HEX,
+    max_amount_with_slippage, min_amount_with_slippage, orders::OrderBook,
+    router::DEFAULT_MAX_HOPS, Exchange, Pair, LIQUIDITY_PROVIDER,
+    TEX_COMPONENT_INSTANCE_ADDRESS_STR,
 };
 use tari_template_lib::{
     args,
-    prelude::{Amount, ComponentAddress, ResourceAddress, TemplateAddress},
+    prelude::{Amount, ComponentAddress, ResourceAddress},
 };
-use tari_transaction::Transaction;
-use tari_wallet_daemon_client::ComponentAddressOrName;
+use tari_transaction::{Transaction, TransactionBuilder, UnsignedTransaction};
+use tari_wallet_daemon_client::{ComponentAddressOrName, WalletDaemonClient};
 
 #[tauri::command(rename_all = "snake_case")]
 pub async fn add_liquidity(
@@ -17,107 +20,146 @@ pub async fn add_liquidity(
     a_amount: f64,
     b_resource_address_str: String,
     b_amount: f64,
+    fee_tier: f64,
+    min_lp_amount: f64,
 ) -> Result<String, ()> {
     let a_resource_address = ResourceAddress::from_str(&a_resource_address_str).unwrap();
     let b_resource_address = ResourceAddress::from_str(&b_resource_address_str).unwrap();
-    let mut wallet_daemon_client = wallet_daemon::client_connect_and_login(None).await.unwrap();
-    let tex_component_instance_address =
-        ComponentAddress::from_str(TEX_COMPONENT_INSTANCE_ADDRESS_STR).unwrap();
-    let liquidity_provider_account_name = LIQUIDITY_PROVIDER.to_string();
-    //TODO: would be nice to store in local storage or somewhere in backend?
-    let liquidity_provider_account = wallet_daemon_client
-        .accounts_get(ComponentAddressOrName::Name(
-            liquidity_provider_account_name.clone(),
-        ))
-        .await
-        .unwrap()
-        .account;
-    let liquidity_provider_address =
-        ComponentAddress::try_from(liquidity_provider_account.address).unwrap();
-    let transaction_id = wallet_daemon::transaction_call(
-        liquidity_provider_account.key_index,
-        Transaction::builder()
-            .fee_transaction_pay_from_component(liquidity_provider_address, Amount(1000))
+    submit(|builder, account, tex| {
+        builder
             .call_method(
-                liquidity_provider_address,
+                account,
                 "withdraw",
                 args![a_resource_address, a_amount.round() as i64],
             )
             .put_last_instruction_output_on_workspace("a")
             .call_method(
-                liquidity_provider_address,
+                account,
                 "withdraw",
                 args![b_resource_address, b_amount.round() as i64],
             )
             .put_last_instruction_output_on_workspace("b")
             .call_method(
-                tex_component_instance_address,
-                "add_liquidity",
-                args![Workspace("a"), Workspace("b")],
+                tex,
+                "add_liquidity_with_min_lp",
+                args![
+                    Workspace("a"),
+                    Workspace("b"),
+                    fee_tier.round() as u32,
+                    min_lp_amount.round() as i64
+                ],
             )
-            .put_last_instruction_output_on_workspace("liquidity_provided_token")
+            .put_last_instruction_output_on_workspace("liquidity_provided_token_and_refund")
             .call_method(
-                liquidity_provider_address,
-                "deposit",
-                args![Workspace("liquidity_provided_token")],
+                account,
+                "deposit_all",
+                args![Workspace("liquidity_provided_token_and_refund")],
             )
-            .build_unsigned_transaction(),
-        &mut wallet_daemon_client,
-    )
+    })
     .await
-    .unwrap();
-    println!("{}", transaction_id);
-    Ok(transaction_id.to_string())
 }
 
 #[tauri::command(rename_all = "snake_case")]
 pub async fn remove_liquidity(
     lp_resource_address_str: String,
     lp_amount: f64,
+    min_a_amount: f64,
+    min_b_amount: f64,
 ) -> Result<String, ()> {
     let lp_resource_address = ResourceAddress::from_str(&lp_resource_address_str).unwrap();
-    let mut wallet_daemon_client = wallet_daemon::client_connect_and_login(None).await.unwrap();
-    let tex_component_instance_address =
-        ComponentAddress::from_str(TEX_COMPONENT_INSTANCE_ADDRESS_STR).unwrap();
-    let liquidity_provider_account_name = LIQUIDITY_PROVIDER.to_string();
-    //TODO: would be nice to store in local storage or somewhere in backend?
-    let liquidity_provider_account = wallet_daemon_client
-        .accounts_get(ComponentAddressOrName::Name(
-            liquidity_provider_account_name.clone(),
-        ))
-        .await
-        .unwrap()
-        .account;
-    let liquidity_provider_address =
-        ComponentAddress::try_from(liquidity_provider_account.address).unwrap();
-    let transaction_id = wallet_daemon::transaction_call(
-        liquidity_provider_account.key_index,
-        Transaction::builder()
-            .fee_transaction_pay_from_component(liquidity_provider_address, Amount(1000))
+    submit(|builder, account, tex| {
+        builder
             .call_method(
-                liquidity_provider_address,
+                account,
                 "withdraw",
                 args![lp_resource_address, lp_amount.round() as i64],
             )
             .put_last_instruction_output_on_workspace("lp")
             .call_method(
-                tex_component_instance_address,
-                "remove_liquidity",
-                args![Workspace("lp")],
+                tex,
+                "remove_liquidity_with_min_amounts",
+                args![
+                    Workspace("lp"),
+                    min_a_amount.round() as i64,
+                    min_b_amount.round() as i64
+                ],
             )
             .put_last_instruction_output_on_workspace("a_and_b")
+            .call_method(account, "deposit_all", args![Workspace("a_and_b")])
+    })
+    .await
+}
+
+/// Adds liquidity to the pool of `a` and `b` resources with `a` only, see `Tex::zap_in`.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn zap_in(
+    a_resource_address_str: String,
+    a_amount: f64,
+    b_resource_address_str: String,
+    fee_tier: f64,
+    min_lp_amount: f64,
+) -> Result<String, ()> {
+    let a_resource_address = ResourceAddress::from_str(&a_resource_address_str).unwrap();
+    let b_resource_address = ResourceAddress::from_str(&b_resource_address_str).unwrap();
+    submit(|builder, account, tex| {
+        builder
             .call_method(
-                liquidity_provider_address,
+                account,
+                "withdraw",
+                args![a_resource_address, a_amount.round() as i64],
+            )
+            .put_last_instruction_output_on_workspace("a")
+            .call_method(
+                tex,
+                "zap_in",
+                args![
+                    Workspace("a"),
+                    (a_resource_address, b_resource_address),
+                    fee_tier.round() as u32,
+                    min_lp_amount.round() as i64
+                ],
+            )
+            .put_last_instruction_output_on_workspace("liquidity_provided_token_and_refund")
+            .call_method(
+                account,
                 "deposit_all",
-                args![Workspace("a_and_b")],
+                args![Workspace("liquidity_provided_token_and_refund")],
             )
-            .build_unsigned_transaction(),
-        &mut wallet_daemon_client,
-    )
+    })
+    .await
+}
+
+/// Removes liquidity and takes it all in `b`, see `Tex::zap_out`.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn zap_out(
+    lp_resource_address_str: String,
+    lp_amount: f64,
+    b_resource_address_str: String,
+    min_b_amount: f64,
+) -> Result<String, ()> {
+    let lp_resource_address = ResourceAddress::from_str(&lp_resource_address_str).unwrap();
+    let b_resource_address = ResourceAddress::from_str(&b_resource_address_str).unwrap();
+    submit(|builder, account, tex| {
+        builder
+            .call_method(
+                account,
+                "withdraw",
+                args![lp_resource_address, lp_amount.round() as i64],
+            )
+            .put_last_instruction_output_on_workspace("lp")
+            .call_method(
+                tex,
+                "zap_out",
+                args![
+                    Workspace("lp"),
+                    b_resource_address,
+                    min_b_amount.round() as i64
+                ],
+            )
+            .put_last_instruction_output_on_workspace("b")
+            .call_method(account, "deposit", args![Workspace("b")])
+    })
     .await
-    .unwrap();
-    println!("{}", transaction_id);
-    Ok(transaction_id.to_string())
 }
 
 #[tauri::command(rename_all = "snake_case")]
@@ -125,145 +167,227 @@ pub async fn swap(
     a_resource_address_str: String,
     a_amount: f64,
     b_resource_address_str: String,
+    fee_tier: f64,
+    slippage_tolerance: f64,
 ) -> Result<String, ()> {
     let a_resource_address = ResourceAddress::from_str(&a_resource_address_str).unwrap();
     let b_resource_address = ResourceAddress::from_str(&b_resource_address_str).unwrap();
-    let mut wallet_daemon_client = wallet_daemon::client_connect_and_login(None).await.unwrap();
-    let tex_component_instance_address =
-        ComponentAddress::from_str(TEX_COMPONENT_INSTANCE_ADDRESS_STR).unwrap();
-    let liquidity_provider_account_name = LIQUIDITY_PROVIDER.to_string();
-    //TODO: would be nice to store in local storage or somewhere in backend?
-    let liquidity_provider_account = wallet_daemon_client
-        .accounts_get(ComponentAddressOrName::Name(
-            liquidity_provider_account_name.clone(),
-        ))
+    let a_amount = a_amount.round() as i64;
+    let fee_tier = fee_tier.round() as u32;
+    let expected_b_amount = fetch_exchange()
         .await
-        .unwrap()
-        .account;
-    let liquidity_provider_address =
-        ComponentAddress::try_from(liquidity_provider_account.address).unwrap();
-    let transaction_id = wallet_daemon::transaction_call(
-        liquidity_provider_account.key_index,
-        Transaction::builder()
-            .fee_transaction_pay_from_component(liquidity_provider_address, Amount(1000))
-            .call_method(
-                liquidity_provider_address,
-                "withdraw",
-                args![a_resource_address, a_amount.round() as i64],
-            )
+        .quote_swap(a_resource_address, a_amount, b_resource_address, fee_tier)
+        .ok_or(())?;
+    let min_b_amount = min_amount_with_slippage(expected_b_amount, slippage_tolerance);
+    submit(|builder, account, tex| {
+        builder
+            .call_method(account, "withdraw", args![a_resource_address, a_amount])
             .put_last_instruction_output_on_workspace("a")
             .call_method(
-                tex_component_instance_address,
-                "swap",
-                args![Workspace("a"), b_resource_address],
+                tex,
+                "swap_with_min_out",
+                args![Workspace("a"), b_resource_address, fee_tier, min_b_amount],
             )
             .put_last_instruction_output_on_workspace("b")
-            .call_method(liquidity_provider_address, "deposit", args![Workspace("b")])
-            .build_unsigned_transaction(),
-        &mut wallet_daemon_client,
-    )
+            .call_method(account, "deposit", args![Workspace("b")])
+    })
     .await
-    .unwrap();
-    println!("{}", transaction_id);
-    Ok(transaction_id.to_string())
 }
 
 #[tauri::command(rename_all = "snake_case")]
-pub async fn exchange_state(account_name: String) -> Exchange {
-    println!("{account_name} loads exchange");
-    let mut indexer_client = indexer::client_connect(None).await.unwrap();
-    let substates = indexer::substates(
-        TemplateAddress::from_hex(TEX_TEMPLATE_HEX).unwrap(),
-        &mut indexer_client,
-    )
+pub async fn swap_for_exact(
+    a_resource_address_str: String,
+    b_resource_address_str: String,
+    fee_tier: f64,
+    b_amount: f64,
+    slippage_tolerance: f64,
+) -> Result<String, ()> {
+    let a_resource_address = ResourceAddress::from_str(&a_resource_address_str).unwrap();
+    let b_resource_address = ResourceAddress::from_str(&b_resource_address_str).unwrap();
+    let b_amount = b_amount.round() as i64;
+    let fee_tier = fee_tier.round() as u32;
+    let expected_a_amount = fetch_exchange()
+        .await
+        .quote_swap_for_exact(a_resource_address, b_resource_address, fee_tier, b_amount)
+        .ok_or(())?;
+    let max_a_amount = max_amount_with_slippage(expected_a_amount, slippage_tolerance);
+    submit(|builder, account, tex| {
+        builder
+            .call_method(account, "withdraw", args![a_resource_address, max_a_amount])
+            .put_last_instruction_output_on_workspace("a")
+            .call_method(
+                tex,
+                "swap_for_exact",
+                args![Workspace("a"), b_resource_address, fee_tier, b_amount],
+            )
+            .put_last_instruction_output_on_workspace("b_and_change")
+            .call_method(account, "deposit_all", args![Workspace("b_and_change")])
+    })
     .await
-    .unwrap();
-    println!("exchange substates: {substates:?}");
-    let deserialized = substates
-        .first()
-        .unwrap()
-        .substate_value()
-        .as_component()
-        .unwrap()
-        .body
-        .state
-        .deserialized::<low_level::Exchange>()
-        .unwrap();
-    println!("exchange deserialized: {deserialized:?}");
-    let result = Exchange::from(deserialized);
-    println!("exchange converted: {result:?}");
-    result
 }
 
-mod low_level {
-    use std::collections::HashMap;
+/// Swaps through the best route found over current pools, possibly via other resources.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn swap_route(
+    a_resource_address_str: String,
+    a_amount: f64,
+    b_resource_address_str: String,
+    slippage_tolerance: f64,
+) -> Result<String, ()> {
+    let a_resource_address = ResourceAddress::from_str(&a_resource_address_str).unwrap();
+    let b_resource_address = ResourceAddress::from_str(&b_resource_address_str).unwrap();
+    let a_amount = a_amount.round() as i64;
+    let route = fetch_exchange()
+        .await
+        .best_route(
+            a_resource_address,
+            a_amount,
+            b_resource_address,
+            DEFAULT_MAX_HOPS,
+        )
+        .ok_or(())?;
+    log::debug!("route: {route:?}");
+    let min_b_amount = min_amount_with_slippage(route.amount_out, slippage_tolerance);
+    let path = route.path;
+    submit(|builder, account, tex| {
+        builder
+            .call_method(account, "withdraw", args![a_resource_address, a_amount])
+            .put_last_instruction_output_on_workspace("a")
+            .call_method(tex, "swap_route", args![Workspace("a"), path, min_b_amount])
+            .put_last_instruction_output_on_workspace("b")
+            .call_method(account, "deposit", args![Workspace("b")])
+    })
+    .await
+}
 
-    use serde::{Deserialize, Serialize};
-    use tari_all_in_one_rust_sdk::indexer;
-    use tari_template_lib::prelude::{ResourceAddress, Vault};
-    use tokio::{runtime::Handle, task};
+/// Asks Tex what a swap would return right now, by dry-running `get_amount_out`.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn quote_swap(
+    a_resource_address_str: String,
+    a_amount: f64,
+    b_resource_address_str: String,
+    fee_tier: f64,
+) -> Result<i64, ()> {
+    let a_resource_address = ResourceAddress::from_str(&a_resource_address_str).unwrap();
+    let b_resource_address = ResourceAddress::from_str(&b_resource_address_str).unwrap();
+    dry_run::<_, Amount>(|builder, _, tex| {
+        builder.call_method(
+            tex,
+            "get_amount_out",
+            args![
+                a_resource_address,
+                a_amount.round() as i64,
+                b_resource_address,
+                fee_tier.round() as u32
+            ],
+        )
+    })
+    .await
+    .map(|amount| amount.value())
+}
 
-    pub type Pair = (ResourceAddress, ResourceAddress);
+#[tauri::command(rename_all = "snake_case")]
+pub async fn exchange_state(account_name: String) -> Exchange {
+    log::info!("{account_name} loads exchange");
+    fetch_exchange().await
+}
 
-    #[derive(Debug, Clone, Serialize, Deserialize)]
-    struct LiquidityPool {
-        a: Vault,
-        b: Vault,
-        lp_resource: ResourceAddress,
-        fees_collected: HashMap<String, f64>,
-    }
+/// Open limit orders of the pool of `a` and `b` resources with the `fee_tier`.
+#[tauri::command(rename_all = "snake_case")]
+pub async fn order_book(
+    a_resource_address_str: String,
+    b_resource_address_str: String,
+    fee_tier: f64,
+) -> Result<OrderBook, ()> {
+    let a_resource_address = ResourceAddress::from_str(&a_resource_address_str).unwrap();
+    let b_resource_address = ResourceAddress::from_str(&b_resource_address_str).unwrap();
+    let pair = Pair::new(a_resource_address, b_resource_address).map_err(|_| ())?;
+    fetch_exchange()
+        .await
+        .order_book(&pair, fee_tier.round() as u32)
+        .ok_or(())
+}
 
-    #[derive(Debug, Clone, Default, Serialize, Deserialize)]
-    pub struct LiquidityPools {
-        inner: HashMap<Pair, LiquidityPool>,
-    }
+/// Submits the transaction of `instructions` from the liquidity provider account, which pays the
+/// fee, see `liquidity_provider_transaction`.
+async fn submit<I>(instructions: I) -> Result<String, ()>
+where
+    I: FnOnce(TransactionBuilder, ComponentAddress, ComponentAddress) -> TransactionBuilder,
+{
+    let (key_index, transaction, mut wallet_daemon_client) =
+        liquidity_provider_transaction(instructions).await?;
+    let transaction_id =
+        wallet_daemon::transaction_call(key_index, transaction, &mut wallet_daemon_client)
+            .await
+            .map_err(|error| log::error!("transaction submission failed: {error:?}"))?;
+    log::info!("transaction submitted: {transaction_id}");
+    Ok(transaction_id.to_string())
+}
 
-    #[derive(Debug, Clone, Default, Serialize, Deserialize)]
-    pub struct Exchange {
-        liquidity_pools: LiquidityPools,
-        fee: i64,
-    }
+/// Executes the transaction of `instructions` from the liquidity provider account without
+/// committing it and decodes the output of its first instruction, see
+/// `liquidity_provider_transaction`.
+async fn dry_run<I, T>(instructions: I) -> Result<T, ()>
+where
+    I: FnOnce(TransactionBuilder, ComponentAddress, ComponentAddress) -> TransactionBuilder,
+    T: DeserializeOwned,
+{
+    let (key_index, transaction, mut wallet_daemon_client) =
+        liquidity_provider_transaction(instructions).await?;
+    let result =
+        wallet_daemon::transaction_dry_run(key_index, transaction, &mut wallet_daemon_client)
+            .await
+            .map_err(|error| log::error!("transaction dry run failed: {error:?}"))?;
+    result
+        .execution_results
+        .first()
+        .and_then(|instruction_result| instruction_result.decode().ok())
+        .ok_or(())
+}
 
-    impl From<Exchange> for tari_exchange::Exchange {
-        fn from(value: Exchange) -> Self {
-            Self {
-                liquidity_pools: value.liquidity_pools.into(),
-                fee: value.fee,
-            }
-        }
-    }
-    impl From<LiquidityPools> for tari_exchange::LiquidityPools {
-        fn from(value: LiquidityPools) -> Self {
-            Self {
-                inner: value
-                    .inner
-                    .into_iter()
-                    .map(|(pair, pool)| (format!("{}:{}", pair.0, pair.1), pool.into()))
-                    .collect(),
-            }
-        }
-    }
+/// Builds a transaction paid by the liquidity provider account, with `instructions` adding the
+/// calls given that account and the Tex component instance addresses.
+async fn liquidity_provider_transaction<I>(
+    instructions: I,
+) -> Result<(u64, UnsignedTransaction, WalletDaemonClient), ()>
+where
+    I: FnOnce(TransactionBuilder, ComponentAddress, ComponentAddress) -> TransactionBuilder,
+{
+    let mut wallet_daemon_client = wallet_daemon::client_connect_and_login(None)
+        .await
+        .map_err(|error| log::error!("wallet daemon login failed: {error:?}"))?;
+    let tex_component_instance_address =
+        ComponentAddress::from_str(TEX_COMPONENT_INSTANCE_ADDRESS_STR).unwrap();
+    //TODO: would be nice to store in local storage or somewhere in backend?
+    let liquidity_provider_account = wallet_daemon_client
+        .accounts_get(ComponentAddressOrName::Name(LIQUIDITY_PROVIDER.to_string()))
+        .await
+        .map_err(|error| log::error!("liquidity provider account not found: {error:?}"))?
+        .account;
+    let liquidity_provider_address =
+        ComponentAddress::try_from(liquidity_provider_account.address).unwrap();
+    let transaction = instructions(
+        Transaction::builder()
+            .fee_transaction_pay_from_component(liquidity_provider_address, Amount(1000)),
+        liquidity_provider_address,
+        tex_component_instance_address,
+    )
+    .build_unsigned_transaction();
+    Ok((
+        liquidity_provider_account.key_index,
+        transaction,
+        wallet_daemon_client,
+    ))
+}
 
-    impl From<LiquidityPool> for tari_exchange::LiquidityPool {
-        fn from(value: LiquidityPool) -> Self {
-            task::block_in_place(move || {
-                Handle::current().block_on(async {
-                    let mut indexer_client = indexer::client_connect(None).await.unwrap();
-                    Self {
-                        a: indexer::get_vault(value.a.vault_id(), &mut indexer_client)
-                            .await
-                            .balance()
-                            .0,
-                        b: indexer::get_vault(value.b.vault_id(), &mut indexer_client)
-                            .await
-                            .balance()
-                            .0,
-                        lp_resource: value.lp_resource,
-                        //TODO:
-                        fees_collected: HashMap::new(),
-                    }
-                })
-            })
-        }
-    }
+async fn fetch_exchange() -> Exchange {
+    let mut indexer_client = indexer::client_connect(None).await.unwrap();
+    let result = exchange::exchange_state(
+        ComponentAddress::from_str(TEX_COMPONENT_INSTANCE_ADDRESS_STR).unwrap(),
+        &mut indexer_client,
+    )
+    .await
+    .unwrap();
+    log::debug!("exchange: {result:?}");
+    result
 }
@@ -5,7 +5,7 @@ pub mod exchange;
 
 #[tauri::command(rename_all = "snake_case")]
 pub async fn coins_load(account_name: &str) -> Result<Vec<TariCoin>, ()> {
-    println!("{account_name} loads coins");
+    log::info!("{account_name} loads coins");
     let mut wallet_daemon_client = wallet_daemon::client_connect_and_login(None).await.unwrap();
     let tokens = wallet_daemon::accounts_tokens(account_name, &mut wallet_daemon_client)
         .await
@@ -23,7 +23,7 @@ pub async fn coins_load(account_name: &str) -> Result<Vec<TariCoin>, ()> {
 
 #[tauri::command(rename_all = "snake_case")]
 pub async fn transactions_load(account_name: &str) -> Result<Vec<TariTransaction>, ()> {
-    println!("{account_name} loads transactions");
+    log::info!("{account_name} loads transactions");
     let mut wallet_daemon_client = wallet_daemon::client_connect_and_login(None).await.unwrap();
     let transactions =
         wallet_daemon::accounts_transactions(account_name, &mut wallet_daemon_client)
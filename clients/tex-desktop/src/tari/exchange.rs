@@ -8,6 +8,8 @@ pub async fn add_liquidity(
     a_amount: f64,
     b_resource_address_str: String,
     b_amount: f64,
+    fee_tier: f64,
+    min_lp_amount: f64,
 ) -> String {
     serde_wasm_bindgen::from_value(
         app::invoke(
@@ -18,6 +20,8 @@ pub async fn add_liquidity(
                    "a_amount": a_amount,
                    "b_resource_address_str": b_resource_address_str,
                    "b_amount": b_amount,
+                   "fee_tier": fee_tier,
+                   "min_lp_amount": min_lp_amount,
                 }
             ))
             .unwrap(),
@@ -27,7 +31,12 @@ pub async fn add_liquidity(
     .unwrap()
 }
 
-pub async fn remove_liquidity(lp_resource_address_str: String, lp_amount: f64) -> String {
+pub async fn remove_liquidity(
+    lp_resource_address_str: String,
+    lp_amount: f64,
+    min_a_amount: f64,
+    min_b_amount: f64,
+) -> String {
     serde_wasm_bindgen::from_value(
         app::invoke(
             "remove_liquidity",
@@ -35,6 +44,58 @@ pub async fn remove_liquidity(lp_resource_address_str: String, lp_amount: f64) -
                {
                    "lp_resource_address_str": lp_resource_address_str,
                    "lp_amount": lp_amount,
+                   "min_a_amount": min_a_amount,
+                   "min_b_amount": min_b_amount,
+                }
+            ))
+            .unwrap(),
+        )
+        .await,
+    )
+    .unwrap()
+}
+
+pub async fn zap_in(
+    a_resource_address_str: String,
+    a_amount: f64,
+    b_resource_address_str: String,
+    fee_tier: f64,
+    min_lp_amount: f64,
+) -> String {
+    serde_wasm_bindgen::from_value(
+        app::invoke(
+            "zap_in",
+            serde_wasm_bindgen::to_value(&json!(
+               {
+                   "a_resource_address_str": a_resource_address_str,
+                   "a_amount": a_amount,
+                   "b_resource_address_str": b_resource_address_str,
+                   "fee_tier": fee_tier,
+                   "min_lp_amount": min_lp_amount,
+                }
+            ))
+            .unwrap(),
+        )
+        .await,
+    )
+    .unwrap()
+}
+
+pub async fn zap_out(
+    lp_resource_address_str: String,
+    lp_amount: f64,
+    b_resource_address_str: String,
+    min_b_amount: f64,
+) -> String {
+    serde_wasm_bindgen::from_value(
+        app::invoke(
+            "zap_out",
+            serde_wasm_bindgen::to_value(&json!(
+               {
+                   "lp_resource_address_str": lp_resource_address_str,
+                   "lp_amount": lp_amount,
+                   "b_resource_address_str": b_resource_address_str,
+                   "min_b_amount": min_b_amount,
                 }
             ))
             .unwrap(),
@@ -48,6 +109,8 @@ pub async fn swap(
     a_resource_address_str: String,
     a_amount: f64,
     b_resource_address_str: String,
+    fee_tier: f64,
+    slippage_tolerance: f64,
 ) -> String {
     serde_wasm_bindgen::from_value(
         app::invoke(
@@ -57,6 +120,83 @@ pub async fn swap(
                    "a_resource_address_str": a_resource_address_str,
                    "a_amount": a_amount,
                    "b_resource_address_str": b_resource_address_str,
+                   "fee_tier": fee_tier,
+                   "slippage_tolerance": slippage_tolerance,
+                }
+            ))
+            .unwrap(),
+        )
+        .await,
+    )
+    .unwrap()
+}
+
+/// Expected output of a swap, `None` if Tex can't quote it (e.g. there is no such pool).
+pub async fn quote_swap(
+    a_resource_address_str: String,
+    a_amount: f64,
+    b_resource_address_str: String,
+    fee_tier: f64,
+) -> Option<i64> {
+    serde_wasm_bindgen::from_value(
+        app::invoke(
+            "quote_swap",
+            serde_wasm_bindgen::to_value(&json!(
+               {
+                   "a_resource_address_str": a_resource_address_str,
+                   "a_amount": a_amount,
+                   "b_resource_address_str": b_resource_address_str,
+                   "fee_tier": fee_tier,
+                }
+            ))
+            .unwrap(),
+        )
+        .await,
+    )
+    .ok()
+}
+
+pub async fn swap_for_exact(
+    a_resource_address_str: String,
+    b_resource_address_str: String,
+    fee_tier: f64,
+    b_amount: f64,
+    slippage_tolerance: f64,
+) -> String {
+    serde_wasm_bindgen::from_value(
+        app::invoke(
+            "swap_for_exact",
+            serde_wasm_bindgen::to_value(&json!(
+               {
+                   "a_resource_address_str": a_resource_address_str,
+                   "b_resource_address_str": b_resource_address_str,
+                   "fee_tier": fee_tier,
+                   "b_amount": b_amount,
+                   "slippage_tolerance": slippage_tolerance,
+                }
+            ))
+            .unwrap(),
+        )
+        .await,
+    )
+    .unwrap()
+}
+
+pub async fn swap_route(
+    a_resource_address_str: String,
+    a_amount: f64,
+    b_resource_address_str: String,
+    slippage_tolerance: f64,
+) -> String {
+    serde_wasm_bindgen::from_value(
+        app::invoke(
+            "swap_route",
+            serde_wasm_bindgen::to_value(&json!(
+               {
+                   "a_resource_address_str": a_resource_address_str,
+                   "a_amount": a_amount,
+                   "b_resource_address_str": b_resource_address_str,
+                   "slippage_tolerance": slippage_tolerance,
                 }
             ))
             .unwrap(),
@@ -82,7 +222,11 @@ pub fn ActionsView() -> View {
         div(class="actions") {
             button(on:click=move |_| open_popup("add")) { "Add Liquidity" }
             button(on:click=move |_| open_popup("remove")) { "Remove Liquidity" }
+            button(on:click=move |_| open_popup("zap_in")) { "Add With One Token" }
+            button(on:click=move |_| open_popup("zap_out")) { "Remove To One Token" }
             button(on:click=move |_| open_popup("swap")) { "Execute Swap" }
+            button(on:click=move |_| open_popup("swap_for_exact")) { "Swap For Exact" }
+            button(on:click=move |_| open_popup("swap_route")) { "Swap Via Route" }
         }
          (if show_popup.get() {
             if let Some(menu_item) = selected_menu_item.get_clone() {
@@ -107,6 +251,26 @@ pub fn ActionsView() -> View {
                                 }
                             }
                         },
+                    "zap_in" =>
+                        view! {
+                            div(class="popup") {
+                                div(class="popup-content") {
+                                    h3 { (selected_menu_item.get_clone().unwrap_or(String::from("UNKNOWN"))) }
+                                    ZapInPopupView()
+                                    button(on:click=move |_| close_popup(), class="close-button") { "Close" }
+                                }
+                            }
+                        },
+                    "zap_out" =>
+                        view! {
+                            div(class="popup") {
+                                div(class="popup-content") {
+                                    h3 { (selected_menu_item.get_clone().unwrap_or(String::from("UNKNOWN"))) }
+                                    ZapOutPopupView()
+                                    button(on:click=move |_| close_popup(), class="close-button") { "Close" }
+                                }
+                            }
+                        },
                     "swap" =>
                          view! {
                             div(class="popup") {
@@ -117,6 +281,26 @@ pub fn ActionsView() -> View {
                                 }
                             }
                         },
+                    "swap_for_exact" =>
+                         view! {
+                            div(class="popup") {
+                                div(class="popup-content") {
+                                    h3 { (selected_menu_item.get_clone().unwrap_or(String::from("UNKNOWN"))) }
+                                    SwapForExactPopupView()
+                                    button(on:click=move |_| close_popup(), class="close-button") { "Close" }
+                                }
+                            }
+                        },
+                    "swap_route" =>
+                         view! {
+                            div(class="popup") {
+                                div(class="popup-content") {
+                                    h3 { (selected_menu_item.get_clone().unwrap_or(String::from("UNKNOWN"))) }
+                                    SwapRoutePopupView()
+                                    button(on:click=move |_| close_popup(), class="close-button") { "Close" }
+                                }
+                            }
+                        },
                         _ => view! {}
                     }
                 }
@@ -133,6 +317,8 @@ pub fn AddLiquidityPopupView() -> View {
     let a_amount = create_signal(0_f64);
     let b_resource_address_str = create_signal(String::new());
     let b_amount = create_signal(0_f64);
+    let fee_tier = create_signal(3000_f64);
+    let min_lp_amount = create_signal(0_f64);
     let transaction_id = create_signal(String::new());
     let handle_add_liquidity = move |_| async move {
         transaction_id.set(
@@ -141,6 +327,8 @@ pub fn AddLiquidityPopupView() -> View {
                 a_amount.get_clone(),
                 b_resource_address_str.get_clone(),
                 b_amount.get_clone(),
+                fee_tier.get_clone(),
+                min_lp_amount.get_clone(),
             )
             .await,
         );
@@ -150,6 +338,8 @@ pub fn AddLiquidityPopupView() -> View {
         input(id="a_amount",r#type="number", min="1", step="1", max="1000", bind:valueAsNumber=a_amount)
         input(id="b_resource_address_str",bind:value=b_resource_address_str,placeholder="B")
         input(id="b_amount",r#type="number", min="1", step="1", max="1000", bind:valueAsNumber=b_amount)
+        input(id="fee_tier",r#type="number", min="0", step="1", max="1000000", placeholder="Fee tier, hundredths of a bip", bind:valueAsNumber=fee_tier)
+        input(id="min_lp_amount",r#type="number", min="0", step="1", placeholder="Min LP", bind:valueAsNumber=min_lp_amount)
         button(on:click=handle_add_liquidity) { "Add" }
         p { (transaction_id) }
     }
@@ -159,32 +349,181 @@ pub fn AddLiquidityPopupView() -> View {
 pub fn RemoveLiquidityPopupView() -> View {
     let lp_resource_address_str = create_signal(String::new());
     let lp_amount = create_signal(0_f64);
+    let min_a_amount = create_signal(0_f64);
+    let min_b_amount = create_signal(0_f64);
     let transaction_id = create_signal(String::new());
     let handle_remove_liquidity = move |_| async move {
         transaction_id.set(
-            remove_liquidity(lp_resource_address_str.get_clone(), lp_amount.get_clone()).await,
+            remove_liquidity(
+                lp_resource_address_str.get_clone(),
+                lp_amount.get_clone(),
+                min_a_amount.get_clone(),
+                min_b_amount.get_clone(),
+            )
+            .await,
         );
     };
     view! {
         input(id="lp_resource_address_str",bind:value=lp_resource_address_str,placeholder="LP")
         input(id="lp_amount",r#type="number", min="1", step="1", max="1000", bind:valueAsNumber=lp_amount)
+        input(id="min_a_amount",r#type="number", min="0", step="1", placeholder="Min A (pair order)", bind:valueAsNumber=min_a_amount)
+        input(id="min_b_amount",r#type="number", min="0", step="1", placeholder="Min B (pair order)", bind:valueAsNumber=min_b_amount)
         button(on:click=handle_remove_liquidity) { "Remove" }
         p { (transaction_id) }
     }
 }
 
+#[component]
+pub fn ZapInPopupView() -> View {
+    let a_resource_address_str = create_signal(String::new());
+    let a_amount = create_signal(0_f64);
+    let b_resource_address_str = create_signal(String::new());
+    let fee_tier = create_signal(3000_f64);
+    let min_lp_amount = create_signal(0_f64);
+    let transaction_id = create_signal(String::new());
+    let handle_zap_in = move |_| async move {
+        transaction_id.set(
+            zap_in(
+                a_resource_address_str.get_clone(),
+                a_amount.get_clone(),
+                b_resource_address_str.get_clone(),
+                fee_tier.get_clone(),
+                min_lp_amount.get_clone(),
+            )
+            .await,
+        );
+    };
+    view! {
+        input(id="a_resource_address_str",bind:value=a_resource_address_str,placeholder="A")
+        input(id="a_amount",r#type="number", min="1", step="1", max="1000", bind:valueAsNumber=a_amount)
+        input(id="b_resource_address_str",bind:value=b_resource_address_str,placeholder="B (pool pair)")
+        input(id="fee_tier",r#type="number", min="0", step="1", max="1000000", placeholder="Fee tier, hundredths of a bip", bind:valueAsNumber=fee_tier)
+        input(id="min_lp_amount",r#type="number", min="0", step="1", placeholder="Min LP", bind:valueAsNumber=min_lp_amount)
+        button(on:click=handle_zap_in) { "Add" }
+        p { (transaction_id) }
+    }
+}
+
+#[component]
+pub fn ZapOutPopupView() -> View {
+    let lp_resource_address_str = create_signal(String::new());
+    let lp_amount = create_signal(0_f64);
+    let b_resource_address_str = create_signal(String::new());
+    let min_b_amount = create_signal(0_f64);
+    let transaction_id = create_signal(String::new());
+    let handle_zap_out = move |_| async move {
+        transaction_id.set(
+            zap_out(
+                lp_resource_address_str.get_clone(),
+                lp_amount.get_clone(),
+                b_resource_address_str.get_clone(),
+                min_b_amount.get_clone(),
+            )
+            .await,
+        );
+    };
+    view! {
+        input(id="lp_resource_address_str",bind:value=lp_resource_address_str,placeholder="LP")
+        input(id="lp_amount",r#type="number", min="1", step="1", max="1000", bind:valueAsNumber=lp_amount)
+        input(id="b_resource_address_str",bind:value=b_resource_address_str,placeholder="Receive")
+        input(id="min_b_amount",r#type="number", min="0", step="1", placeholder="Min received", bind:valueAsNumber=min_b_amount)
+        button(on:click=handle_zap_out) { "Remove" }
+        p { (transaction_id) }
+    }
+}
+
 #[component]
 pub fn SwapPopupView() -> View {
     let a_resource_address_str = create_signal(String::new());
     let a_amount = create_signal(0_f64);
     let b_resource_address_str = create_signal(String::new());
+    let fee_tier = create_signal(3000_f64);
+    let slippage_tolerance = create_signal(0.5_f64);
+    let quote = create_signal(String::new());
     let transaction_id = create_signal(String::new());
+    let handle_quote = move |_| async move {
+        quote.set(
+            quote_swap(
+                a_resource_address_str.get_clone(),
+                a_amount.get_clone(),
+                b_resource_address_str.get_clone(),
+                fee_tier.get_clone(),
+            )
+            .await
+            .map(|amount| format!("Expected: {amount}"))
+            .unwrap_or_else(|| String::from("Quote is not available")),
+        );
+    };
     let handle_swap = move |_| async move {
         transaction_id.set(
             swap(
                 a_resource_address_str.get_clone(),
                 a_amount.get_clone(),
                 b_resource_address_str.get_clone(),
+                fee_tier.get_clone(),
+                slippage_tolerance.get_clone(),
+            )
+            .await,
+        );
+    };
+    view! {
+        input(id="a_resource_address_str",bind:value=a_resource_address_str,placeholder="A")
+        input(id="a_amount",r#type="number", min="1", step="1", max="1000", bind:valueAsNumber=a_amount)
+        input(id="b_resource_address_str",bind:value=b_resource_address_str,placeholder="B")
+        input(id="fee_tier",r#type="number", min="0", step="1", max="1000000", placeholder="Fee tier, hundredths of a bip", bind:valueAsNumber=fee_tier)
+        input(id="slippage_tolerance",r#type="number", min="0", step="0.1", max="100", placeholder="Slippage tolerance, %", bind:valueAsNumber=slippage_tolerance)
+        button(on:click=handle_quote) { "Quote" }
+        p { (quote) }
+        button(on:click=handle_swap) { "Swap" }
+        p { (transaction_id) }
+    }
+}
+
+#[component]
+pub fn SwapForExactPopupView() -> View {
+    let a_resource_address_str = create_signal(String::new());
+    let b_resource_address_str = create_signal(String::new());
+    let fee_tier = create_signal(3000_f64);
+    let b_amount = create_signal(0_f64);
+    let slippage_tolerance = create_signal(0.5_f64);
+    let transaction_id = create_signal(String::new());
+    let handle_swap = move |_| async move {
+        transaction_id.set(
+            swap_for_exact(
+                a_resource_address_str.get_clone(),
+                b_resource_address_str.get_clone(),
+                fee_tier.get_clone(),
+                b_amount.get_clone(),
+                slippage_tolerance.get_clone(),
+            )
+            .await,
+        );
+    };
+    view! {
+        input(id="a_resource_address_str",bind:value=a_resource_address_str,placeholder="A")
+        input(id="b_resource_address_str",bind:value=b_resource_address_str,placeholder="B")
+        input(id="fee_tier",r#type="number", min="0", step="1", max="1000000", placeholder="Fee tier, hundredths of a bip", bind:valueAsNumber=fee_tier)
+        input(id="b_amount",r#type="number", min="1", step="1", max="1000", bind:valueAsNumber=b_amount)
+        input(id="slippage_tolerance",r#type="number", min="0", step="0.1", max="100", placeholder="Slippage tolerance, %", bind:valueAsNumber=slippage_tolerance)
+        button(on:click=handle_swap) { "Swap" }
+        p { (transaction_id) }
+    }
+}
+
+#[component]
+pub fn SwapRoutePopupView() -> View {
+    let a_resource_address_str = create_signal(String::new());
+    let a_amount = create_signal(0_f64);
+    let b_resource_address_str = create_signal(String::new());
+    let slippage_tolerance = create_signal(0.5_f64);
+    let transaction_id = create_signal(String::new());
+    let handle_swap = move |_| async move {
+        transaction_id.set(
+            swap_route(
+                a_resource_address_str.get_clone(),
+                a_amount.get_clone(),
+                b_resource_address_str.get_clone(),
+                slippage_tolerance.get_clone(),
             )
             .await,
         );
@@ -193,6 +532,7 @@ pub fn SwapPopupView() -> View {
         input(id="a_resource_address_str",bind:value=a_resource_address_str,placeholder="A")
         input(id="a_amount",r#type="number", min="1", step="1", max="1000", bind:valueAsNumber=a_amount)
         input(id="b_resource_address_str",bind:value=b_resource_address_str,placeholder="B")
+        input(id="slippage_tolerance",r#type="number", min="0", step="0.1", max="100", placeholder="Slippage tolerance, %", bind:valueAsNumber=slippage_tolerance)
         button(on:click=handle_swap) { "Swap" }
         p { (transaction_id) }
     }
@@ -168,19 +168,36 @@ pub fn PoolsView() -> View {
 }
 
 #[component(inline_props)]
-fn TariPoolView(model: LiquidityPool) -> View {
+fn TariPoolView(model: LiquidityPool, protocol_fee_share: u32, epoch: u64) -> View {
+    let lp_fee = model.lp_fee(protocol_fee_share, epoch);
     let LiquidityPool {
         a,
         b,
         lp_resource,
+        fee,
         fees_collected,
+        protocol_fee_on,
+        paused,
+        ..
     } = model;
     view! {
         tr {
             td { (lp_resource.to_string()) }
             td { (a) }
             td { (b) }
-            // td { (fees_collected.to_string()) }
+            td { (format!("{}%", f64::from(fee) / 10_000.0)) }
+            td { (format!("{}%", f64::from(lp_fee) / 10_000.0)) }
+            td { (if protocol_fee_on { "on" } else { "off" }) }
+            td { (if paused { "paused" } else { "active" }) }
+            td {
+                ul {
+                    Keyed(
+                        list=fees_collected.into_iter().collect::<Vec<_>>(),
+                        view=|(resource, fee)| view! { li { (fee) b { (resource) } } },
+                        key=|(resource, _)| resource.clone(),
+                    )
+                }
+            }
         }
     }
 }
@@ -189,20 +206,34 @@ fn TariPoolView(model: LiquidityPool) -> View {
 pub fn TariPoolsListView(maybe_exchange: Resource<Exchange>) -> View {
     view! {
         (if let Some(exchange) = maybe_exchange.get_clone() {
+            let protocol_fee_share = exchange.protocol_fee_share;
+            let epoch = exchange.epoch;
+            let paused = exchange.paused;
             view! {
+                (if paused {
+                    view! { p(class="paused") { "Exchange is paused, only liquidity removal is allowed" } }
+                } else {
+                    view! {}
+                })
                 table {
                     thead {
                         tr {
                             th { "LP: " }
                             th { "A: " }
                             th { "B: " }
-                            // th { "Fees: " }
+                            th { "Fee: " }
+                            th { "LP Fee: " }
+                            th { "Protocol Fee: " }
+                            th { "Status: " }
+                            th { "Fees: " }
                         }
                     }
                     tbody {
                         Keyed(
                             list=exchange.pools(),
-                            view=move |transaction| view! { TariPoolView(model=transaction) },
+                            view=move |transaction| view! {
+                                TariPoolView(model=transaction, protocol_fee_share=protocol_fee_share, epoch=epoch)
+                            },
                             key=|transaction| transaction.lp_resource.to_string(),
                         )
                     }
@@ -0,0 +1,610 @@
+//   Copyright 2025. The Tari Project
+//
+//   Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//   following conditions are met:
+//
+//   1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//   disclaimer.
+//
+//   2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//   following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//   3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//   products derived from this software without specific prior written permission.
+//
+//   THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//   INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//   DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//   SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//   SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//   WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//   USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Concentrated liquidity pools.
+//! Providers choose a range of ticks for their liquidity, which backs swaps and earns fees only
+//! while the price is within the range. Each deposit is a position, represented by a non-fungible
+//! LP token carrying the `Position` data. Swaps move the price across ticks, activating and
+//! deactivating liquidity of positions bounded by them.
+
+use tari_template_abi::rust::collections::{BTreeMap, HashMap};
+use tari_template_lib::{
+    prelude::*,
+    template_dependencies::serde::{Deserialize, Serialize},
+};
+
+use crate::{events, lp_token, math, tick_math, Fee, Pair, Treasury};
+
+/// Data of a position token.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Position {
+    pub lower_tick: i32,
+    pub upper_tick: i32,
+    pub liquidity: u128,
+    /// Fee growth inside the range when the position was opened, fees earned since are paid out
+    /// when the position is closed.
+    fee_growth_inside: FeeGrowth,
+}
+
+/// Fees earned per unit of liquidity, scaled by `2^64`, for each resource of the pair.
+/// Wraps around on overflow, differences stay correct.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+struct FeeGrowth {
+    a: u128,
+    b: u128,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Tick {
+    /// Liquidity activated when the price crosses the tick upwards, deactivated when downwards.
+    liquidity_net: i128,
+    /// Liquidity of all positions bounded by the tick, the tick is dropped once it's zero.
+    liquidity_gross: u128,
+    /// Fee growth on the other side of the tick from the current price.
+    fee_growth_outside: FeeGrowth,
+}
+
+/// Price of a pool a swap moves, walked apart from the pool so quotes leave it untouched.
+#[derive(Debug, Clone, Copy)]
+struct SwapState {
+    sqrt_price: u128,
+    tick: i32,
+    liquidity: u128,
+}
+
+/// Part of a swap within a single range of constant liquidity.
+struct Step {
+    swap: tick_math::SwapStep,
+    /// Liquidity active during the step.
+    liquidity: u128,
+    /// Initialized tick the step ended on, crossed in the direction of the swap.
+    crossed: Option<i32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConcentratedPool {
+    a: Vault,
+    b: Vault,
+    position_resource: ResourceAddress,
+    pub fee: Fee,
+    /// Square root of the price of `a` in `b`, see `tick_math`. Zero until the first position
+    /// is opened, which sets the initial price.
+    sqrt_price: u128,
+    /// Greatest tick at or below the price.
+    tick: i32,
+    /// Liquidity of positions with the price in their range.
+    liquidity: u128,
+    /// Ticks bounding at least one position.
+    ticks: BTreeMap<i32, Tick>,
+    fee_growth_global: FeeGrowth,
+    /// Fees charged on swaps, kept apart from the reserves until positions are closed.
+    fees_collected: HashMap<ResourceAddress, Vault>,
+    positions_opened: u64,
+    /// Whether the `Treasury` takes its share of swap fees charged by this pool.
+    pub protocol_fee_on: bool,
+    /// Paused pools accept neither swaps nor new positions until the admin unpauses them,
+    /// positions can still be closed.
+    pub paused: bool,
+}
+
+impl FeeGrowth {
+    fn wrapping_sub(self, other: FeeGrowth) -> FeeGrowth {
+        FeeGrowth {
+            a: self.a.wrapping_sub(other.a),
+            b: self.b.wrapping_sub(other.b),
+        }
+    }
+
+    /// Fees earned by `liquidity` over this growth, per resource, rounded down.
+    fn fees(&self, liquidity: u128) -> (Amount, Amount) {
+        let fees = |growth: u128| narrow(math::mul_div(liquidity, growth, tick_math::Q64));
+        (fees(self.a), fees(self.b))
+    }
+}
+
+impl ConcentratedPool {
+    pub fn new(pair: Pair, fee: Fee, lp_token: lp_token::LpToken) -> Self {
+        assert!(
+            i128::from(fee.get()) < math::FEE_DENOMINATOR,
+            "concentrated liquidity pools can't charge a 100% fee"
+        );
+        let position_resource = lp_token.non_fungible();
+        let fees_collected = [pair.a(), pair.b()]
+            .into_iter()
+            .map(|resource| (resource, Vault::new_empty(resource)))
+            .collect();
+        let pool = ConcentratedPool {
+            a: Vault::new_empty(pair.a()),
+            b: Vault::new_empty(pair.b()),
+            position_resource,
+            fee,
+            sqrt_price: 0,
+            tick: 0,
+            liquidity: 0,
+            ticks: BTreeMap::new(),
+            fee_growth_global: FeeGrowth::default(),
+            fees_collected,
+            positions_opened: 0,
+            protocol_fee_on: false,
+            paused: false,
+        };
+        pool.event(events::POOL_CREATED).emit();
+        pool
+    }
+
+    pub fn position_resource(&self) -> ResourceAddress {
+        self.position_resource
+    }
+
+    /// Ticks of positions must be multiples of it, so pools with higher fees, meant for more
+    /// volatile pairs, have fewer ticks to cross.
+    pub fn tick_spacing(&self) -> i32 {
+        (self.fee.get() / 50).max(1) as i32
+    }
+
+    /// Current tick, `None` until the first position sets the price.
+    pub fn current_tick(&self) -> Option<i32> {
+        (self.sqrt_price != 0).then_some(self.tick)
+    }
+
+    /// Event of the pool, prefilled with the pool identity, reserves and the current tick.
+    fn event(&self, topic: &'static str) -> events::PoolEvent {
+        events::PoolEvent::new(topic)
+            .with("lp_resource", self.position_resource)
+            .with("fee_tier", self.fee)
+            .with("resource_a", self.a.resource_address())
+            .with("resource_b", self.b.resource_address())
+            .with("reserve_a", self.a.balance())
+            .with("reserve_b", self.b.balance())
+            .with("tick", self.tick)
+            .with("liquidity", self.liquidity)
+    }
+
+    /// Opens a position with liquidity between `lower_tick` and `upper_tick`.
+    /// The first position sets the initial price to the ratio of provided amounts.
+    /// Returns the position token together with refunds of what the position does not need.
+    pub fn open_position(
+        &mut self,
+        (a, b): (Bucket, Bucket),
+        lower_tick: i32,
+        upper_tick: i32,
+    ) -> (Bucket, Bucket, Bucket) {
+        self.assert_not_paused();
+        let (mut a, mut b) = if a.resource_address() == self.a.resource_address() {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        let spacing = self.tick_spacing();
+        assert!(
+            lower_tick < upper_tick && lower_tick % spacing == 0 && upper_tick % spacing == 0,
+            "invalid range [{lower_tick}, {upper_tick}), ticks must be ascending multiples of {spacing}"
+        );
+        let lower = tick_math::sqrt_price_at_tick(lower_tick);
+        let upper = tick_math::sqrt_price_at_tick(upper_tick);
+        if self.sqrt_price == 0 {
+            self.sqrt_price = tick_math::sqrt_price_of_amounts(wide(a.amount()), wide(b.amount()));
+            self.tick = tick_math::tick_at_sqrt_price(self.sqrt_price);
+        }
+        let liquidity = tick_math::liquidity_for_amounts(
+            self.sqrt_price,
+            lower,
+            upper,
+            wide(a.amount()),
+            wide(b.amount()),
+        );
+        assert!(
+            liquidity > 0,
+            "provided amounts are too small to open a position"
+        );
+        assert!(
+            liquidity <= tick_math::MAX_LIQUIDITY,
+            "position liquidity {liquidity} is too large, widen the range"
+        );
+        let (a_amount, b_amount) = self.amounts(lower, upper, liquidity, true);
+        assert!(
+            a.amount() >= a_amount && b.amount() >= b_amount,
+            "position requires ({a_amount}, {b_amount}), ({}, {}) provided",
+            a.amount(),
+            b.amount()
+        );
+        self.update_tick(lower_tick, liquidity as i128, false);
+        self.update_tick(upper_tick, liquidity as i128, true);
+        if (lower_tick..upper_tick).contains(&self.tick) {
+            self.liquidity += liquidity;
+            assert!(
+                self.liquidity <= tick_math::MAX_LIQUIDITY,
+                "pool liquidity {} is too large",
+                self.liquidity
+            );
+        }
+        self.a.deposit(a.take(a_amount));
+        self.b.deposit(b.take(b_amount));
+        self.positions_opened += 1;
+        let position_id = NonFungibleId::from_u64(self.positions_opened);
+        let position = ResourceManager::get(self.position_resource).mint_non_fungible(
+            position_id.clone(),
+            &Position {
+                lower_tick,
+                upper_tick,
+                liquidity,
+                fee_growth_inside: self.fee_growth_inside(lower_tick, upper_tick),
+            },
+            &(),
+        );
+        self.event(events::POSITION_OPENED)
+            .with("position", position_id)
+            .with("lower_tick", lower_tick)
+            .with("upper_tick", upper_tick)
+            .with("position_liquidity", liquidity)
+            .with("amount_a", a_amount)
+            .with("amount_b", b_amount)
+            .emit();
+        (position, a, b)
+    }
+
+    /// Closes the `position`, returning its share of reserves together with fees it earned.
+    pub fn close_position(&mut self, position_bucket: Bucket) -> (Bucket, Bucket) {
+        assert!(
+            position_bucket.resource_address() == self.position_resource
+                && position_bucket.amount() == Amount::new(1),
+            "exactly one position token of {} expected",
+            self.position_resource
+        );
+        let position_id = position_bucket
+            .get_non_fungible_ids()
+            .pop()
+            .expect("position token is missing");
+        let position: Position = ResourceManager::get(self.position_resource)
+            .get_non_fungible(&position_id)
+            .get_data();
+        let Position {
+            lower_tick,
+            upper_tick,
+            liquidity,
+            ..
+        } = position;
+        let (a_fees, b_fees) = self
+            .fee_growth_inside(lower_tick, upper_tick)
+            .wrapping_sub(position.fee_growth_inside)
+            .fees(liquidity);
+        self.update_tick(lower_tick, -(liquidity as i128), false);
+        self.update_tick(upper_tick, -(liquidity as i128), true);
+        if (lower_tick..upper_tick).contains(&self.tick) {
+            self.liquidity -= liquidity;
+        }
+        let (a_amount, b_amount) = self.amounts(
+            tick_math::sqrt_price_at_tick(lower_tick),
+            tick_math::sqrt_price_at_tick(upper_tick),
+            liquidity,
+            false,
+        );
+        position_bucket.burn();
+        let mut a_bucket = self.a.withdraw(a_amount);
+        let mut b_bucket = self.b.withdraw(b_amount);
+        a_bucket.deposit(
+            self.fee_vault_mut(a_bucket.resource_address())
+                .withdraw(a_fees),
+        );
+        b_bucket.deposit(
+            self.fee_vault_mut(b_bucket.resource_address())
+                .withdraw(b_fees),
+        );
+        self.event(events::POSITION_CLOSED)
+            .with("position", position_id)
+            .with("lower_tick", lower_tick)
+            .with("upper_tick", upper_tick)
+            .with("position_liquidity", liquidity)
+            .with("amount_a", a_amount)
+            .with("amount_b", b_amount)
+            .with("fees_a", a_fees)
+            .with("fees_b", b_fees)
+            .emit();
+        (a_bucket, b_bucket)
+    }
+
+    /// Swaps the `input`, the treasury takes its share of the fee if the protocol fee is on.
+    pub fn swap(
+        &mut self,
+        mut input: Bucket,
+        output_address: ResourceAddress,
+        treasury: &mut Treasury,
+    ) -> Bucket {
+        self.assert_not_paused();
+        let input_resource = input.resource_address();
+        let input_amount = input.amount();
+        let a_to_b = self.is_a_to_b(input_resource, output_address);
+        let (steps, end) = self.steps(input_resource, input_amount, a_to_b);
+        let mut output_amount = 0;
+        let mut fee_amount = 0;
+        let mut protocol_fee_amount = 0;
+        for Step {
+            swap: step,
+            liquidity,
+            crossed,
+        } in steps
+        {
+            output_amount += step.amount_out;
+            fee_amount += step.fee_amount;
+            // taken per step, so fee growth accounts exactly for what positions are left with
+            let protocol_fee = if self.protocol_fee_on {
+                wide(math::protocol_fee_amount(
+                    narrow(step.fee_amount),
+                    treasury.share,
+                ))
+            } else {
+                0
+            };
+            protocol_fee_amount += protocol_fee;
+            if liquidity > 0 {
+                let growth =
+                    math::mul_div(step.fee_amount - protocol_fee, tick_math::Q64, liquidity);
+                if a_to_b {
+                    self.fee_growth_global.a = self.fee_growth_global.a.wrapping_add(growth);
+                } else {
+                    self.fee_growth_global.b = self.fee_growth_global.b.wrapping_add(growth);
+                }
+            }
+            if let Some(tick) = crossed {
+                self.cross(tick);
+            }
+        }
+        self.sqrt_price = end.sqrt_price;
+        self.tick = end.tick;
+        self.liquidity = end.liquidity;
+        let (output_amount, fee_amount, protocol_fee_amount) = (
+            narrow(output_amount),
+            narrow(fee_amount),
+            narrow(protocol_fee_amount),
+        );
+        assert!(
+            output_amount.is_positive(),
+            "swap of {input_amount} {input_resource} is too small to return anything"
+        );
+        let mut fee_bucket = input.take(fee_amount);
+        if self.protocol_fee_on {
+            treasury.deposit(fee_bucket.take(protocol_fee_amount));
+        }
+        self.fee_vault_mut(input_resource).deposit(fee_bucket);
+        let (input_vault, output_vault) = self.vaults_mut(input_resource, output_address);
+        input_vault.deposit(input);
+        let output = output_vault.withdraw(output_amount);
+        self.event(events::SWAP)
+            .with("input_resource", input_resource)
+            .with("input_amount", input_amount)
+            .with("output_resource", output_address)
+            .with("output_amount", output_amount)
+            .with("fee", fee_amount)
+            .with("protocol_fee", protocol_fee_amount)
+            .with("fee_rate", self.fee)
+            .emit();
+        output
+    }
+
+    /// Output of a `swap` of `input_amount` together with the fee charged on it.
+    pub fn quote_amount_out(
+        &self,
+        input_resource: ResourceAddress,
+        input_amount: Amount,
+        output_resource: ResourceAddress,
+    ) -> (Amount, Amount) {
+        let a_to_b = self.is_a_to_b(input_resource, output_resource);
+        let (steps, _) = self.steps(input_resource, input_amount, a_to_b);
+        let (output_amount, fee_amount) = steps.iter().fold((0, 0), |(output, fee), step| {
+            (output + step.swap.amount_out, fee + step.swap.fee_amount)
+        });
+        (narrow(output_amount), narrow(fee_amount))
+    }
+
+    /// Walks the price for a swap of `input_amount`, tick by tick, without changing the pool.
+    /// Returns steps of the swap together with the price it ends at.
+    fn steps(
+        &self,
+        input_resource: ResourceAddress,
+        input_amount: Amount,
+        a_to_b: bool,
+    ) -> (Vec<Step>, SwapState) {
+        assert!(
+            self.sqrt_price != 0,
+            "pool {} has no positions yet",
+            self.position_resource
+        );
+        let fee = self.fee.get();
+        let mut state = SwapState {
+            sqrt_price: self.sqrt_price,
+            tick: self.tick,
+            liquidity: self.liquidity,
+        };
+        let mut steps = Vec::new();
+        let mut remaining = wide(input_amount);
+        while remaining > 0 {
+            let (next_tick, next) = if a_to_b {
+                self.ticks.range(..=state.tick).next_back()
+            } else {
+                self.ticks.range(state.tick + 1..).next()
+            }
+            .unwrap_or_else(|| {
+                panic!(
+                    "pool {} has not enough liquidity for {input_amount} of {input_resource}",
+                    self.position_resource
+                )
+            });
+            let target = tick_math::sqrt_price_at_tick(*next_tick);
+            let step = tick_math::swap_step(
+                state.sqrt_price,
+                target,
+                state.liquidity,
+                remaining,
+                fee,
+                a_to_b,
+            );
+            remaining -= step.amount_in + step.fee_amount;
+            let liquidity = state.liquidity;
+            state.sqrt_price = step.sqrt_price;
+            let crossed = (step.sqrt_price == target).then_some(*next_tick);
+            if crossed.is_some() {
+                let liquidity_net = if a_to_b {
+                    -next.liquidity_net
+                } else {
+                    next.liquidity_net
+                };
+                state.liquidity = u128::try_from(state.liquidity as i128 + liquidity_net)
+                    .unwrap_or_else(|_| {
+                        panic!("liquidity of the pool became negative at tick {next_tick}")
+                    });
+                state.tick = if a_to_b { next_tick - 1 } else { *next_tick };
+            } else {
+                state.tick = tick_math::tick_at_sqrt_price(state.sqrt_price);
+            }
+            steps.push(Step {
+                swap: step,
+                liquidity,
+                crossed,
+            });
+        }
+        (steps, state)
+    }
+
+    fn assert_not_paused(&self) {
+        assert!(
+            !self.paused,
+            "pool paused: {} allows only closing positions",
+            self.position_resource
+        );
+    }
+
+    /// Flips fee growth outside an initialized `tick` the price moved across.
+    fn cross(&mut self, tick: i32) {
+        let fee_growth_global = self.fee_growth_global;
+        let info = self
+            .ticks
+            .get_mut(&tick)
+            .unwrap_or_else(|| panic!("tick {tick} is not initialized"));
+        info.fee_growth_outside = fee_growth_global.wrapping_sub(info.fee_growth_outside);
+    }
+
+    /// Adds `liquidity_delta` of a position to its lower or `upper` tick,
+    /// initializing the tick on the first position and dropping it after the last one.
+    fn update_tick(&mut self, tick: i32, liquidity_delta: i128, upper: bool) {
+        let current_tick = self.tick;
+        let fee_growth_global = self.fee_growth_global;
+        let info = self.ticks.entry(tick).or_insert_with(|| Tick {
+            // growth below the price so far is all attributed to the outside of ticks below it
+            fee_growth_outside: if tick <= current_tick {
+                fee_growth_global
+            } else {
+                FeeGrowth::default()
+            },
+            ..Tick::default()
+        });
+        info.liquidity_gross = (info.liquidity_gross as i128 + liquidity_delta) as u128;
+        info.liquidity_net += if upper {
+            -liquidity_delta
+        } else {
+            liquidity_delta
+        };
+        if info.liquidity_gross == 0 {
+            self.ticks.remove(&tick);
+        }
+    }
+
+    fn fee_growth_inside(&self, lower_tick: i32, upper_tick: i32) -> FeeGrowth {
+        let outside = |tick: i32| self.ticks[&tick].fee_growth_outside;
+        let below = if self.tick >= lower_tick {
+            outside(lower_tick)
+        } else {
+            self.fee_growth_global.wrapping_sub(outside(lower_tick))
+        };
+        let above = if self.tick < upper_tick {
+            outside(upper_tick)
+        } else {
+            self.fee_growth_global.wrapping_sub(outside(upper_tick))
+        };
+        self.fee_growth_global
+            .wrapping_sub(below)
+            .wrapping_sub(above)
+    }
+
+    /// Amounts of `(a, b)` backing `liquidity` between `lower` and `upper` square roots of prices
+    /// at the current price.
+    fn amounts(
+        &self,
+        lower: u128,
+        upper: u128,
+        liquidity: u128,
+        round_up: bool,
+    ) -> (Amount, Amount) {
+        let a_amount = if self.sqrt_price < upper {
+            tick_math::amount_a_delta(self.sqrt_price.max(lower), upper, liquidity, round_up)
+        } else {
+            0
+        };
+        let b_amount = if self.sqrt_price > lower {
+            tick_math::amount_b_delta(lower, self.sqrt_price.min(upper), liquidity, round_up)
+        } else {
+            0
+        };
+        (narrow(a_amount), narrow(b_amount))
+    }
+
+    fn fee_vault_mut(&mut self, resource: ResourceAddress) -> &mut Vault {
+        self.fees_collected
+            .get_mut(&resource)
+            .unwrap_or_else(|| panic!("no fee vault for resource {resource}"))
+    }
+
+    /// Returns `(input, output)` vaults of the pool, in that order, for the given swap direction.
+    fn vaults_mut(
+        &mut self,
+        input_address: ResourceAddress,
+        output_address: ResourceAddress,
+    ) -> (&mut Vault, &mut Vault) {
+        if self.is_a_to_b(input_address, output_address) {
+            (&mut self.a, &mut self.b)
+        } else {
+            (&mut self.b, &mut self.a)
+        }
+    }
+
+    /// Direction of a swap, fails if resources do not belong to the pool.
+    fn is_a_to_b(&self, input_address: ResourceAddress, output_address: ResourceAddress) -> bool {
+        let a_address = self.a.resource_address();
+        let b_address = self.b.resource_address();
+        if input_address == a_address && output_address == b_address {
+            true
+        } else if input_address == b_address && output_address == a_address {
+            false
+        } else {
+            panic!(
+                "swap {input_address}->{output_address} does not match pool ({a_address}, {b_address})"
+            )
+        }
+    }
+}
+
+fn wide(amount: Amount) -> u128 {
+    math::wide(amount) as u128
+}
+
+fn narrow(value: u128) -> Amount {
+    math::narrow(i128::try_from(value).unwrap_or_else(|_| panic!("amount {value} overflows")))
+}
@@ -0,0 +1,72 @@
+//   Copyright 2025. The Tari Project
+//
+//   Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//   following conditions are met:
+//
+//   1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//   disclaimer.
+//
+//   2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//   following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//   3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//   products derived from this software without specific prior written permission.
+//
+//   THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//   INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//   DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//   SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//   SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//   WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//   USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Events emitted by liquidity pools and farms, so indexers can reconstruct their history.
+//! Payload is a flat string map, amounts are formatted as decimal integers.
+//! Keep in sync with `tari_exchange::events`, which decodes them.
+
+use tari_template_lib::prelude::*;
+
+pub const POOL_CREATED: &str = "PoolCreated";
+pub const LIQUIDITY_ADDED: &str = "LiquidityAdded";
+pub const LIQUIDITY_REMOVED: &str = "LiquidityRemoved";
+pub const SWAP: &str = "Swap";
+pub const FLASH_LOAN: &str = "FlashLoan";
+pub const POSITION_OPENED: &str = "PositionOpened";
+pub const POSITION_CLOSED: &str = "PositionClosed";
+pub const WEIGHTED_POOL_CREATED: &str = "WeightedPoolCreated";
+pub const WEIGHTED_LIQUIDITY_ADDED: &str = "WeightedLiquidityAdded";
+pub const WEIGHTED_LIQUIDITY_REMOVED: &str = "WeightedLiquidityRemoved";
+pub const WEIGHTED_SWAP: &str = "WeightedSwap";
+pub const FARM_STAKED: &str = "FarmStaked";
+pub const FARM_UNSTAKED: &str = "FarmUnstaked";
+pub const FARM_REWARDS_CLAIMED: &str = "FarmRewardsClaimed";
+pub const ORDER_PLACED: &str = "OrderPlaced";
+pub const ORDER_FILLED: &str = "OrderFilled";
+pub const ORDER_CANCELLED: &str = "OrderCancelled";
+pub const ORDER_CLAIMED: &str = "OrderClaimed";
+
+/// Builder of a pool event payload.
+pub struct PoolEvent {
+    topic: &'static str,
+    payload: Metadata,
+}
+
+impl PoolEvent {
+    pub fn new(topic: &'static str) -> Self {
+        let mut payload = Metadata::new();
+        payload.insert(
+            "caller",
+            CallerContext::transaction_signer_public_key().to_string(),
+        );
+        Self { topic, payload }
+    }
+
+    pub fn with<T: ToString>(mut self, key: &str, value: T) -> Self {
+        self.payload.insert(key, value.to_string());
+        self
+    }
+
+    pub fn emit(self) {
+        emit_event(self.topic, self.payload);
+    }
+}
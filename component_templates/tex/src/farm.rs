@@ -0,0 +1,310 @@
+//   Copyright 2025. The Tari Project
+//
+//   Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//   following conditions are met:
+//
+//   1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//   disclaimer.
+//
+//   2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//   following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//   3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//   products derived from this software without specific prior written permission.
+//
+//   THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//   INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//   DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//   SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//   SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//   WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//   USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Liquidity mining farms rewarding LP tokens staked in them.
+//! Each farm emits `reward_per_epoch` of its reward resource, funded by the admin, shared by
+//! stakers pro rata to their stakes. Stakes are non-fungible receipts carrying the `Stake` data.
+//! Rewards accrue per staked LP token, so a stake is settled without touching the others.
+
+use tari_template_lib::{
+    prelude::*,
+    template_dependencies::serde::{Deserialize, Serialize},
+};
+
+use crate::{events, math};
+
+/// Scale of rewards per staked LP token.
+const REWARD_SCALE: u128 = 1 << 64;
+
+/// Data of a stake receipt.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Stake {
+    pub amount: Amount,
+    /// `Farm::reward_per_token` when rewards of the stake were last paid out.
+    reward_per_token_paid: u128,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Farm {
+    staked: Vault,
+    rewards: Vault,
+    /// Part of `rewards` not allocated to stakers yet.
+    unallocated: Amount,
+    reward_per_epoch: Amount,
+    /// Rewards per staked LP token since the farm was created, scaled by `REWARD_SCALE`.
+    /// Wraps around on overflow, differences stay correct.
+    reward_per_token: u128,
+    last_update_epoch: u64,
+    stake_resource: ResourceAddress,
+    stakes_issued: u64,
+}
+
+impl Farm {
+    /// Farm of the `lp_resource` paying rewards in the `reward_resource`, emitting nothing
+    /// until funded and given an emission rate.
+    pub fn new(lp_resource: ResourceAddress, reward_resource: ResourceAddress) -> Self {
+        let stake_resource = ResourceBuilder::non_fungible()
+            .with_token_symbol("TEXSTAKE")
+            .build();
+        Farm {
+            staked: Vault::new_empty(lp_resource),
+            rewards: Vault::new_empty(reward_resource),
+            unallocated: Amount::zero(),
+            reward_per_epoch: Amount::zero(),
+            reward_per_token: 0,
+            last_update_epoch: Consensus::current_epoch(),
+            stake_resource,
+            stakes_issued: 0,
+        }
+    }
+
+    pub fn stake_resource(&self) -> ResourceAddress {
+        self.stake_resource
+    }
+
+    /// Event of the farm, prefilled with the farm identity and total stake at the moment.
+    fn event(&self, topic: &'static str) -> events::PoolEvent {
+        events::PoolEvent::new(topic)
+            .with("lp_resource", self.staked.resource_address())
+            .with("reward_resource", self.rewards.resource_address())
+            .with("total_staked", self.staked.balance())
+    }
+
+    /// Adds `rewards` to be emitted from now on.
+    pub fn fund(&mut self, rewards: Bucket) {
+        assert!(
+            rewards.resource_address() == self.rewards.resource_address(),
+            "farm rewards are paid in {}, {} provided",
+            self.rewards.resource_address(),
+            rewards.resource_address()
+        );
+        self.update();
+        self.unallocated = self.unallocated + rewards.amount();
+        self.rewards.deposit(rewards);
+    }
+
+    /// Changes the emission from now on, rewards accrued so far are kept.
+    pub fn set_reward_per_epoch(&mut self, reward_per_epoch: Amount) {
+        assert!(
+            !reward_per_epoch.is_negative(),
+            "reward per epoch can't be negative, got {reward_per_epoch}"
+        );
+        self.update();
+        self.reward_per_epoch = reward_per_epoch;
+    }
+
+    /// Locks LP tokens of the `lp_bucket` in the farm, returning a stake receipt.
+    pub fn stake(&mut self, lp_bucket: Bucket) -> Bucket {
+        assert!(
+            lp_bucket.resource_address() == self.staked.resource_address(),
+            "farm stakes {}, {} provided",
+            self.staked.resource_address(),
+            lp_bucket.resource_address()
+        );
+        assert!(
+            lp_bucket.amount().is_positive(),
+            "nothing to stake in the farm of {}",
+            self.staked.resource_address()
+        );
+        self.update();
+        let amount = lp_bucket.amount();
+        self.staked.deposit(lp_bucket);
+        let (stake_id, receipt) = self.mint_stake(amount);
+        self.event(events::FARM_STAKED)
+            .with("stake", stake_id)
+            .with("amount", amount)
+            .with("rewards", Amount::zero())
+            .emit();
+        receipt
+    }
+
+    /// Pays out rewards of the stake `receipt`, returning them together with a new receipt
+    /// of the same stake.
+    pub fn claim(&mut self, receipt: Bucket) -> (Bucket, Bucket) {
+        self.update();
+        let stake = self.burn_stake(receipt);
+        let rewards = self.rewards.withdraw(self.earned(&stake));
+        let (stake_id, receipt) = self.mint_stake(stake.amount);
+        self.event(events::FARM_REWARDS_CLAIMED)
+            .with("stake", stake_id)
+            .with("amount", stake.amount)
+            .with("rewards", rewards.amount())
+            .emit();
+        (receipt, rewards)
+    }
+
+    /// Unlocks LP tokens of the stake `receipt`, returning them together with its rewards.
+    pub fn unstake(&mut self, receipt: Bucket) -> (Bucket, Bucket) {
+        self.update();
+        let stake_id = receipt
+            .get_non_fungible_ids()
+            .pop()
+            .expect("stake receipt is empty");
+        let stake = self.burn_stake(receipt);
+        let rewards = self.rewards.withdraw(self.earned(&stake));
+        let lp_bucket = self.staked.withdraw(stake.amount);
+        self.event(events::FARM_UNSTAKED)
+            .with("stake", stake_id)
+            .with("amount", stake.amount)
+            .with("rewards", rewards.amount())
+            .emit();
+        (lp_bucket, rewards)
+    }
+
+    /// Rewards the stake with the `stake_id` would be paid now.
+    pub fn pending_rewards(&self, stake_id: &NonFungibleId) -> Amount {
+        let stake: Stake = ResourceManager::get(self.stake_resource)
+            .get_non_fungible(stake_id)
+            .get_data();
+        let mut farm = self.clone();
+        farm.update();
+        farm.earned(&stake)
+    }
+
+    /// Allocates rewards emitted since the last update to current stakers.
+    /// Nothing is emitted while nothing is staked, or beyond the funded rewards.
+    fn update(&mut self) {
+        let epoch = Consensus::current_epoch();
+        let total_staked = wide(self.staked.balance());
+        if epoch > self.last_update_epoch && total_staked > 0 {
+            let emission = emission(
+                epoch - self.last_update_epoch,
+                self.reward_per_epoch,
+                self.unallocated,
+            );
+            self.reward_per_token = self
+                .reward_per_token
+                .wrapping_add(reward_per_token(emission, total_staked));
+            self.unallocated = self.unallocated - narrow(emission);
+        }
+        self.last_update_epoch = epoch;
+    }
+
+    /// Rewards of the `stake` since they were last paid, rounded down,
+    /// so the farm never pays more than it allocated.
+    fn earned(&self, stake: &Stake) -> Amount {
+        rewards(
+            stake.amount,
+            self.reward_per_token
+                .wrapping_sub(stake.reward_per_token_paid),
+        )
+    }
+
+    fn mint_stake(&mut self, amount: Amount) -> (NonFungibleId, Bucket) {
+        self.stakes_issued += 1;
+        let stake_id = NonFungibleId::from_u64(self.stakes_issued);
+        let receipt = ResourceManager::get(self.stake_resource).mint_non_fungible(
+            stake_id.clone(),
+            &Stake {
+                amount,
+                reward_per_token_paid: self.reward_per_token,
+            },
+            &(),
+        );
+        (stake_id, receipt)
+    }
+
+    fn burn_stake(&self, receipt: Bucket) -> Stake {
+        assert!(
+            receipt.resource_address() == self.stake_resource && receipt.amount() == Amount::new(1),
+            "exactly one stake receipt of {} expected",
+            self.stake_resource
+        );
+        let stake = receipt
+            .get_non_fungibles()
+            .first()
+            .expect("stake receipt is empty")
+            .get_data();
+        receipt.burn();
+        stake
+    }
+}
+
+/// Rewards emitted over `elapsed` epochs, capped by the `unallocated` ones.
+fn emission(elapsed: u64, reward_per_epoch: Amount, unallocated: Amount) -> u128 {
+    (u128::from(elapsed) * wide(reward_per_epoch)).min(wide(unallocated))
+}
+
+/// Increase of the reward per staked LP token when the `emission` is shared by the
+/// `total_staked`, rounded down.
+fn reward_per_token(emission: u128, total_staked: u128) -> u128 {
+    math::mul_div(emission, REWARD_SCALE, total_staked)
+}
+
+/// Rewards of a stake of the `amount` while the reward per token increased by
+/// `reward_per_token`, rounded down.
+fn rewards(amount: Amount, reward_per_token: u128) -> Amount {
+    narrow(math::mul_div(wide(amount), reward_per_token, REWARD_SCALE))
+}
+
+fn wide(amount: Amount) -> u128 {
+    math::wide(amount) as u128
+}
+
+fn narrow(value: u128) -> Amount {
+    math::narrow(i128::try_from(value).unwrap_or_else(|_| panic!("amount {value} overflows")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn emission_is_capped_by_unallocated_rewards() {
+        assert_eq!(emission(3, Amount::new(50), Amount::new(1_000)), 150);
+        assert_eq!(emission(30, Amount::new(50), Amount::new(1_000)), 1_000);
+        assert_eq!(emission(30, Amount::zero(), Amount::new(1_000)), 0);
+    }
+
+    #[test]
+    fn rewards_accrue_pro_rata_to_stakes() {
+        let per_token = reward_per_token(100, 4);
+        assert_eq!(rewards(Amount::new(1), per_token), Amount::new(25));
+        assert_eq!(rewards(Amount::new(3), per_token), Amount::new(75));
+    }
+
+    #[test]
+    fn rewards_never_exceed_the_emission() {
+        for (emission, stakes) in [(100, [1, 1, 1]), (1, [1, 1, 1]), (1_000_003, [7, 11, 13])] {
+            let total_staked = stakes.iter().sum::<i64>();
+            let per_token = reward_per_token(emission, total_staked as u128);
+            let paid = stakes
+                .iter()
+                .map(|stake| rewards(Amount::new(*stake), per_token).value())
+                .sum::<i64>();
+            assert!(paid as u128 <= emission);
+            // every stake loses less than one unit to rounding
+            assert!(paid as u128 + stakes.len() as u128 > emission);
+        }
+    }
+
+    #[test]
+    fn rewards_survive_reward_per_token_wraparound() {
+        let paid = u128::MAX - REWARD_SCALE;
+        let per_token = paid.wrapping_add(reward_per_token(60, 3));
+        assert!(per_token < paid);
+        assert_eq!(
+            rewards(Amount::new(2), per_token.wrapping_sub(paid)),
+            Amount::new(40)
+        );
+    }
+}
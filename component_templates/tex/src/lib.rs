@@ -20,94 +20,979 @@
 //   WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
 //   USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
 
-use bounded_integer::BoundedU8;
-use tari_template_abi::rust::collections::HashMap;
+use bounded_integer::BoundedU32;
+use tari_template_abi::rust::collections::{BTreeSet, HashMap};
 use tari_template_lib::{
     prelude::*,
     template_dependencies::serde::{Deserialize, Serialize},
 };
 
+mod concentrated;
+mod events;
+mod farm;
+mod lp_token;
+mod math;
+mod oracle;
+mod orders;
+mod stable;
+mod tick_math;
+mod weighted;
+mod weighted_math;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct LiquidityPool {
     a: Vault,
     b: Vault,
     lp_resource: ResourceAddress,
-    fees_collected: HashMap<String, f64>,
+    /// Fee tier of the pool, fixed when the pool is created. Charged on swap inputs,
+    /// unless `dynamic_fee` is set.
+    fee: Fee,
+    /// Pricing curve, fixed when the pool is created.
+    curve: Curve,
+    /// Fees charged on swaps, kept apart from the reserves, one vault per resource of the pair.
+    fees_collected: HashMap<ResourceAddress, Vault>,
+    /// `MINIMUM_LIQUIDITY` LP tokens minted on the first deposit and never withdrawn,
+    /// so the pool can't be fully drained and LP token price can't be inflated.
+    locked_liquidity: Vault,
+    /// Delisted pools accept neither swaps nor new liquidity, but providers can still withdraw.
+    delisted: bool,
+    /// Whether the `Treasury` takes its share of swap fees charged by this pool.
+    protocol_fee_on: bool,
+    /// Paused pools work like delisted ones until the admin unpauses them.
+    paused: bool,
+    /// Price accumulators, updated before every change of reserves.
+    oracle: oracle::PriceOracle,
+    /// Set while a flash loan taken from the pool is not repaid, the pool is locked until then.
+    flash_loan_outstanding: bool,
+    /// Limit orders resting on the pool.
+    order_book: orders::OrderBook,
+    /// Set when swaps are charged by recent volatility, see `LiquidityPool::swap_fee`.
+    dynamic_fee: Option<DynamicFee>,
+}
+
+/// Bounds of the fee of a pool charging swaps by recent volatility, see `Tex::set_dynamic_fee`.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+struct DynamicFee {
+    min: Fee,
+    max: Fee,
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LiquidityPools {
-    inner: HashMap<Pair, LiquidityPool>,
+    /// Pools of each pair, at most one per fee tier.
+    inner: HashMap<Pair, Vec<LiquidityPool>>,
+    /// Concentrated liquidity pools of each pair, at most one per fee tier,
+    /// independent of constant product pools with the same fee.
+    concentrated: HashMap<Pair, Vec<concentrated::ConcentratedPool>>,
+    /// Weighted pools of each set of assets, at most one per fee tier.
+    weighted: HashMap<Assets, Vec<weighted::WeightedPool>>,
+}
+
+/// Pricing curve of a pool.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+enum Curve {
+    /// `a * b = k`, suits any pair.
+    ConstantProduct,
+    /// StableSwap curve, suits pairs of pegged resources, see `stable`.
+    Stable(stable::Amplification),
+}
+
+/// Protocol's cut of swap fees, only the admin can withdraw it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Treasury {
+    /// Share of swap fees diverted from pools with the protocol fee switched on,
+    /// in the same units as `Fee`.
+    share: Fee,
+    vaults: HashMap<ResourceAddress, Vault>,
+}
+
+/// Data of a flash loan debt receipt, see `Tex::flash_borrow`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct FlashLoan {
+    pair: Pair,
+    fee: Fee,
+    resource: ResourceAddress,
+    amount: Amount,
+    /// Fee due on top of the `amount`, charged at the pool fee.
+    fee_amount: Amount,
+}
+
+/// Data of a limit order receipt, see `Tex::place_limit_order`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LimitOrder {
+    pair: Pair,
+    fee: Fee,
+    id: u64,
+}
+
+/// Fee in hundredths of a basis point, e.g. `3000` is 0.3%, see `math::FEE_DENOMINATOR`.
+type Fee = BoundedU32<0, 1_000_000>;
+
+const MINIMUM_LIQUIDITY: i128 = 100;
+
+/// Methods of `Tex` callable only with a proof of the admin badge.
+const ADMIN_METHODS: &[&str] = &[
+    "add_fee_tier",
+    "remove_fee_tier",
+    "delist_pool",
+    "set_protocol_fee_share",
+    "set_protocol_fee",
+    "set_weighted_protocol_fee",
+    "withdraw_treasury",
+    "set_paused",
+    "set_pool_paused",
+    "set_weighted_pool_paused",
+    "ramp_amplification",
+    "set_token_symbol",
+    "fund_farm",
+    "set_farm_emission",
+    "set_access_pass",
+    "set_permissionless",
+    "set_resource_allowed",
+    "set_resource_denied",
+    "set_dynamic_fee",
+    "clear_dynamic_fee",
+];
+
+/// Methods of `Tex` trading or providing liquidity, callable only with a proof of the access pass
+/// while one is set, see `Tex::set_access_pass`. Removing liquidity in all resources of a pool or
+/// claiming is never gated, so funds can't get stuck in Tex, while exits in a single resource
+/// trade the rest of it and are gated like swaps.
+const GATED_METHODS: &[&str] = &[
+    "swap",
+    "swap_for_exact",
+    "swap_with_min_out",
+    "swap_route",
+    "add_liquidity",
+    "add_liquidity_with_min_lp",
+    "create_stable_pool",
+    "zap_in",
+    "zap_out",
+    "flash_borrow",
+    "place_limit_order",
+    "add_concentrated_liquidity",
+    "swap_concentrated",
+    "create_weighted_pool",
+    "join_weighted_pool",
+    "join_weighted_pool_single",
+    "exit_weighted_pool_single",
+    "swap_weighted",
+];
+
+/// Pair of distinct resources traded in a single pool.
+/// Addresses are kept sorted, so `Pair::new(a, b)` and `Pair::new(b, a)` are the same pair.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(
+    from = "(ResourceAddress, ResourceAddress)",
+    into = "(ResourceAddress, ResourceAddress)"
+)]
+pub struct Pair(ResourceAddress, ResourceAddress);
+
+impl Pair {
+    pub fn new(a: ResourceAddress, b: ResourceAddress) -> Self {
+        assert!(
+            a != b,
+            "pair requires two different resources, got {a} twice"
+        );
+        if a < b {
+            Pair(a, b)
+        } else {
+            Pair(b, a)
+        }
+    }
+
+    pub fn a(&self) -> ResourceAddress {
+        self.0
+    }
+
+    pub fn b(&self) -> ResourceAddress {
+        self.1
+    }
+
+    pub fn contains(&self, resource: ResourceAddress) -> bool {
+        self.0 == resource || self.1 == resource
+    }
+}
+
+/// Pairs coming from transaction arguments are sorted as well, so callers may pass resources in any order.
+impl From<(ResourceAddress, ResourceAddress)> for Pair {
+    fn from((a, b): (ResourceAddress, ResourceAddress)) -> Self {
+        Pair::new(a, b)
+    }
+}
+
+impl From<Pair> for (ResourceAddress, ResourceAddress) {
+    fn from(Pair(a, b): Pair) -> Self {
+        (a, b)
+    }
+}
+
+/// Set of 2 to `weighted::MAX_ASSETS` distinct resources traded in a single weighted pool.
+/// Addresses are kept sorted like in a `Pair`, so callers may pass resources in any order.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "Vec<ResourceAddress>", into = "Vec<ResourceAddress>")]
+pub struct Assets(Vec<ResourceAddress>);
+
+impl Assets {
+    pub fn new(mut resources: Vec<ResourceAddress>) -> Self {
+        let count = resources.len();
+        assert!(
+            (2..=weighted::MAX_ASSETS).contains(&count),
+            "weighted pools hold 2 to {} assets, got {count}",
+            weighted::MAX_ASSETS
+        );
+        resources.sort();
+        resources.dedup();
+        assert!(
+            resources.len() == count,
+            "weighted pool assets must be distinct resources"
+        );
+        Assets(resources)
+    }
+
+    pub fn resources(&self) -> &[ResourceAddress] {
+        &self.0
+    }
+}
+
+/// Assets coming from transaction arguments are sorted as well.
+impl From<Vec<ResourceAddress>> for Assets {
+    fn from(resources: Vec<ResourceAddress>) -> Self {
+        Assets::new(resources)
+    }
+}
+
+impl From<Assets> for Vec<ResourceAddress> {
+    fn from(Assets(resources): Assets) -> Self {
+        resources
+    }
+}
+
+impl Curve {
+    /// Amplification of the stable curve at the moment, `None` for constant product.
+    fn amplification(&self) -> Option<u64> {
+        match self {
+            Curve::ConstantProduct => None,
+            Curve::Stable(amplification) => Some(amplification.at(Consensus::current_epoch())),
+        }
+    }
+
+    fn amount_out(
+        &self,
+        input_reserve: Amount,
+        output_reserve: Amount,
+        net_input: Amount,
+    ) -> Amount {
+        match self.amplification() {
+            None => math::amount_out(input_reserve, output_reserve, net_input),
+            Some(amplification) => {
+                stable::amount_out(input_reserve, output_reserve, net_input, amplification)
+            }
+        }
+    }
+
+    fn amount_in(&self, input_reserve: Amount, output_reserve: Amount, output: Amount) -> Amount {
+        match self.amplification() {
+            None => math::amount_in(input_reserve, output_reserve, output),
+            Some(amplification) => {
+                stable::amount_in(input_reserve, output_reserve, output, amplification)
+            }
+        }
+    }
+
+    fn price(&self, base_reserve: Amount, quote_reserve: Amount) -> u64 {
+        match self.amplification() {
+            None => math::price(base_reserve, quote_reserve),
+            Some(amplification) => stable::price(base_reserve, quote_reserve, amplification),
+        }
+    }
+}
+
+impl DynamicFee {
+    /// The `base` fee raised by `volatility` in the same units, kept within bounds.
+    fn fee(&self, base: Fee, volatility: u64) -> Fee {
+        let fee = u64::from(base.get())
+            .saturating_add(volatility)
+            .clamp(u64::from(self.min.get()), u64::from(self.max.get()));
+        Fee::new(fee as u32).expect("fee is within bounds")
+    }
+}
+
+impl Treasury {
+    fn new(share: Fee) -> Self {
+        Treasury {
+            share,
+            vaults: HashMap::new(),
+        }
+    }
+
+    /// Takes the protocol share out of swap `fees`, rounded down in favour of liquidity providers.
+    /// Returns the amount taken.
+    fn collect(&mut self, fees: &mut Bucket) -> Amount {
+        self.deposit(fees.take(math::protocol_fee_amount(fees.amount(), self.share)))
+    }
+
+    /// Keeps the `protocol_fee` a pool worked out itself, see `ConcentratedPool::swap`.
+    /// Returns its amount.
+    fn deposit(&mut self, protocol_fee: Bucket) -> Amount {
+        let amount = protocol_fee.amount();
+        let resource = protocol_fee.resource_address();
+        self.vaults
+            .entry(resource)
+            .or_insert_with(|| Vault::new_empty(resource))
+            .deposit(protocol_fee);
+        amount
+    }
+
+    fn withdraw(&mut self, resource: ResourceAddress, amount: Amount) -> Bucket {
+        self.vaults
+            .get_mut(&resource)
+            .unwrap_or_else(|| panic!("treasury holds no {resource}"))
+            .withdraw(amount)
+    }
+
+    fn balances(&self) -> HashMap<ResourceAddress, Amount> {
+        self.vaults
+            .iter()
+            .map(|(resource, vault)| (*resource, vault.balance()))
+            .collect()
+    }
+}
+
+/// Resources new pools may list, curated by the admin.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ListingPolicy {
+    /// Whether any resource not `denied` may be listed, otherwise only `allowed` ones may.
+    permissionless: bool,
+    allowed: BTreeSet<ResourceAddress>,
+    /// Never listed, even in permissionless mode.
+    denied: BTreeSet<ResourceAddress>,
+}
+
+impl ListingPolicy {
+    fn permissionless() -> Self {
+        ListingPolicy {
+            permissionless: true,
+            allowed: BTreeSet::new(),
+            denied: BTreeSet::new(),
+        }
+    }
+
+    /// Fails unless a new pool may list all the `resources`.
+    fn assert_listable(&self, resources: &[ResourceAddress]) {
+        for resource in resources {
+            assert!(
+                !self.denied.contains(resource),
+                "resource {resource} is denied listing"
+            );
+            assert!(
+                self.permissionless || self.allowed.contains(resource),
+                "resource {resource} is not allowed listing"
+            );
+        }
+    }
 }
 
-type Fee = BoundedU8<0, 100>;
-//TODO:
-// [] check a != b
-// make it side independent (no matter if it's a<>b or b<>a)
-type Pair = (ResourceAddress, ResourceAddress);
+/// State of the pool an operation must not make worse, see `LiquidityPool::assert_invariant`.
+struct PoolSnapshot {
+    a: i128,
+    b: i128,
+    a_fees: i128,
+    b_fees: i128,
+    lp_supply: i128,
+}
+
+impl PoolSnapshot {
+    /// Amounts backing the LP supply: reserves together with fees collected for providers.
+    fn backing(&self) -> (i128, i128) {
+        (self.a + self.a_fees, self.b + self.b_fees)
+    }
+}
 
 impl LiquidityPool {
-    fn new((a_address, b_address): Pair) -> Self {
-        //TODO: [STAGE2] customize LP to hold information about range
-        //TODO: token should depend on Pool's identity
-        let lp_resource = ResourceBuilder::fungible().with_token_symbol("LP").build();
-        LiquidityPool {
-            a: Vault::new_empty(a_address),
-            b: Vault::new_empty(b_address),
+    fn new(pair: Pair, fee: Fee, curve: Curve, lp_token: lp_token::LpToken) -> Self {
+        let lp_resource = lp_token.fungible();
+        let fees_collected = [pair.a(), pair.b()]
+            .into_iter()
+            .map(|resource| (resource, Vault::new_empty(resource)))
+            .collect();
+        let pool = LiquidityPool {
+            a: Vault::new_empty(pair.a()),
+            b: Vault::new_empty(pair.b()),
             lp_resource,
-            fees_collected: HashMap::new(),
+            fee,
+            curve,
+            fees_collected,
+            locked_liquidity: Vault::new_empty(lp_resource),
+            delisted: false,
+            protocol_fee_on: false,
+            paused: false,
+            oracle: oracle::PriceOracle::default(),
+            flash_loan_outstanding: false,
+            order_book: orders::OrderBook::new(pair),
+            dynamic_fee: None,
+        };
+        pool.event(events::POOL_CREATED).emit();
+        pool
+    }
+
+    /// Event of the pool, prefilled with the pool identity and reserves at the moment.
+    fn event(&self, topic: &'static str) -> events::PoolEvent {
+        events::PoolEvent::new(topic)
+            .with("lp_resource", self.lp_resource)
+            .with("fee_tier", self.fee)
+            .with("resource_a", self.a.resource_address())
+            .with("resource_b", self.b.resource_address())
+            .with("reserve_a", self.a.balance())
+            .with("reserve_b", self.b.balance())
+    }
+
+    fn emit_swap(
+        &self,
+        input_resource: ResourceAddress,
+        input_amount: Amount,
+        output_resource: ResourceAddress,
+        output_amount: Amount,
+        fee_amount: Amount,
+        protocol_fee_amount: Amount,
+    ) {
+        self.event(events::SWAP)
+            .with("input_resource", input_resource)
+            .with("input_amount", input_amount)
+            .with("output_resource", output_resource)
+            .with("output_amount", output_amount)
+            .with("fee", fee_amount)
+            .with("protocol_fee", protocol_fee_amount)
+            // the oracle is updated before any swap of the epoch, so the rate is the one applied,
+            // regardless of reserves changed by the swap
+            .with("fee_rate", self.swap_fee())
+            .emit();
+    }
+
+    /// Fee a swap pays now: the fee tier, or with `dynamic_fee` set, the fee tier raised by
+    /// volatility of recent prices, within bounds.
+    fn swap_fee(&self) -> Fee {
+        match &self.dynamic_fee {
+            None => self.fee,
+            Some(dynamic_fee) => dynamic_fee.fee(
+                self.fee,
+                self.oracle
+                    .volatility(Consensus::current_epoch(), self.prices()),
+            ),
         }
     }
 
-    fn swap(&mut self, input: Bucket, output_address: ResourceAddress, fee: Fee) -> Bucket {
+    /// Keeps swap `fees` for liquidity providers, less the treasury share if the protocol fee is on.
+    /// Returns the amount the treasury took.
+    fn collect_fees(&mut self, mut fees: Bucket, treasury: &mut Treasury) -> Amount {
+        let protocol_fee_amount = if self.protocol_fee_on {
+            treasury.collect(&mut fees)
+        } else {
+            Amount::zero()
+        };
+        self.fee_vault_mut(fees.resource_address()).deposit(fees);
+        protocol_fee_amount
+    }
+
+    /// Whether the pool accepts swaps right now, i.e. it's active and has liquidity.
+    fn is_tradable(&self) -> bool {
+        !self.delisted
+            && !self.paused
+            && !self.flash_loan_outstanding
+            && !self.a.balance().is_zero()
+            && !self.b.balance().is_zero()
+    }
+
+    /// Fails unless the pool accepts swaps and new liquidity.
+    fn assert_active(&self) {
+        assert!(
+            !self.delisted,
+            "pool delisted: {} allows only liquidity removal",
+            self.lp_resource
+        );
+        assert!(
+            !self.paused,
+            "pool paused: {} allows only liquidity removal",
+            self.lp_resource
+        );
+        self.assert_unlocked();
+    }
+
+    /// Fails while a flash loan taken from the pool is not repaid.
+    fn assert_unlocked(&self) {
+        assert!(
+            !self.flash_loan_outstanding,
+            "pool locked: {} has a flash loan outstanding",
+            self.lp_resource
+        );
+    }
+
+    /// Lends `amount` of `resource` out of the reserves and locks the pool until `flash_repay`.
+    /// Returns the loan together with the fee due on it.
+    fn flash_borrow(&mut self, resource: ResourceAddress, amount: Amount) -> (Bucket, Amount) {
+        self.assert_active();
+        assert!(amount.is_positive(), "flash loan amount must be positive");
+        self.update_oracle();
+        let loan = self.reserve_mut(resource).withdraw(amount);
+        self.flash_loan_outstanding = true;
+        (loan, math::fee_amount(amount, self.fee))
+    }
+
+    /// Returns the `loan` principal to the reserves and its fee to liquidity providers,
+    /// unlocking the pool. Returns the part of the `repayment` above what is due.
+    fn flash_repay(
+        &mut self,
+        loan: &FlashLoan,
+        mut repayment: Bucket,
+        treasury: &mut Treasury,
+    ) -> Bucket {
+        assert!(
+            repayment.resource_address() == loan.resource,
+            "flash loan of {} can't be repaid with {}",
+            loan.resource,
+            repayment.resource_address()
+        );
+        let amount_due = loan.amount + loan.fee_amount;
+        assert!(
+            repayment.amount() >= amount_due,
+            "flash loan repayment of {} is not enough, {amount_due} due",
+            repayment.amount()
+        );
+        self.update_oracle();
+        let change = repayment.take(repayment.amount() - amount_due);
+        let fee_bucket = repayment.take(loan.fee_amount);
+        self.reserve_mut(loan.resource).deposit(repayment);
+        let protocol_fee_amount = self.collect_fees(fee_bucket, treasury);
+        self.flash_loan_outstanding = false;
+        self.event(events::FLASH_LOAN)
+            .with("resource", loan.resource)
+            .with("amount", loan.amount)
+            .with("fee", loan.fee_amount)
+            .with("protocol_fee", protocol_fee_amount)
+            .emit();
+        change
+    }
+
+    /// Swaps the `input`, then fills limit orders the swap moved the price through.
+    fn swap(
+        &mut self,
+        input: Bucket,
+        output_address: ResourceAddress,
+        treasury: &mut Treasury,
+    ) -> Bucket {
+        let output = self.execute_swap(input, output_address, treasury);
+        self.fill_orders(treasury);
+        output
+    }
+
+    fn execute_swap(
+        &mut self,
+        mut input: Bucket,
+        output_address: ResourceAddress,
+        treasury: &mut Treasury,
+    ) -> Bucket {
+        self.assert_active();
+        self.update_oracle();
+        let snapshot = self.snapshot();
+        let input_resource = input.resource_address();
+        let (output_amount, fee_amount) =
+            self.quote_amount_out(input_resource, input.amount(), output_address);
+        let input_amount = input.amount();
+        assert!(
+            output_amount.is_positive(),
+            "swap of {input_amount} {input_resource} is too small to return anything"
+        );
+        let fee_bucket = input.take(fee_amount);
+        let (input_vault, output_vault) = self.vaults_mut(input_resource, output_address);
+        input_vault.deposit(input);
+        let output = output_vault.withdraw(output_amount);
+        let protocol_fee_amount = self.collect_fees(fee_bucket, treasury);
+        self.assert_invariant(snapshot);
+        self.emit_swap(
+            input_resource,
+            input_amount,
+            output_address,
+            output_amount,
+            fee_amount,
+            protocol_fee_amount,
+        );
+        output
+    }
+
+    /// Swaps just enough of the `input` to receive exactly `output_amount` of `output_address`.
+    /// Returns the output together with the unspent part of the `input`.
+    fn swap_for_exact(
+        &mut self,
+        mut input: Bucket,
+        output_address: ResourceAddress,
+        output_amount: Amount,
+        treasury: &mut Treasury,
+    ) -> (Bucket, Bucket) {
+        self.assert_active();
+        self.update_oracle();
+        let snapshot = self.snapshot();
         let input_resource = input.resource_address();
-        //TODO we are assuming that input a for now, make it both ways
-        let input_pool_balance = self.a.balance();
-        let output_pool_balance = self.b.balance();
+        let (input_amount, fee_amount) =
+            self.quote_amount_in(input_resource, output_address, output_amount);
         assert!(
-            !input_pool_balance.is_zero(),
+            input.amount() >= input_amount,
+            "input of {} is not enough, {} required",
+            input.amount(),
+            input_amount
+        );
+        let change = input.take(input.amount() - input_amount);
+        let fee_bucket = input.take(fee_amount);
+        let (input_vault, output_vault) = self.vaults_mut(input_resource, output_address);
+        input_vault.deposit(input);
+        let output = output_vault.withdraw(output_amount);
+        let protocol_fee_amount = self.collect_fees(fee_bucket, treasury);
+        self.assert_invariant(snapshot);
+        self.emit_swap(
+            input_resource,
+            input_amount,
+            output_address,
+            output_amount,
+            fee_amount,
+            protocol_fee_amount,
+        );
+        self.fill_orders(treasury);
+        (output, change)
+    }
+
+    /// Places a limit order with the `id` selling the `input` for at least `limit_price`,
+    /// then fills it right away if the pool already pays enough, see `orders`.
+    fn place_order(&mut self, id: u64, input: Bucket, limit_price: u64, treasury: &mut Treasury) {
+        self.assert_active();
+        self.order_book.place(id, input, limit_price);
+        self.emit_order(events::ORDER_PLACED, id, self.order_book.order(id));
+        self.fill_orders(treasury);
+    }
+
+    /// Removes the open order with the `id`, returning its input.
+    fn cancel_order(&mut self, id: u64) -> Bucket {
+        let (order, input) = self.order_book.cancel(id);
+        self.emit_order(events::ORDER_CANCELLED, id, &order);
+        input
+    }
+
+    /// Removes the filled order with the `id`, returning its output.
+    fn claim_order(&mut self, id: u64) -> Bucket {
+        let output_resource = self.counter_resource(self.order_book.order(id).input_resource);
+        let (order, output) = self.order_book.claim(id, output_resource);
+        self.emit_order(events::ORDER_CLAIMED, id, &order);
+        output
+    }
+
+    /// Fills open orders the pool pays at least their limit price for, lowest limit price first
+    /// on each side of the book, at most `orders::MAX_FILLS` at a time. Filling stops once
+    /// neither side's best order can be filled, so at most two orders are checked per fill.
+    /// Returns the number of filled orders.
+    fn fill_orders(&mut self, treasury: &mut Treasury) -> usize {
+        let mut filled = 0;
+        while filled < orders::MAX_FILLS && self.is_tradable() {
+            let Some(id) = [self.a.resource_address(), self.b.resource_address()]
+                .into_iter()
+                .filter_map(|input_resource| self.order_book.best_open_order(input_resource))
+                .find(|id| self.can_fill(*id))
+            else {
+                break;
+            };
+            let output_resource = self.counter_resource(self.order_book.order(id).input_resource);
+            let input = self.order_book.take_input(id);
+            let output = self.execute_swap(input, output_resource, treasury);
+            self.order_book.settle(id, output);
+            self.emit_order(events::ORDER_FILLED, id, self.order_book.order(id));
+            filled += 1;
+        }
+        filled
+    }
+
+    /// Whether the pool pays at least the limit price of the open order with the `id` right now.
+    fn can_fill(&self, id: u64) -> bool {
+        let order = self.order_book.order(id);
+        let Some((a_price, b_price)) = self.prices() else {
+            return false;
+        };
+        let price = if order.input_resource == self.a.resource_address() {
+            a_price
+        } else {
+            b_price
+        };
+        // the marginal price bounds what the pool pays, so most orders are rejected unquoted
+        if price < order.limit_price {
+            return false;
+        }
+        let output_resource = self.counter_resource(order.input_resource);
+        let (output_amount, _) =
+            self.quote_amount_out(order.input_resource, order.amount, output_resource);
+        // the least output is positive, so the swap never fails for returning nothing
+        output_amount >= order.min_output()
+    }
+
+    fn emit_order(&self, topic: &'static str, id: u64, order: &orders::Order) {
+        self.event(topic)
+            .with("order", id)
+            .with("input_resource", order.input_resource)
+            .with("amount", order.amount)
+            .with("limit_price", order.limit_price)
+            .with("output_amount", order.filled.unwrap_or_default())
+            .emit();
+    }
+
+    /// The other resource of the pair.
+    fn counter_resource(&self, resource: ResourceAddress) -> ResourceAddress {
+        if resource == self.a.resource_address() {
+            self.b.resource_address()
+        } else {
+            self.a.resource_address()
+        }
+    }
+
+    /// Output of a `swap` of `input_amount` together with the fee charged on it.
+    fn quote_amount_out(
+        &self,
+        input_resource: ResourceAddress,
+        input_amount: Amount,
+        output_resource: ResourceAddress,
+    ) -> (Amount, Amount) {
+        let (input_reserve, output_reserve) = self.reserves(input_resource, output_resource);
+        let fee_amount = math::fee_amount(input_amount, self.swap_fee());
+        let output_amount =
+            self.curve
+                .amount_out(input_reserve, output_reserve, input_amount - fee_amount);
+        (output_amount, fee_amount)
+    }
+
+    /// Input a `swap_for_exact` of `output_amount` requires together with the fee charged on it.
+    fn quote_amount_in(
+        &self,
+        input_resource: ResourceAddress,
+        output_resource: ResourceAddress,
+        output_amount: Amount,
+    ) -> (Amount, Amount) {
+        let (input_reserve, output_reserve) = self.reserves(input_resource, output_resource);
+        let net_input_amount = self
+            .curve
+            .amount_in(input_reserve, output_reserve, output_amount);
+        let input_amount = math::gross_amount(net_input_amount, self.swap_fee());
+        (input_amount, input_amount - net_input_amount)
+    }
+
+    /// Returns `(input, output)` reserves of the pool, in that order, failing if any is empty.
+    fn reserves(
+        &self,
+        input_resource: ResourceAddress,
+        output_resource: ResourceAddress,
+    ) -> (Amount, Amount) {
+        let (input_reserve, output_reserve) = if self.is_a_to_b(input_resource, output_resource) {
+            (self.a.balance(), self.b.balance())
+        } else {
+            (self.b.balance(), self.a.balance())
+        };
+        assert!(
+            !input_reserve.is_zero(),
             "The pool for resource '{}' is empty",
             input_resource
         );
         assert!(
-            !output_pool_balance.is_zero(),
+            !output_reserve.is_zero(),
             "The pool for resource '{}' is empty",
-            output_address
-        );
-        let input_bucket_balance = input.amount().value();
-        //TODO: move to some method of FEE
-        use num_traits::ToPrimitive;
-        let effective_input_balance = input_bucket_balance
-            - (input_bucket_balance * fee.to_i64().expect("fee is not a valid i64")) / 100_i64;
-        let effective_input_balance = Amount::new(effective_input_balance);
-        //TODO: do not use fee automatically, put it into `fees_collected`
-        let k = input_pool_balance * output_pool_balance;
-        let new_input_pool_balance = input_pool_balance + effective_input_balance;
-        let new_output_pool_balance = k / new_input_pool_balance;
-        let output_bucket_amount = output_pool_balance - new_output_pool_balance;
-        self.a.deposit(input);
-        self.b.withdraw(output_bucket_amount)
+            output_resource
+        );
+        (input_reserve, output_reserve)
+    }
+
+    /// Value of `lp_amount` LP tokens per resource: the share of reserves together with
+    /// collected fees, which is what `remove` pays out for them.
+    fn lp_share_value(&self, lp_amount: Amount) -> HashMap<ResourceAddress, Amount> {
+        let snapshot = self.snapshot();
+        let total_supply = math::narrow(snapshot.lp_supply);
+        let (a_backing, b_backing) = snapshot.backing();
+        HashMap::from([
+            (
+                self.a.resource_address(),
+                math::share(math::narrow(a_backing), lp_amount, total_supply),
+            ),
+            (
+                self.b.resource_address(),
+                math::share(math::narrow(b_backing), lp_amount, total_supply),
+            ),
+        ])
+    }
+
+    /// Price of each resource of the pool expressed in the other one, scaled by `PRICE_SCALE`.
+    fn spot_price(&self) -> HashMap<ResourceAddress, u64> {
+        let a_reserve = self.a.balance();
+        let b_reserve = self.b.balance();
+        assert!(
+            !a_reserve.is_zero() && !b_reserve.is_zero(),
+            "The pool is empty, price is not defined"
+        );
+        HashMap::from([
+            (
+                self.a.resource_address(),
+                self.curve.price(a_reserve, b_reserve),
+            ),
+            (
+                self.b.resource_address(),
+                self.curve.price(b_reserve, a_reserve),
+            ),
+        ])
+    }
+
+    /// Spot prices of `(a, b)`, each in the other resource, `None` while the pool is empty.
+    fn prices(&self) -> Option<(u64, u64)> {
+        let a_reserve = self.a.balance();
+        let b_reserve = self.b.balance();
+        if a_reserve.is_zero() || b_reserve.is_zero() {
+            return None;
+        }
+        Some((
+            self.curve.price(a_reserve, b_reserve),
+            self.curve.price(b_reserve, a_reserve),
+        ))
+    }
+
+    fn update_oracle(&mut self) {
+        let prices = self.prices();
+        self.oracle.update(Consensus::current_epoch(), prices);
+    }
+
+    /// Time-weighted average price of each resource of the pool over the last `window` epochs.
+    fn observe(&self, window: u64) -> HashMap<ResourceAddress, u64> {
+        let (a_price, b_price) =
+            self.oracle
+                .observe(Consensus::current_epoch(), window, self.prices());
+        HashMap::from([
+            (self.a.resource_address(), a_price),
+            (self.b.resource_address(), b_price),
+        ])
+    }
+
+    fn reserves_by_resource(&self) -> HashMap<ResourceAddress, Amount> {
+        HashMap::from([
+            (self.a.resource_address(), self.a.balance()),
+            (self.b.resource_address(), self.b.balance()),
+        ])
+    }
+
+    fn reserve_mut(&mut self, resource: ResourceAddress) -> &mut Vault {
+        if resource == self.a.resource_address() {
+            &mut self.a
+        } else if resource == self.b.resource_address() {
+            &mut self.b
+        } else {
+            panic!(
+                "resource {resource} does not belong to pool ({}, {})",
+                self.a.resource_address(),
+                self.b.resource_address()
+            )
+        }
+    }
+
+    fn fee_vault_mut(&mut self, resource: ResourceAddress) -> &mut Vault {
+        self.fees_collected
+            .get_mut(&resource)
+            .unwrap_or_else(|| panic!("no fee vault for resource {resource}"))
+    }
+
+    fn fees_collected(&self) -> HashMap<ResourceAddress, Amount> {
+        self.fees_collected
+            .iter()
+            .map(|(resource, vault)| (*resource, vault.balance()))
+            .collect()
+    }
+
+    /// Returns `(input, output)` vaults of the pool, in that order, for the given swap direction.
+    fn vaults_mut(
+        &mut self,
+        input_address: ResourceAddress,
+        output_address: ResourceAddress,
+    ) -> (&mut Vault, &mut Vault) {
+        if self.is_a_to_b(input_address, output_address) {
+            (&mut self.a, &mut self.b)
+        } else {
+            (&mut self.b, &mut self.a)
+        }
+    }
+
+    /// Direction of a swap, fails if resources do not belong to the pool.
+    fn is_a_to_b(&self, input_address: ResourceAddress, output_address: ResourceAddress) -> bool {
+        let a_address = self.a.resource_address();
+        let b_address = self.b.resource_address();
+        if input_address == a_address && output_address == b_address {
+            true
+        } else if input_address == b_address && output_address == a_address {
+            false
+        } else {
+            panic!(
+                "swap {input_address}->{output_address} does not match pool ({a_address}, {b_address})"
+            )
+        }
     }
 
     //TODO: replace (Bucket, Bucket) with Pair analogy.
     // Pair should represent Pair of addresses while this type should represent values.
-    fn add(&mut self, (a, b): (Bucket, Bucket)) -> Bucket {
-        let a_amount = a.amount();
-        let b_amount = b.amount();
-        self.a.deposit(a);
-        self.b.deposit(b);
-        let a_ratio = if self.a.balance().is_zero() {
-            Amount::new(1)
+    /// Deposits liquidity in the current ratio of the pool and mints LP tokens for it.
+    /// The first deposit mints `sqrt(a * b)` minus locked `MINIMUM_LIQUIDITY`, later ones mint
+    /// a share of the LP supply proportional to the share of reserves and collected fees provided,
+    /// as LP tokens are redeemed for both, so new providers don't take fees collected before.
+    /// Returns minted LP tokens together with a refund of the side provided above the pool ratio.
+    fn add(&mut self, (a, b): (Bucket, Bucket)) -> (Bucket, Bucket) {
+        self.assert_active();
+        self.update_oracle();
+        let snapshot = self.snapshot();
+        let (mut a, mut b) = if a.resource_address() == self.a.resource_address() {
+            (a, b)
         } else {
-            a_amount / self.a.balance()
+            (b, a)
         };
-        let b_ratio = if self.b.balance().is_zero() {
-            Amount::new(1)
+        assert!(
+            a.amount().is_positive() && b.amount().is_positive(),
+            "both resources must be provided to add liquidity"
+        );
+        let lp_manager = ResourceManager::get(self.lp_resource);
+        let total_supply = snapshot.lp_supply;
+        let a_amount = math::wide(a.amount());
+        let b_amount = math::wide(b.amount());
+        if total_supply == 0 {
+            let liquidity = math::isqrt(math::mul(a_amount, b_amount));
+            assert!(
+                liquidity > MINIMUM_LIQUIDITY,
+                "initial liquidity {liquidity} must exceed {MINIMUM_LIQUIDITY}"
+            );
+            let refund = a.take(Amount::zero());
+            self.a.deposit(a);
+            self.b.deposit(b);
+            self.locked_liquidity
+                .deposit(lp_manager.mint_fungible(math::narrow(MINIMUM_LIQUIDITY)));
+            let lp_bucket = lp_manager.mint_fungible(math::narrow(liquidity - MINIMUM_LIQUIDITY));
+            self.emit_liquidity_added(&lp_bucket, &refund, &snapshot);
+            return (lp_bucket, refund);
+        }
+        let (a_backing, b_backing) = snapshot.backing();
+        // both optimal amounts are rounded up, so rounding never dilutes existing providers
+        let b_optimal = math::div_ceil(math::mul(a_amount, b_backing), a_backing);
+        let refund = if b_optimal <= b_amount {
+            b.take(math::narrow(b_amount - b_optimal))
         } else {
-            b_amount / self.b.balance()
+            let a_optimal = math::div_ceil(math::mul(b_amount, a_backing), b_backing);
+            a.take(math::narrow(a_amount - a_optimal))
         };
-        let new_lp_amount = a_ratio * a_amount + b_ratio * b_amount;
-        ResourceManager::get(self.lp_resource).mint_fungible(new_lp_amount)
+        let liquidity =
+            math::div_floor(math::mul(math::wide(a.amount()), total_supply), a_backing).min(
+                math::div_floor(math::mul(math::wide(b.amount()), total_supply), b_backing),
+            );
+        assert!(
+            liquidity > 0,
+            "provided liquidity is too small to mint any LP tokens"
+        );
+        self.a.deposit(a);
+        self.b.deposit(b);
+        let lp_bucket = lp_manager.mint_fungible(math::narrow(liquidity));
+        self.emit_liquidity_added(&lp_bucket, &refund, &snapshot);
+        self.assert_invariant(snapshot);
+        (lp_bucket, refund)
     }
 
     pub fn remove(&mut self, lp_bucket: Bucket) -> (Bucket, Bucket) {
@@ -117,29 +1002,221 @@ impl LiquidityPool {
             lp_bucket.resource_address(),
             self.lp_resource
         );
-        let a_balance = self.a.balance().value() as f64;
-        let b_balance = self.b.balance().value() as f64;
-        let lp_ratio = lp_bucket.amount().value() as f64
-            / ResourceManager::get(self.lp_resource)
-                .total_supply()
-                .value() as f64;
-        let a_amount = Amount::new((lp_ratio * a_balance).ceil() as i64);
-        let b_amount = Amount::new((lp_ratio * b_balance).ceil() as i64);
+        self.assert_unlocked();
+        self.update_oracle();
+        let snapshot = self.snapshot();
+        let lp_amount = lp_bucket.amount();
+        let total_supply = math::narrow(snapshot.lp_supply);
+        let (a_backing, b_backing) = snapshot.backing();
+        let a_amount = math::share(math::narrow(a_backing), lp_amount, total_supply);
+        let b_amount = math::share(math::narrow(b_backing), lp_amount, total_supply);
         lp_bucket.burn();
+        // fees belong to liquidity providers, so the share of collected fees is paid out
+        // together with reserves, the rest of the share of the backing coming from reserves
+        let a_fees = self.take_fees_share(self.a.resource_address(), lp_amount, total_supply);
+        let b_fees = self.take_fees_share(self.b.resource_address(), lp_amount, total_supply);
+        self.a.deposit(a_fees);
+        self.b.deposit(b_fees);
         let a_bucket = self.a.withdraw(a_amount);
         let b_bucket = self.b.withdraw(b_amount);
+        self.assert_invariant(snapshot);
+        self.event(events::LIQUIDITY_REMOVED)
+            .with("amount_a", a_amount)
+            .with("amount_b", b_amount)
+            .with("lp_amount", lp_amount)
+            .emit();
         (a_bucket, b_bucket)
     }
 
-    // pub fn collect_fees(&mut self, token: &Token, fee: f64) {
-    //     let token_key = token.symbol.clone();
-    //     *self.fees_collected.entry(token_key).or_insert(0.0) += fee;
-    // }
+    /// Adds liquidity with the `input` of a single resource of the pool, swapping the part
+    /// of it that leaves the rest in the pool ratio after the swap.
+    /// Returns minted LP tokens together with a refund of rounding leftovers.
+    fn zap_in(&mut self, mut input: Bucket, treasury: &mut Treasury) -> (Bucket, Bucket) {
+        self.assert_active();
+        let input_resource = input.resource_address();
+        let output_resource = self.counter_resource(input_resource);
+        let swap_amount = self.zap_swap_amount(input_resource, input.amount());
+        assert!(
+            swap_amount.is_positive(),
+            "input of {} is too small to add liquidity with",
+            input.amount()
+        );
+        let output = self.execute_swap(input.take(swap_amount), output_resource, treasury);
+        let added = self.add((input, output));
+        self.fill_orders(treasury);
+        added
+    }
+
+    /// Removes liquidity of the `lp_bucket` and swaps the other resource of the pool
+    /// to the `output_resource`, returning everything in it.
+    fn zap_out(
+        &mut self,
+        lp_bucket: Bucket,
+        output_resource: ResourceAddress,
+        treasury: &mut Treasury,
+    ) -> Bucket {
+        self.assert_active();
+        let input_resource = self.counter_resource(output_resource);
+        let (a, b) = self.remove(lp_bucket);
+        let (output, input) = if a.resource_address() == output_resource {
+            (a, b)
+        } else {
+            (b, a)
+        };
+        let swapped = self.execute_swap(input, output_resource, treasury);
+        // buckets are merged through the reserve, which is left as it was
+        let amount = output.amount() + swapped.amount();
+        let reserve = self.reserve_mut(output_resource);
+        reserve.deposit(output);
+        reserve.deposit(swapped);
+        let output = reserve.withdraw(amount);
+        self.fill_orders(treasury);
+        output
+    }
+
+    /// Largest part of `amount` of `input_resource` which, once swapped, leaves no less input
+    /// than the pool ratio after the swap requires for the output, found by bisection, so it
+    /// holds for any curve.
+    fn zap_swap_amount(&self, input_resource: ResourceAddress, amount: Amount) -> Amount {
+        let output_resource = self.counter_resource(input_resource);
+        let (input_reserve, output_reserve) = self.reserves(input_resource, output_resource);
+        let fees = |resource: ResourceAddress| math::wide(self.fees_collected[&resource].balance());
+        let (mut low, mut high) = (0, math::wide(amount));
+        while low < high {
+            let swap_amount = low + (high - low + 1) / 2;
+            let (output_amount, _) =
+                self.quote_amount_out(input_resource, math::narrow(swap_amount), output_resource);
+            // `add` takes the ratio of reserves together with collected fees, which keep the swap
+            // fee less the treasury share, see `collect_fees`, so the input side is never less
+            let input_backing = math::wide(input_reserve) + fees(input_resource) + swap_amount;
+            let output_backing = math::wide(output_reserve - output_amount) + fees(output_resource);
+            let remaining_input = math::wide(amount) - swap_amount;
+            if math::mul(remaining_input, output_backing)
+                >= math::mul(math::wide(output_amount), input_backing)
+            {
+                low = swap_amount;
+            } else {
+                high = swap_amount - 1;
+            }
+        }
+        math::narrow(low)
+    }
+
+    /// Emits `LiquidityAdded` with amounts actually deposited since the `before` snapshot.
+    fn emit_liquidity_added(&self, lp_bucket: &Bucket, refund: &Bucket, before: &PoolSnapshot) {
+        self.event(events::LIQUIDITY_ADDED)
+            .with("amount_a", math::wide(self.a.balance()) - before.a)
+            .with("amount_b", math::wide(self.b.balance()) - before.b)
+            .with("lp_amount", lp_bucket.amount())
+            .with("refund_resource", refund.resource_address())
+            .with("refund_amount", refund.amount())
+            .emit();
+    }
+
+    fn take_fees_share(
+        &mut self,
+        resource: ResourceAddress,
+        lp_amount: Amount,
+        total_supply: Amount,
+    ) -> Bucket {
+        let fee_vault = self.fee_vault_mut(resource);
+        let amount = math::share(fee_vault.balance(), lp_amount, total_supply);
+        fee_vault.withdraw(amount)
+    }
+
+    fn snapshot(&self) -> PoolSnapshot {
+        PoolSnapshot {
+            a: math::wide(self.a.balance()),
+            b: math::wide(self.b.balance()),
+            a_fees: math::wide(self.fees_collected[&self.a.resource_address()].balance()),
+            b_fees: math::wide(self.fees_collected[&self.b.resource_address()].balance()),
+            lp_supply: math::wide(ResourceManager::get(self.lp_resource).total_supply()),
+        }
+    }
+
+    /// Swaps must never decrease `k = a * b`, or `D` of the stable curve, while adding or
+    /// removing liquidity must never decrease reserves and collected fees backing a single LP token.
+    fn assert_invariant(&self, before: PoolSnapshot) {
+        let after = self.snapshot();
+        if before.lp_supply != after.lp_supply {
+            let (a_before, b_before) = before.backing();
+            let (a_after, b_after) = after.backing();
+            assert!(
+                math::mul(a_after, before.lp_supply) >= math::mul(a_before, after.lp_supply)
+                    && math::mul(b_after, before.lp_supply) >= math::mul(b_before, after.lp_supply),
+                "pool invariant violated: backing ({}, {}) per {} LP became ({}, {}) per {} LP",
+                a_before,
+                b_before,
+                before.lp_supply,
+                a_after,
+                b_after,
+                after.lp_supply
+            );
+        } else if let Some(amplification) = self.curve.amplification() {
+            let invariant = |snapshot: &PoolSnapshot| {
+                stable::invariant(
+                    math::narrow(snapshot.a),
+                    math::narrow(snapshot.b),
+                    amplification,
+                )
+            };
+            let (d_before, d_after) = (invariant(&before), invariant(&after));
+            // `D` is solved to within a unit, so the same reserves may give `D` one unit apart
+            assert!(
+                d_after + 1 >= d_before,
+                "pool invariant violated: D decreased from {d_before} to {d_after}"
+            );
+        } else {
+            assert!(
+                math::mul(after.a, after.b) >= math::mul(before.a, before.b),
+                "pool invariant violated: k decreased from {} * {} to {} * {}",
+                before.a,
+                before.b,
+                after.a,
+                after.b
+            );
+        }
+    }
 }
 
 impl LiquidityPools {
-    fn liquidity_pool_mut(&mut self, pair: &Pair) -> Option<&mut LiquidityPool> {
-        self.inner.get_mut(pair)
+    fn liquidity_pool(&self, pair: &Pair, fee: Fee) -> Option<&LiquidityPool> {
+        self.inner.get(pair)?.iter().find(|pool| pool.fee == fee)
+    }
+
+    fn liquidity_pool_mut(&mut self, pair: &Pair, fee: Fee) -> Option<&mut LiquidityPool> {
+        self.inner
+            .get_mut(pair)?
+            .iter_mut()
+            .find(|pool| pool.fee == fee)
+    }
+
+    fn existing_liquidity_pool_mut(&mut self, pair: &Pair, fee: Fee) -> &mut LiquidityPool {
+        self.liquidity_pool_mut(pair, fee).unwrap_or_else(|| {
+            panic!(
+                "liquidity pool not available for ({}{}) pair with {fee} fee",
+                pair.a(),
+                pair.b()
+            )
+        })
+    }
+
+    fn existing_liquidity_pool(&self, pair: &Pair, fee: Fee) -> &LiquidityPool {
+        self.liquidity_pool(pair, fee).unwrap_or_else(|| {
+            panic!(
+                "liquidity pool not available for ({}{}) pair with {fee} fee",
+                pair.a(),
+                pair.b()
+            )
+        })
+    }
+
+    fn liquidity_pool_by_lp_resource(&self, lp_resource: ResourceAddress) -> &LiquidityPool {
+        self.inner
+            .values()
+            .flatten()
+            .find(|pool| pool.lp_resource == lp_resource)
+            .unwrap_or_else(|| panic!("liquidity pool not available for {lp_resource}"))
     }
 
     //TODO: merge with ^^^ and make it liquidity pool key - it should be a pair or liquidity provider token
@@ -149,14 +1226,304 @@ impl LiquidityPools {
     ) -> Option<&mut LiquidityPool> {
         self.inner
             .values_mut()
+            .flatten()
             .find(|pool| pool.lp_resource == liqudity_provider_token.resource_address())
     }
 
-    fn liquidity_pool_mut_or_insert(&mut self, pair: Pair) -> &mut LiquidityPool {
+    /// Tradable pool of the `pair` returning the most for `amount` of `input_resource`.
+    fn best_liquidity_pool(
+        &self,
+        input_resource: ResourceAddress,
+        amount: Amount,
+        output_resource: ResourceAddress,
+    ) -> &LiquidityPool {
+        self.inner
+            .get(&Pair::new(input_resource, output_resource))
+            .and_then(|pools| {
+                pools
+                    .iter()
+                    .filter(|pool| pool.is_tradable())
+                    .max_by_key(|pool| {
+                        pool.quote_amount_out(input_resource, amount, output_resource)
+                            .0
+                    })
+            })
+            .unwrap_or_else(|| {
+                panic!("liquidity pool not available for ({input_resource}{output_resource}) pair")
+            })
+    }
+
+    fn best_liquidity_pool_mut(
+        &mut self,
+        input_resource: ResourceAddress,
+        amount: Amount,
+        output_resource: ResourceAddress,
+    ) -> &mut LiquidityPool {
+        let fee = self
+            .best_liquidity_pool(input_resource, amount, output_resource)
+            .fee;
+        self.existing_liquidity_pool_mut(&Pair::new(input_resource, output_resource), fee)
+    }
+
+    /// Pool of the `pair` with the `fee`, created with the `curve` if there is none yet,
+    /// its LP token named after `symbols` of the pair.
+    fn liquidity_pool_mut_or_insert(
+        &mut self,
+        pair: Pair,
+        fee: Fee,
+        curve: Curve,
+        symbols: &HashMap<ResourceAddress, String>,
+    ) -> &mut LiquidityPool {
+        let pools = self.inner.entry(pair).or_default();
+        let index = match pools.iter().position(|pool| pool.fee == fee) {
+            Some(index) => index,
+            None => {
+                let kind = match curve {
+                    Curve::ConstantProduct => lp_token::CONSTANT_PRODUCT,
+                    Curve::Stable(_) => lp_token::STABLE,
+                };
+                let lp_token = lp_token::LpToken::new(kind, &[pair.a(), pair.b()], fee, symbols);
+                pools.push(LiquidityPool::new(pair, fee, curve, lp_token));
+                pools.len() - 1
+            }
+        };
+        &mut pools[index]
+    }
+
+    fn concentrated_pool(&self, pair: &Pair, fee: Fee) -> Option<&concentrated::ConcentratedPool> {
+        self.concentrated
+            .get(pair)?
+            .iter()
+            .find(|pool| pool.fee == fee)
+    }
+
+    fn existing_concentrated_pool(&self, pair: &Pair, fee: Fee) -> &concentrated::ConcentratedPool {
+        self.concentrated_pool(pair, fee).unwrap_or_else(|| {
+            panic!(
+                "concentrated liquidity pool not available for ({}{}) pair with {fee} fee",
+                pair.a(),
+                pair.b()
+            )
+        })
+    }
+
+    fn concentrated_pool_mut(
+        &mut self,
+        pair: &Pair,
+        fee: Fee,
+    ) -> Option<&mut concentrated::ConcentratedPool> {
+        self.concentrated
+            .get_mut(pair)?
+            .iter_mut()
+            .find(|pool| pool.fee == fee)
+    }
+
+    fn existing_concentrated_pool_mut(
+        &mut self,
+        pair: &Pair,
+        fee: Fee,
+    ) -> &mut concentrated::ConcentratedPool {
+        self.concentrated_pool_mut(pair, fee).unwrap_or_else(|| {
+            panic!(
+                "concentrated liquidity pool not available for ({}{}) pair with {fee} fee",
+                pair.a(),
+                pair.b()
+            )
+        })
+    }
+
+    /// The constant product or stable pool and the concentrated liquidity pool of the `pair`
+    /// with the `fee`, fails unless at least one of them exists.
+    fn existing_pair_pools_mut(
+        &mut self,
+        pair: &Pair,
+        fee: Fee,
+    ) -> (
+        Option<&mut LiquidityPool>,
+        Option<&mut concentrated::ConcentratedPool>,
+    ) {
+        let pool = self
+            .inner
+            .get_mut(pair)
+            .and_then(|pools| pools.iter_mut().find(|pool| pool.fee == fee));
+        let concentrated_pool = self
+            .concentrated
+            .get_mut(pair)
+            .and_then(|pools| pools.iter_mut().find(|pool| pool.fee == fee));
+        assert!(
+            pool.is_some() || concentrated_pool.is_some(),
+            "no pool available for ({}{}) pair with {fee} fee",
+            pair.a(),
+            pair.b()
+        );
+        (pool, concentrated_pool)
+    }
+
+    fn concentrated_pool_mut_by_position(
+        &mut self,
+        position_resource: ResourceAddress,
+    ) -> &mut concentrated::ConcentratedPool {
+        self.concentrated
+            .values_mut()
+            .flatten()
+            .find(|pool| pool.position_resource() == position_resource)
+            .unwrap_or_else(|| {
+                panic!("concentrated liquidity pool not available for {position_resource}")
+            })
+    }
+
+    fn concentrated_pool_mut_or_insert(
+        &mut self,
+        pair: Pair,
+        fee: Fee,
+        symbols: &HashMap<ResourceAddress, String>,
+    ) -> &mut concentrated::ConcentratedPool {
+        let pools = self.concentrated.entry(pair).or_default();
+        let index = match pools.iter().position(|pool| pool.fee == fee) {
+            Some(index) => index,
+            None => {
+                let lp_token = lp_token::LpToken::new(
+                    lp_token::CONCENTRATED,
+                    &[pair.a(), pair.b()],
+                    fee,
+                    symbols,
+                );
+                pools.push(concentrated::ConcentratedPool::new(pair, fee, lp_token));
+                pools.len() - 1
+            }
+        };
+        &mut pools[index]
+    }
+
+    /// Whether the `resource` is the fungible LP resource of a pool.
+    fn is_lp_resource(&self, resource: ResourceAddress) -> bool {
         self.inner
-            .entry(pair)
-            .or_insert_with(|| LiquidityPool::new(pair))
+            .values()
+            .flatten()
+            .any(|pool| pool.lp_resource == resource)
+            || self
+                .weighted
+                .values()
+                .flatten()
+                .any(|pool| pool.lp_resource() == resource)
     }
+
+    fn weighted_pool(&self, assets: &Assets, fee: Fee) -> Option<&weighted::WeightedPool> {
+        self.weighted
+            .get(assets)?
+            .iter()
+            .find(|pool| pool.fee == fee)
+    }
+
+    fn existing_weighted_pool(&self, assets: &Assets, fee: Fee) -> &weighted::WeightedPool {
+        self.weighted_pool(assets, fee).unwrap_or_else(|| {
+            panic!(
+                "weighted pool not available for {:?} with {fee} fee",
+                assets.resources()
+            )
+        })
+    }
+
+    fn existing_weighted_pool_mut(
+        &mut self,
+        assets: &Assets,
+        fee: Fee,
+    ) -> &mut weighted::WeightedPool {
+        self.weighted
+            .get_mut(assets)
+            .and_then(|pools| pools.iter_mut().find(|pool| pool.fee == fee))
+            .unwrap_or_else(|| {
+                panic!(
+                    "weighted pool not available for {:?} with {fee} fee",
+                    assets.resources()
+                )
+            })
+    }
+
+    fn weighted_pool_mut_by_lp_resource(
+        &mut self,
+        lp_resource: ResourceAddress,
+    ) -> &mut weighted::WeightedPool {
+        self.weighted
+            .values_mut()
+            .flatten()
+            .find(|pool| pool.lp_resource() == lp_resource)
+            .unwrap_or_else(|| panic!("weighted pool not available for {lp_resource}"))
+    }
+}
+
+/// Fails if the whole exchange is paused, see `Tex::set_paused`.
+fn assert_not_paused(paused: bool) {
+    assert!(
+        !paused,
+        "exchange paused: only liquidity removal is allowed"
+    );
+}
+
+/// Admin methods require a proof of the `admin_badge`, and while an `access_pass` is set,
+/// `GATED_METHODS` require a proof of it.
+fn access_rules(admin_badge: ResourceAddress, access_pass: Option<ResourceAddress>) -> AccessRules {
+    let access_rules = ADMIN_METHODS
+        .iter()
+        .fold(AccessRules::new(), |access_rules, method| {
+            access_rules.add_method_rule(*method, rule!(resource(admin_badge)))
+        });
+    let access_rules = match access_pass {
+        Some(access_pass) => GATED_METHODS
+            .iter()
+            .fold(access_rules, |access_rules, method| {
+                access_rules.add_method_rule(*method, rule!(resource(access_pass)))
+            }),
+        None => access_rules,
+    };
+    access_rules.default(AccessRule::AllowAll)
+}
+
+/// Fails unless new pools may be created with the `fee`, see `Tex::add_fee_tier`.
+fn assert_fee_tier_enabled(fee_tiers: &BTreeSet<Fee>, fee: Fee) {
+    assert!(
+        fee_tiers.contains(&fee),
+        "fee tier {fee} is not enabled, available tiers are {fee_tiers:?}"
+    );
+}
+
+/// Reads the limit order of the `receipt` of the `order_receipt` resource and burns the receipt.
+fn redeem_order_receipt(order_receipt: Option<ResourceAddress>, receipt: Bucket) -> LimitOrder {
+    assert!(
+        order_receipt == Some(receipt.resource_address()),
+        "invalid limit order receipt {}",
+        receipt.resource_address()
+    );
+    assert!(
+        receipt.amount() == Amount::new(1),
+        "limit orders must be redeemed one receipt at a time"
+    );
+    let order: LimitOrder = receipt
+        .get_non_fungibles()
+        .first()
+        .expect("limit order receipt is empty")
+        .get_data();
+    receipt.burn();
+    order
+}
+
+fn existing_farm_mut(
+    farms: &mut HashMap<ResourceAddress, farm::Farm>,
+    lp_resource: ResourceAddress,
+) -> &mut farm::Farm {
+    farms
+        .get_mut(&lp_resource)
+        .unwrap_or_else(|| panic!("farm not available for {lp_resource}"))
+}
+
+fn farm_mut_by_stake(
+    farms: &mut HashMap<ResourceAddress, farm::Farm>,
+    stake_resource: ResourceAddress,
+) -> &mut farm::Farm {
+    farms
+        .values_mut()
+        .find(|farm| farm.stake_resource() == stake_resource)
+        .unwrap_or_else(|| panic!("farm not available for stake receipt {stake_resource}"))
 }
 
 #[template]
@@ -164,46 +1531,220 @@ mod tex {
     use super::*;
 
     /// Tari Exchange. Decentralized exchange for Tari based network assets.
-    /// Current version includes basic Automated Market Maker functionality with plain fees.
+    /// Current version includes basic Automated Market Maker functionality with per pool fees,
+    /// along with concentrated liquidity pools where providers choose a price range
+    /// and weighted pools of up to 8 assets. LP tokens can be staked in farms for rewards,
+    /// and limit orders can rest on pools of pairs until swaps move the price through them.
+    /// Operators may require an access pass to trade and curate resources new pools may list.
     pub struct Tex {
         liquidity_pools: LiquidityPools,
-        fee: Fee,
+        /// Fees new pools may be created with. Existing pools keep their fee regardless.
+        fee_tiers: BTreeSet<Fee>,
+        treasury: Treasury,
+        /// Stops swaps and new liquidity in all pools, removing liquidity is still possible.
+        paused: bool,
+        /// Resource a proof of which is required to call `ADMIN_METHODS`.
+        admin_badge: ResourceAddress,
+        /// Non-fungible debt receipt of flash loans, created with the first loan,
+        /// so the component owns it and is the only one able to mint and burn it.
+        flash_loan_receipt: Option<ResourceAddress>,
+        flash_loans_issued: u64,
+        /// Symbols of resources LP tokens of new pools are named after, curated by the admin,
+        /// since resources can't be trusted to name themselves.
+        token_symbols: HashMap<ResourceAddress, String>,
+        /// Liquidity mining farms keyed by the LP resource they reward.
+        farms: HashMap<ResourceAddress, farm::Farm>,
+        /// Non-fungible receipt of limit orders, created with the first order like the flash loan one.
+        order_receipt: Option<ResourceAddress>,
+        orders_placed: u64,
+        /// Resource a proof of which is required to call `GATED_METHODS`, anyone may call them if `None`.
+        access_pass: Option<ResourceAddress>,
+        listing: ListingPolicy,
     }
 
     impl Tex {
         /// Initialises a new exchange component.
-        /// `fee` represents a percentage, so it must be between 0 and 100.
-        pub fn new(fee: Fee) -> Component<Self> {
-            Component::new(Self {
-                fee,
+        /// `fee_tiers` are fees pools may be created with, in hundredths of a basis point,
+        /// so each must be between 0 and 1_000_000.
+        /// `protocol_fee_share` is the share of swap fees the treasury takes from pools with
+        /// the protocol fee switched on, in the same units, e.g. 166_667 is about 1/6 of fees.
+        /// Returns the component together with the admin badge, which must be kept by the operator.
+        pub fn new(fee_tiers: Vec<Fee>, protocol_fee_share: Fee) -> (Component<Self>, Bucket) {
+            let admin_badge = ResourceBuilder::fungible()
+                .with_token_symbol("TEXADMIN")
+                .initial_supply(Amount::new(1));
+            let admin_badge_resource = admin_badge.resource_address();
+            let component = Component::new(Self {
+                fee_tiers: fee_tiers.into_iter().collect(),
+                treasury: Treasury::new(protocol_fee_share),
+                paused: false,
                 liquidity_pools: LiquidityPools::default(),
+                admin_badge: admin_badge_resource,
+                flash_loan_receipt: None,
+                flash_loans_issued: 0,
+                token_symbols: HashMap::new(),
+                farms: HashMap::new(),
+                order_receipt: None,
+                orders_placed: 0,
+                access_pass: None,
+                listing: ListingPolicy::permissionless(),
             })
-            //TODO: [STAGE2] to protect liquidity (in a DEFI2.0 way), require to own an additional token (resource) to be able to interact with Tex
-            // that way Tex will be an owner of own liquidity and prevent whales or other parties from manipulations on the markets
-            .with_access_rules(AccessRules::allow_all())
-            .create()
+            .with_owner_rule(OwnerRule::ByAccessRule(rule!(resource(
+                admin_badge_resource
+            ))))
+            .with_access_rules(access_rules(admin_badge_resource, None))
+            .create();
+            (component, admin_badge)
         }
 
         /// Trade provided asset to an asset of requested type.
+        /// Pools can be traded in both directions, no matter in which order liquidity was added.
         /// Execution may fail if we do not have enough liquidity of requested type on the market.
-        pub fn swap(&mut self, input: Bucket, output_address: ResourceAddress) -> Bucket {
-            let input_address = input.resource_address();
+        /// `fee` selects the pool of the pair to trade in.
+        pub fn swap(&mut self, input: Bucket, output_address: ResourceAddress, fee: Fee) -> Bucket {
+            assert_not_paused(self.paused);
             self.liquidity_pools
-                .liquidity_pool_mut(&(input_address, output_address))
-                .unwrap_or_else(|| {
-                    panic!(
-                        "liquidity pool not available for ({input_address}{output_address}) pair"
+                .existing_liquidity_pool_mut(
+                    &Pair::new(input.resource_address(), output_address),
+                    fee,
+                )
+                .swap(input, output_address, &mut self.treasury)
+        }
+
+        /// Trade provided asset to exactly `output_amount` of an asset of requested type.
+        /// Returns the output together with the unspent part of the `input`, so `input` acts as a
+        /// maximum the caller is ready to pay.
+        pub fn swap_for_exact(
+            &mut self,
+            input: Bucket,
+            output_address: ResourceAddress,
+            fee: Fee,
+            output_amount: Amount,
+        ) -> (Bucket, Bucket) {
+            assert_not_paused(self.paused);
+            self.liquidity_pools
+                .existing_liquidity_pool_mut(
+                    &Pair::new(input.resource_address(), output_address),
+                    fee,
+                )
+                .swap_for_exact(input, output_address, output_amount, &mut self.treasury)
+        }
+
+        /// Same as `swap`, but fails the transaction if less than `min_out` would be returned.
+        pub fn swap_with_min_out(
+            &mut self,
+            input: Bucket,
+            output_address: ResourceAddress,
+            fee: Fee,
+            min_out: Amount,
+        ) -> Bucket {
+            let output = self.swap(input, output_address, fee);
+            assert!(
+                output.amount() >= min_out,
+                "slippage exceeded: swap returns {}, at least {min_out} expected",
+                output.amount()
+            );
+            output
+        }
+
+        /// Trade provided asset through every resource of the `path` in turn, atomically,
+        /// e.g. `input` of A with `path` of `[B, C]` swaps A to B and then B to C.
+        /// Each hop goes through the pool of the pair returning the most at the moment.
+        /// Fails the transaction if less than `min_out` of the last resource would be returned.
+        pub fn swap_route(
+            &mut self,
+            input: Bucket,
+            path: Vec<ResourceAddress>,
+            min_out: Amount,
+        ) -> Bucket {
+            assert_not_paused(self.paused);
+            assert!(!path.is_empty(), "swap route requires at least one hop");
+            let mut output = input;
+            for output_address in path {
+                output = self
+                    .liquidity_pools
+                    .best_liquidity_pool_mut(
+                        output.resource_address(),
+                        output.amount(),
+                        output_address,
                     )
-                })
-                .swap(input, output_address, self.fee)
+                    .swap(output, output_address, &mut self.treasury);
+            }
+            assert!(
+                output.amount() >= min_out,
+                "slippage exceeded: swap route returns {}, at least {min_out} expected",
+                output.amount()
+            );
+            output
         }
 
-        pub fn add_liquidity(&mut self, a: Bucket, b: Bucket) -> Bucket {
+        /// Provide liquidity to the pool of `a` and `b` resources with the `fee`,
+        /// creating the pool if needed, in which case `fee` must be one of the fee tiers.
+        /// Returns LP tokens and a refund of the resource provided above the current pool ratio.
+        pub fn add_liquidity(&mut self, a: Bucket, b: Bucket, fee: Fee) -> (Bucket, Bucket) {
+            assert_not_paused(self.paused);
+            let pair = Pair::new(a.resource_address(), b.resource_address());
+            if self.liquidity_pools.liquidity_pool(&pair, fee).is_none() {
+                assert_fee_tier_enabled(&self.fee_tiers, fee);
+                self.listing.assert_listable(&[pair.a(), pair.b()]);
+            }
             self.liquidity_pools
-                .liquidity_pool_mut_or_insert((a.resource_address(), b.resource_address()))
+                .liquidity_pool_mut_or_insert(
+                    pair,
+                    fee,
+                    Curve::ConstantProduct,
+                    &self.token_symbols,
+                )
                 .add((a, b))
         }
 
+        /// Create an empty pool of the `pair` with the `fee` priced by the StableSwap curve,
+        /// for resources meant to trade close to one to one, e.g. two stablecoins.
+        /// The higher the `amplification`, the lower the slippage around the peg.
+        /// Liquidity is then provided, swapped and removed the same way as in any other pool.
+        /// Returns the LP resource of the pool.
+        pub fn create_stable_pool(
+            &mut self,
+            pair: Pair,
+            fee: Fee,
+            amplification: u64,
+        ) -> ResourceAddress {
+            assert_not_paused(self.paused);
+            assert_fee_tier_enabled(&self.fee_tiers, fee);
+            self.listing.assert_listable(&[pair.a(), pair.b()]);
+            assert!(
+                self.liquidity_pools.liquidity_pool(&pair, fee).is_none(),
+                "liquidity pool for ({}{}) pair with {fee} fee already exists",
+                pair.a(),
+                pair.b()
+            );
+            self.liquidity_pools
+                .liquidity_pool_mut_or_insert(
+                    pair,
+                    fee,
+                    Curve::Stable(stable::Amplification::new(amplification)),
+                    &self.token_symbols,
+                )
+                .lp_resource
+        }
+
+        /// Same as `add_liquidity`, but fails the transaction if less than `min_lp` is minted.
+        pub fn add_liquidity_with_min_lp(
+            &mut self,
+            a: Bucket,
+            b: Bucket,
+            fee: Fee,
+            min_lp: Amount,
+        ) -> (Bucket, Bucket) {
+            let (lp_bucket, refund) = self.add_liquidity(a, b, fee);
+            assert!(
+                lp_bucket.amount() >= min_lp,
+                "slippage exceeded: add liquidity mints {}, at least {min_lp} expected",
+                lp_bucket.amount()
+            );
+            (lp_bucket, refund)
+        }
+
         pub fn remove_liquidity(&mut self, lp_bucket: Bucket) -> (Bucket, Bucket) {
             self.liquidity_pools
                 .liquidity_pool_mut_by_(&lp_bucket)
@@ -216,8 +1757,819 @@ mod tex {
                 .remove(lp_bucket)
         }
 
+        /// Same as `remove_liquidity`, but fails the transaction if either returned amount is too low.
+        /// Minimums follow the order of returned buckets, which is the sorted order of pool's `Pair`.
+        pub fn remove_liquidity_with_min_amounts(
+            &mut self,
+            lp_bucket: Bucket,
+            min_a: Amount,
+            min_b: Amount,
+        ) -> (Bucket, Bucket) {
+            let (a, b) = self.remove_liquidity(lp_bucket);
+            assert!(
+                a.amount() >= min_a && b.amount() >= min_b,
+                "slippage exceeded: remove liquidity returns ({}, {}), at least ({min_a}, {min_b}) expected",
+                a.amount(),
+                b.amount()
+            );
+            (a, b)
+        }
+
+        /// Provide liquidity to the existing pool of the `pair` with the `fee` with the `input` of
+        /// one of its resources only. The part of the `input` that balances the rest is swapped
+        /// first, paying the pool fee on it.
+        /// Fails the transaction if less than `min_lp` is minted.
+        /// Returns LP tokens and a refund of rounding leftovers.
+        pub fn zap_in(
+            &mut self,
+            input: Bucket,
+            pair: Pair,
+            fee: Fee,
+            min_lp: Amount,
+        ) -> (Bucket, Bucket) {
+            assert_not_paused(self.paused);
+            assert!(
+                pair.contains(input.resource_address()),
+                "resource {} is not in pair ({}{})",
+                input.resource_address(),
+                pair.a(),
+                pair.b()
+            );
+            let (lp_bucket, refund) = self
+                .liquidity_pools
+                .existing_liquidity_pool_mut(&pair, fee)
+                .zap_in(input, &mut self.treasury);
+            assert!(
+                lp_bucket.amount() >= min_lp,
+                "slippage exceeded: zap in mints {}, at least {min_lp} expected",
+                lp_bucket.amount()
+            );
+            (lp_bucket, refund)
+        }
+
+        /// Remove liquidity of the `lp_bucket` and swap the other resource of its pool to the
+        /// `output_resource`, so everything is returned in it.
+        /// Fails the transaction if less than `min_out` would be returned.
+        pub fn zap_out(
+            &mut self,
+            lp_bucket: Bucket,
+            output_resource: ResourceAddress,
+            min_out: Amount,
+        ) -> Bucket {
+            assert_not_paused(self.paused);
+            let output = self
+                .liquidity_pools
+                .liquidity_pool_mut_by_(&lp_bucket)
+                .unwrap_or_else(|| {
+                    panic!(
+                        "liquidity pool not available for {}",
+                        lp_bucket.resource_address()
+                    )
+                })
+                .zap_out(lp_bucket, output_resource, &mut self.treasury);
+            assert!(
+                output.amount() >= min_out,
+                "slippage exceeded: zap out returns {}, at least {min_out} expected",
+                output.amount()
+            );
+            output
+        }
+
+        /// Lends `amount` of `resource` out of the pool of the `pair` with the `fee`.
+        /// Returns the loan together with a debt receipt, which can't be deposited anywhere,
+        /// so the transaction fails unless the receipt is passed to `flash_repay` before it ends.
+        /// The pool is locked until then.
+        pub fn flash_borrow(
+            &mut self,
+            pair: Pair,
+            fee: Fee,
+            resource: ResourceAddress,
+            amount: Amount,
+        ) -> (Bucket, Bucket) {
+            assert_not_paused(self.paused);
+            let (loan, fee_amount) = self
+                .liquidity_pools
+                .existing_liquidity_pool_mut(&pair, fee)
+                .flash_borrow(resource, amount);
+            let receipt_resource = *self.flash_loan_receipt.get_or_insert_with(|| {
+                ResourceBuilder::non_fungible()
+                    .with_token_symbol("TEXDEBT")
+                    .depositable(AccessRule::DenyAll)
+                    .build()
+            });
+            self.flash_loans_issued += 1;
+            let receipt = ResourceManager::get(receipt_resource).mint_non_fungible(
+                NonFungibleId::from_u64(self.flash_loans_issued),
+                &FlashLoan {
+                    pair,
+                    fee,
+                    resource,
+                    amount,
+                    fee_amount,
+                },
+                &(),
+            );
+            (loan, receipt)
+        }
+
+        /// Repays the flash loan of the `receipt`, which must cover the borrowed amount plus
+        /// the pool fee on it. The fee goes to liquidity providers like swap fees do.
+        /// Returns the part of the `repayment` above what is due.
+        pub fn flash_repay(&mut self, receipt: Bucket, repayment: Bucket) -> Bucket {
+            assert!(
+                self.flash_loan_receipt == Some(receipt.resource_address()),
+                "invalid flash loan receipt {}",
+                receipt.resource_address()
+            );
+            assert!(
+                receipt.amount() == Amount::new(1),
+                "flash loans must be repaid one receipt at a time"
+            );
+            let loan: FlashLoan = receipt
+                .get_non_fungibles()
+                .first()
+                .expect("flash loan receipt is empty")
+                .get_data();
+            let change = self
+                .liquidity_pools
+                .existing_liquidity_pool_mut(&loan.pair, loan.fee)
+                .flash_repay(&loan, repayment, &mut self.treasury);
+            receipt.burn();
+            change
+        }
+
+        /// Places a limit order selling the whole `input` for `output_resource` in the pool of
+        /// the pair with the `fee`, once the pool pays at least `limit_price` of output per unit
+        /// of input, scaled by `PRICE_SCALE`, fees and price impact included.
+        /// Orders are filled lowest limit price first, oldest first among equal ones, by swaps
+        /// moving the price of the pool, or on `fill_orders`, possibly right away if the pool
+        /// already pays enough.
+        /// Returns the order receipt, which redeems the output once filled, see `claim_limit_order`,
+        /// or the input until then, see `cancel_limit_order`.
+        pub fn place_limit_order(
+            &mut self,
+            input: Bucket,
+            output_resource: ResourceAddress,
+            fee: Fee,
+            limit_price: u64,
+        ) -> Bucket {
+            assert_not_paused(self.paused);
+            let pair = Pair::new(input.resource_address(), output_resource);
+            self.orders_placed += 1;
+            let id = self.orders_placed;
+            self.liquidity_pools
+                .existing_liquidity_pool_mut(&pair, fee)
+                .place_order(id, input, limit_price, &mut self.treasury);
+            let receipt_resource = *self.order_receipt.get_or_insert_with(|| {
+                ResourceBuilder::non_fungible()
+                    .with_token_symbol("TEXORDER")
+                    .build()
+            });
+            ResourceManager::get(receipt_resource).mint_non_fungible(
+                NonFungibleId::from_u64(id),
+                &LimitOrder { pair, fee, id },
+                &(),
+            )
+        }
+
+        /// Cancels the open limit order of the `receipt`, returning its input.
+        /// Possible even in paused and delisted pools.
+        pub fn cancel_limit_order(&mut self, receipt: Bucket) -> Bucket {
+            let order = redeem_order_receipt(self.order_receipt, receipt);
+            self.liquidity_pools
+                .existing_liquidity_pool_mut(&order.pair, order.fee)
+                .cancel_order(order.id)
+        }
+
+        /// Claims the output of the filled limit order of the `receipt`.
+        pub fn claim_limit_order(&mut self, receipt: Bucket) -> Bucket {
+            let order = redeem_order_receipt(self.order_receipt, receipt);
+            self.liquidity_pools
+                .existing_liquidity_pool_mut(&order.pair, order.fee)
+                .claim_order(order.id)
+        }
+
+        /// Fills open limit orders of the pool of the `pair` with the `fee` the pool pays
+        /// enough for, at most `MAX_FILLS` of them. Returns the number of filled orders.
+        pub fn fill_orders(&mut self, pair: Pair, fee: Fee) -> u32 {
+            assert_not_paused(self.paused);
+            let filled = self
+                .liquidity_pools
+                .existing_liquidity_pool_mut(&pair, fee)
+                .fill_orders(&mut self.treasury);
+            filled as u32
+        }
+
+        /// Open a position in the concentrated liquidity pool of `a` and `b` resources with the `fee`,
+        /// creating the pool if needed, in which case `fee` must be one of the fee tiers and
+        /// the ratio of provided amounts sets the initial price.
+        /// Liquidity of the position is used between `lower_tick` and `upper_tick` only, price at
+        /// tick `i` being `1.0001^i` of `b` per `a` in the sorted order of the `Pair`.
+        /// Returns the non-fungible position token and refunds of what the position does not need.
+        pub fn add_concentrated_liquidity(
+            &mut self,
+            a: Bucket,
+            b: Bucket,
+            fee: Fee,
+            lower_tick: i32,
+            upper_tick: i32,
+        ) -> (Bucket, Bucket, Bucket) {
+            assert_not_paused(self.paused);
+            let pair = Pair::new(a.resource_address(), b.resource_address());
+            if self.liquidity_pools.concentrated_pool(&pair, fee).is_none() {
+                assert_fee_tier_enabled(&self.fee_tiers, fee);
+                self.listing.assert_listable(&[pair.a(), pair.b()]);
+            }
+            self.liquidity_pools
+                .concentrated_pool_mut_or_insert(pair, fee, &self.token_symbols)
+                .open_position((a, b), lower_tick, upper_tick)
+        }
+
+        /// Close the position of the `position` token, returning its liquidity and earned fees.
+        pub fn remove_concentrated_liquidity(&mut self, position: Bucket) -> (Bucket, Bucket) {
+            self.liquidity_pools
+                .concentrated_pool_mut_by_position(position.resource_address())
+                .close_position(position)
+        }
+
+        /// Same as `swap`, but trades in the concentrated liquidity pool of the pair with the `fee`.
+        pub fn swap_concentrated(
+            &mut self,
+            input: Bucket,
+            output_address: ResourceAddress,
+            fee: Fee,
+        ) -> Bucket {
+            assert_not_paused(self.paused);
+            self.liquidity_pools
+                .existing_concentrated_pool_mut(
+                    &Pair::new(input.resource_address(), output_address),
+                    fee,
+                )
+                .swap(input, output_address, &mut self.treasury)
+        }
+
+        /// Create a weighted pool of `deposits` of 2 to 8 distinct resources, with `weights`
+        /// relative to each other in the same order, e.g. `[50, 25, 25]`, each at least 1% of the
+        /// total. The `fee` must be one of the fee tiers and can't be zero. Deposits set initial
+        /// prices, so their values should follow the weights.
+        /// Returns LP tokens minted for the deposits.
+        pub fn create_weighted_pool(
+            &mut self,
+            deposits: Vec<Bucket>,
+            weights: Vec<u32>,
+            fee: Fee,
+        ) -> Bucket {
+            assert_not_paused(self.paused);
+            assert_fee_tier_enabled(&self.fee_tiers, fee);
+            let assets = Assets::new(
+                deposits
+                    .iter()
+                    .map(|deposit| deposit.resource_address())
+                    .collect(),
+            );
+            self.listing.assert_listable(assets.resources());
+            assert!(
+                self.liquidity_pools.weighted_pool(&assets, fee).is_none(),
+                "weighted pool for {:?} with {fee} fee already exists",
+                assets.resources()
+            );
+            let lp_token = lp_token::LpToken::new(
+                lp_token::WEIGHTED,
+                assets.resources(),
+                fee,
+                &self.token_symbols,
+            );
+            let (pool, lp_bucket) = weighted::WeightedPool::new(deposits, weights, fee, lp_token);
+            self.liquidity_pools
+                .weighted
+                .entry(assets)
+                .or_default()
+                .push(pool);
+            lp_bucket
+        }
+
+        /// Add liquidity to the weighted pool of the deposited resources with the `fee`,
+        /// in the current ratio of its reserves, so a deposit of each asset is required.
+        /// Returns minted LP tokens together with refunds of deposits above the ratio,
+        /// sorted by resource.
+        pub fn join_weighted_pool(
+            &mut self,
+            deposits: Vec<Bucket>,
+            fee: Fee,
+        ) -> (Bucket, Vec<Bucket>) {
+            assert_not_paused(self.paused);
+            let assets = Assets::new(
+                deposits
+                    .iter()
+                    .map(|deposit| deposit.resource_address())
+                    .collect(),
+            );
+            self.liquidity_pools
+                .existing_weighted_pool_mut(&assets, fee)
+                .join(deposits)
+        }
+
+        /// Add liquidity to the weighted pool of the `assets` with the `fee` in a single asset,
+        /// paying the fee on the part of the `input` the pool has to rebalance.
+        /// Fails the transaction if less than `min_lp` is minted.
+        pub fn join_weighted_pool_single(
+            &mut self,
+            input: Bucket,
+            assets: Assets,
+            fee: Fee,
+            min_lp: Amount,
+        ) -> Bucket {
+            assert_not_paused(self.paused);
+            let lp_bucket = self
+                .liquidity_pools
+                .existing_weighted_pool_mut(&assets, fee)
+                .join_single(input);
+            assert!(
+                lp_bucket.amount() >= min_lp,
+                "slippage exceeded: join mints {}, at least {min_lp} expected",
+                lp_bucket.amount()
+            );
+            lp_bucket
+        }
+
+        /// Remove liquidity from a weighted pool, returning the share of each of its reserves
+        /// the `lp_bucket` is worth, sorted by resource.
+        pub fn exit_weighted_pool(&mut self, lp_bucket: Bucket) -> Vec<Bucket> {
+            self.liquidity_pools
+                .weighted_pool_mut_by_lp_resource(lp_bucket.resource_address())
+                .exit(lp_bucket)
+        }
+
+        /// Remove liquidity from a weighted pool in the `output_resource` only, paying the fee
+        /// on the part the pool has to rebalance.
+        /// Fails the transaction if less than `min_out` is returned.
+        pub fn exit_weighted_pool_single(
+            &mut self,
+            lp_bucket: Bucket,
+            output_resource: ResourceAddress,
+            min_out: Amount,
+        ) -> Bucket {
+            let output = self
+                .liquidity_pools
+                .weighted_pool_mut_by_lp_resource(lp_bucket.resource_address())
+                .exit_single(lp_bucket, output_resource);
+            assert!(
+                output.amount() >= min_out,
+                "slippage exceeded: exit returns {}, at least {min_out} expected",
+                output.amount()
+            );
+            output
+        }
+
+        /// Same as `swap`, but trades between any two assets of the weighted pool of the `assets`
+        /// with the `fee`. Inputs can't exceed half of the input reserve.
+        pub fn swap_weighted(
+            &mut self,
+            input: Bucket,
+            output_address: ResourceAddress,
+            assets: Assets,
+            fee: Fee,
+        ) -> Bucket {
+            assert_not_paused(self.paused);
+            self.liquidity_pools
+                .existing_weighted_pool_mut(&assets, fee)
+                .swap(input, output_address, &mut self.treasury)
+        }
+
+        /// Locks LP tokens of the `lp_bucket` in the farm of their pool, where they earn rewards
+        /// every epoch pro rata to all staked LP tokens. Returns a stake receipt.
+        pub fn stake(&mut self, lp_bucket: Bucket) -> Bucket {
+            existing_farm_mut(&mut self.farms, lp_bucket.resource_address()).stake(lp_bucket)
+        }
+
+        /// Pays out rewards of the stake `receipt`, returning a new receipt of the same stake
+        /// together with the rewards.
+        pub fn claim_rewards(&mut self, receipt: Bucket) -> (Bucket, Bucket) {
+            farm_mut_by_stake(&mut self.farms, receipt.resource_address()).claim(receipt)
+        }
+
+        /// Unlocks LP tokens of the stake `receipt`, returning them together with its rewards.
+        pub fn unstake(&mut self, receipt: Bucket) -> (Bucket, Bucket) {
+            farm_mut_by_stake(&mut self.farms, receipt.resource_address()).unstake(receipt)
+        }
+
         pub fn pools(&self) -> LiquidityPools {
             self.liquidity_pools.clone()
         }
+
+        pub fn admin_badge(&self) -> ResourceAddress {
+            self.admin_badge
+        }
+
+        pub fn fee_tiers(&self) -> Vec<Fee> {
+            self.fee_tiers.iter().copied().collect()
+        }
+
+        /// Admin only. Sets the `symbol` LP tokens of pools created from now on use for the
+        /// `resource`, e.g. `XTR` for `LP-XTR-BTR`. LP tokens of existing pools keep their symbol.
+        pub fn set_token_symbol(&mut self, resource: ResourceAddress, symbol: String) {
+            lp_token::assert_valid_symbol(&symbol);
+            self.token_symbols.insert(resource, symbol);
+        }
+
+        pub fn token_symbols(&self) -> HashMap<ResourceAddress, String> {
+            self.token_symbols.clone()
+        }
+
+        /// Admin only. Adds `rewards` to the farm of the `lp_resource`, creating the farm if
+        /// there is none yet, in which case the resource of `rewards` is what it pays out.
+        /// Rewards are emitted at the rate set by `set_farm_emission` until they run out.
+        pub fn fund_farm(&mut self, lp_resource: ResourceAddress, rewards: Bucket) {
+            if !self.farms.contains_key(&lp_resource) {
+                assert!(
+                    self.liquidity_pools.is_lp_resource(lp_resource),
+                    "{lp_resource} is not an LP resource of a Tex pool"
+                );
+                self.farms.insert(
+                    lp_resource,
+                    farm::Farm::new(lp_resource, rewards.resource_address()),
+                );
+            }
+            existing_farm_mut(&mut self.farms, lp_resource).fund(rewards);
+        }
+
+        /// Admin only. Sets rewards the farm of the `lp_resource` emits per epoch from now on.
+        pub fn set_farm_emission(
+            &mut self,
+            lp_resource: ResourceAddress,
+            reward_per_epoch: Amount,
+        ) {
+            existing_farm_mut(&mut self.farms, lp_resource).set_reward_per_epoch(reward_per_epoch);
+        }
+
+        pub fn farms(&self) -> HashMap<ResourceAddress, farm::Farm> {
+            self.farms.clone()
+        }
+
+        /// Rewards the stake with the `stake_id` in the farm of the `lp_resource` would be paid now.
+        pub fn pending_rewards(
+            &self,
+            lp_resource: ResourceAddress,
+            stake_id: NonFungibleId,
+        ) -> Amount {
+            self.farms
+                .get(&lp_resource)
+                .unwrap_or_else(|| panic!("farm not available for {lp_resource}"))
+                .pending_rewards(&stake_id)
+        }
+
+        /// Admin only. Requires a proof of the `access_pass` resource to trade or provide liquidity,
+        /// or lifts the requirement with `None`. Removing liquidity in all resources
+        /// of a pool is never gated.
+        pub fn set_access_pass(&mut self, access_pass: Option<ResourceAddress>) {
+            self.access_pass = access_pass;
+            ComponentManager::get(CallerContext::current_component_address())
+                .set_access_rules(access_rules(self.admin_badge, access_pass));
+        }
+
+        pub fn access_pass(&self) -> Option<ResourceAddress> {
+            self.access_pass
+        }
+
+        /// Admin only. In permissionless mode new pools may list any resource which is not denied,
+        /// otherwise allowed ones only. Pools listed already keep working, see `delist_pool`.
+        pub fn set_permissionless(&mut self, permissionless: bool) {
+            self.listing.permissionless = permissionless;
+        }
+
+        /// Admin only. Allows or disallows new pools to list the `resource` outside of
+        /// permissionless mode.
+        pub fn set_resource_allowed(&mut self, resource: ResourceAddress, allowed: bool) {
+            if allowed {
+                self.listing.allowed.insert(resource);
+            } else {
+                self.listing.allowed.remove(&resource);
+            }
+        }
+
+        /// Admin only. Stops or lets new pools list the `resource`, in any mode.
+        pub fn set_resource_denied(&mut self, resource: ResourceAddress, denied: bool) {
+            if denied {
+                self.listing.denied.insert(resource);
+            } else {
+                self.listing.denied.remove(&resource);
+            }
+        }
+
+        pub fn listing_policy(&self) -> ListingPolicy {
+            self.listing.clone()
+        }
+
+        /// Admin only. Allows new pools to be created with the `fee`.
+        pub fn add_fee_tier(&mut self, fee: Fee) {
+            self.fee_tiers.insert(fee);
+        }
+
+        /// Admin only. Stops new pools from being created with the `fee`,
+        /// existing pools with the `fee` keep working.
+        pub fn remove_fee_tier(&mut self, fee: Fee) {
+            assert!(self.fee_tiers.remove(&fee), "fee tier {fee} is not enabled");
+        }
+
+        /// Admin only. Stops swaps and new liquidity in the pool of the `pair` with the `fee`
+        /// for good, providers can still remove their liquidity.
+        pub fn delist_pool(&mut self, pair: Pair, fee: Fee) {
+            self.liquidity_pools
+                .existing_liquidity_pool_mut(&pair, fee)
+                .delisted = true;
+        }
+
+        /// Admin only. Emergency stop of swaps and new liquidity in all pools,
+        /// liquidity providers can still withdraw.
+        pub fn set_paused(&mut self, paused: bool) {
+            self.paused = paused;
+        }
+
+        /// Admin only. Same as `set_paused`, but for pools of the `pair` with the `fee` only,
+        /// both the constant product or stable one and the concentrated liquidity one if any.
+        pub fn set_pool_paused(&mut self, pair: Pair, fee: Fee, paused: bool) {
+            let (pool, concentrated_pool) =
+                self.liquidity_pools.existing_pair_pools_mut(&pair, fee);
+            if let Some(pool) = pool {
+                pool.paused = paused;
+            }
+            if let Some(pool) = concentrated_pool {
+                pool.paused = paused;
+            }
+        }
+
+        /// Admin only. Same as `set_paused`, but for the weighted pool of the `assets` with
+        /// the `fee` only.
+        pub fn set_weighted_pool_paused(&mut self, assets: Assets, fee: Fee, paused: bool) {
+            self.liquidity_pools
+                .existing_weighted_pool_mut(&assets, fee)
+                .paused = paused;
+        }
+
+        /// Admin only. Changes the amplification of the stable pool of the `pair` with the `fee`
+        /// gradually, reaching the `target` at the `target_epoch`.
+        pub fn ramp_amplification(&mut self, pair: Pair, fee: Fee, target: u64, target_epoch: u64) {
+            match &mut self
+                .liquidity_pools
+                .existing_liquidity_pool_mut(&pair, fee)
+                .curve
+            {
+                Curve::Stable(amplification) => {
+                    amplification.ramp(Consensus::current_epoch(), target, target_epoch)
+                }
+                Curve::ConstantProduct => panic!(
+                    "liquidity pool for ({}{}) pair with {fee} fee is not a stable pool",
+                    pair.a(),
+                    pair.b()
+                ),
+            }
+        }
+
+        /// Current amplification of the stable pool of the `pair` with the `fee`,
+        /// `None` for constant product pools.
+        pub fn amplification(&self, pair: Pair, fee: Fee) -> Option<u64> {
+            self.liquidity_pools
+                .existing_liquidity_pool(&pair, fee)
+                .curve
+                .amplification()
+        }
+
+        pub fn is_paused(&self) -> bool {
+            self.paused
+        }
+
+        /// Admin only. Changes the share of swap fees taken by the treasury from now on.
+        pub fn set_protocol_fee_share(&mut self, share: Fee) {
+            self.treasury.share = share;
+        }
+
+        /// Admin only. Switches the protocol fee on or off for pools of the `pair` with the `fee`,
+        /// both the constant product or stable one and the concentrated liquidity one if any.
+        pub fn set_protocol_fee(&mut self, pair: Pair, fee: Fee, enabled: bool) {
+            let (pool, concentrated_pool) =
+                self.liquidity_pools.existing_pair_pools_mut(&pair, fee);
+            if let Some(pool) = pool {
+                pool.protocol_fee_on = enabled;
+            }
+            if let Some(pool) = concentrated_pool {
+                pool.protocol_fee_on = enabled;
+            }
+        }
+
+        /// Admin only. Same as `set_protocol_fee`, but for the weighted pool of the `assets`.
+        pub fn set_weighted_protocol_fee(&mut self, assets: Assets, fee: Fee, enabled: bool) {
+            self.liquidity_pools
+                .existing_weighted_pool_mut(&assets, fee)
+                .protocol_fee_on = enabled;
+        }
+
+        /// Admin only. Charges swaps in the pool of the `pair` with the `fee` by recent volatility:
+        /// its fee tier raised by the mean relative change of its average price between recent
+        /// oracle observations, kept between `min_fee` and `max_fee`.
+        /// Only constant product and stable pools keep a price oracle, so the concentrated
+        /// liquidity pool of the same `pair` and `fee`, like weighted pools, keeps charging its
+        /// fee tier.
+        pub fn set_dynamic_fee(&mut self, pair: Pair, fee: Fee, min_fee: Fee, max_fee: Fee) {
+            assert!(
+                min_fee <= max_fee,
+                "minimum fee {min_fee} exceeds maximum fee {max_fee}"
+            );
+            assert!(
+                i128::from(max_fee.get()) < math::FEE_DENOMINATOR,
+                "maximum fee {max_fee} must be below 100%"
+            );
+            self.liquidity_pools
+                .existing_liquidity_pool_mut(&pair, fee)
+                .dynamic_fee = Some(DynamicFee {
+                min: min_fee,
+                max: max_fee,
+            });
+        }
+
+        /// Admin only. Charges swaps in the pool of the `pair` with the `fee` by its fee tier again.
+        pub fn clear_dynamic_fee(&mut self, pair: Pair, fee: Fee) {
+            self.liquidity_pools
+                .existing_liquidity_pool_mut(&pair, fee)
+                .dynamic_fee = None;
+        }
+
+        /// Fee a swap in the pool of the `pair` with the `fee` pays now, in hundredths of a basis
+        /// point. Differs from the fee tier only in pools with a dynamic fee, see `set_dynamic_fee`,
+        /// so never for `swap_concentrated` or `swap_weighted`.
+        pub fn swap_fee(&self, pair: Pair, fee: Fee) -> Fee {
+            self.liquidity_pools
+                .existing_liquidity_pool(&pair, fee)
+                .swap_fee()
+        }
+
+        /// Admin only. Withdraws `amount` of `resource` collected by the treasury.
+        pub fn withdraw_treasury(&mut self, resource: ResourceAddress, amount: Amount) -> Bucket {
+            self.treasury.withdraw(resource, amount)
+        }
+
+        pub fn protocol_fee_share(&self) -> Fee {
+            self.treasury.share
+        }
+
+        /// Protocol fees collected by the treasury and not withdrawn yet, per resource.
+        pub fn treasury_balances(&self) -> HashMap<ResourceAddress, Amount> {
+            self.treasury.balances()
+        }
+
+        /// Fees collected by the pool of the `pair` with the `fee` so far, per resource.
+        pub fn fees_collected(&self, pair: Pair, fee: Fee) -> HashMap<ResourceAddress, Amount> {
+            self.liquidity_pools
+                .existing_liquidity_pool(&pair, fee)
+                .fees_collected()
+        }
+
+        /// Amount `swap` of `amount` of `input_resource` would return now, without trading.
+        pub fn get_amount_out(
+            &self,
+            input_resource: ResourceAddress,
+            amount: Amount,
+            output_resource: ResourceAddress,
+            fee: Fee,
+        ) -> Amount {
+            self.liquidity_pools
+                .existing_liquidity_pool(&Pair::new(input_resource, output_resource), fee)
+                .quote_amount_out(input_resource, amount, output_resource)
+                .0
+        }
+
+        /// Amount `swap_concentrated` of `amount` of `input_resource` would return now, without trading.
+        pub fn get_amount_out_concentrated(
+            &self,
+            input_resource: ResourceAddress,
+            amount: Amount,
+            output_resource: ResourceAddress,
+            fee: Fee,
+        ) -> Amount {
+            self.liquidity_pools
+                .existing_concentrated_pool(&Pair::new(input_resource, output_resource), fee)
+                .quote_amount_out(input_resource, amount, output_resource)
+                .0
+        }
+
+        /// Amount `swap_weighted` of `amount` of `input_resource` would return now, without trading.
+        pub fn get_amount_out_weighted(
+            &self,
+            input_resource: ResourceAddress,
+            amount: Amount,
+            output_resource: ResourceAddress,
+            assets: Assets,
+            fee: Fee,
+        ) -> Amount {
+            self.liquidity_pools
+                .existing_weighted_pool(&assets, fee)
+                .quote_amount_out(input_resource, amount, output_resource)
+        }
+
+        /// Current reserves of the weighted pool of the `assets` with the `fee`, per resource.
+        pub fn weighted_pool_reserves(
+            &self,
+            assets: Assets,
+            fee: Fee,
+        ) -> HashMap<ResourceAddress, Amount> {
+            self.liquidity_pools
+                .existing_weighted_pool(&assets, fee)
+                .balances()
+        }
+
+        /// Price of the `base` resource in the weighted pool of the `assets` with the `fee`
+        /// expressed in the `quote` resource, in the same format as `spot_price`.
+        pub fn weighted_spot_price(
+            &self,
+            assets: Assets,
+            fee: Fee,
+            base: ResourceAddress,
+            quote: ResourceAddress,
+        ) -> u64 {
+            self.liquidity_pools
+                .existing_weighted_pool(&assets, fee)
+                .spot_price(base, quote)
+        }
+
+        /// Current tick of the concentrated liquidity pool of the `pair` with the `fee`,
+        /// `None` until the first position is opened.
+        pub fn concentrated_pool_tick(&self, pair: Pair, fee: Fee) -> Option<i32> {
+            self.liquidity_pools
+                .existing_concentrated_pool(&pair, fee)
+                .current_tick()
+        }
+
+        /// Amount `swap_route` of `amount` of `input_resource` through the `path` would return now,
+        /// without trading.
+        pub fn get_amount_out_route(
+            &self,
+            input_resource: ResourceAddress,
+            amount: Amount,
+            path: Vec<ResourceAddress>,
+        ) -> Amount {
+            path.into_iter()
+                .fold(
+                    (input_resource, amount),
+                    |(input_resource, amount), output_resource| {
+                        let amount = self
+                            .liquidity_pools
+                            .best_liquidity_pool(input_resource, amount, output_resource)
+                            .quote_amount_out(input_resource, amount, output_resource)
+                            .0;
+                        (output_resource, amount)
+                    },
+                )
+                .1
+        }
+
+        /// Amount of `input_resource` `swap_for_exact` would spend now to return `amount`
+        /// of `output_resource`, without trading.
+        pub fn get_amount_in(
+            &self,
+            input_resource: ResourceAddress,
+            output_resource: ResourceAddress,
+            fee: Fee,
+            amount: Amount,
+        ) -> Amount {
+            self.liquidity_pools
+                .existing_liquidity_pool(&Pair::new(input_resource, output_resource), fee)
+                .quote_amount_in(input_resource, output_resource, amount)
+                .0
+        }
+
+        /// Current reserves of the pool of the `pair` with the `fee`, per resource.
+        /// Collected fees are not included.
+        pub fn pool_reserves(&self, pair: Pair, fee: Fee) -> HashMap<ResourceAddress, Amount> {
+            self.liquidity_pools
+                .existing_liquidity_pool(&pair, fee)
+                .reserves_by_resource()
+        }
+
+        /// Amounts `remove_liquidity` of `amount` LP tokens would return now, per resource.
+        pub fn lp_share_value(
+            &self,
+            lp_resource: ResourceAddress,
+            amount: Amount,
+        ) -> HashMap<ResourceAddress, Amount> {
+            self.liquidity_pools
+                .liquidity_pool_by_lp_resource(lp_resource)
+                .lp_share_value(amount)
+        }
+
+        /// Price of each resource in the pool of the `pair` with the `fee` expressed in the other
+        /// one, as a fixed point number with 9 decimal places.
+        pub fn spot_price(&self, pair: Pair, fee: Fee) -> HashMap<ResourceAddress, u64> {
+            self.liquidity_pools
+                .existing_liquidity_pool(&pair, fee)
+                .spot_price()
+        }
+
+        /// Time-weighted average price of each resource of the `pair` in the pool with the `fee`
+        /// over the last `window` epochs, in the same format as `spot_price`.
+        /// Fails if the pool has no activity old enough to cover the `window`.
+        pub fn observe(&self, pair: Pair, fee: Fee, window: u64) -> HashMap<ResourceAddress, u64> {
+            self.liquidity_pools
+                .existing_liquidity_pool(&pair, fee)
+                .observe(window)
+        }
     }
 }
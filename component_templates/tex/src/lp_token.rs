@@ -0,0 +1,128 @@
+//   Copyright 2025. The Tari Project
+//
+//   Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//   following conditions are met:
+//
+//   1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//   disclaimer.
+//
+//   2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//   following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//   3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//   products derived from this software without specific prior written permission.
+//
+//   THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//   INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//   DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//   SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//   SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//   WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//   USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Identity of pools carried by their LP resources, so wallets can tell LP tokens apart
+//! and clients can resolve them back to pools.
+//! Keep metadata keys in sync with `tari_exchange::lp_token`, which decodes them.
+
+use tari_template_abi::rust::collections::HashMap;
+use tari_template_lib::prelude::*;
+
+use crate::Fee;
+
+/// Metadata keys of LP resources, lists are comma separated.
+pub const TEX_COMPONENT: &str = "tex_component";
+pub const POOL_KIND: &str = "pool_kind";
+pub const RESOURCES: &str = "resources";
+pub const FEE_TIER: &str = "fee_tier";
+
+/// Values of `POOL_KIND`.
+pub const CONSTANT_PRODUCT: &str = "constant_product";
+pub const STABLE: &str = "stable";
+pub const CONCENTRATED: &str = "concentrated";
+pub const WEIGHTED: &str = "weighted";
+
+pub const MAX_SYMBOL_LENGTH: usize = 12;
+
+/// Symbol and metadata of the LP resource of a new pool.
+pub struct LpToken {
+    symbol: String,
+    metadata: Vec<(&'static str, String)>,
+}
+
+impl LpToken {
+    /// LP token of a pool of the `kind` trading `resources` with the `fee`, its symbol made of
+    /// symbols of the resources, e.g. `LP-XTR-BTR`, or `CLP-XTR-BTR` for concentrated liquidity
+    /// positions. Resources without a symbol in `symbols` are represented by the beginning
+    /// of their address.
+    pub fn new(
+        kind: &str,
+        resources: &[ResourceAddress],
+        fee: Fee,
+        symbols: &HashMap<ResourceAddress, String>,
+    ) -> Self {
+        let prefix = if kind == CONCENTRATED { "CLP" } else { "LP" };
+        let symbol = resources
+            .iter()
+            .fold(prefix.to_string(), |symbol, resource| {
+                let resource_symbol = symbols
+                    .get(resource)
+                    .cloned()
+                    .unwrap_or_else(|| short_address(resource));
+                format!("{symbol}-{resource_symbol}")
+            });
+        let resources = resources
+            .iter()
+            .map(|resource| resource.to_string())
+            .collect::<Vec<_>>()
+            .join(",");
+        let metadata = vec![
+            (
+                TEX_COMPONENT,
+                CallerContext::current_component_address().to_string(),
+            ),
+            (POOL_KIND, kind.to_string()),
+            (RESOURCES, resources),
+            (FEE_TIER, fee.to_string()),
+        ];
+        LpToken { symbol, metadata }
+    }
+
+    /// Creates a fungible LP resource, owned by the calling component.
+    pub fn fungible(&self) -> ResourceAddress {
+        self.metadata
+            .iter()
+            .fold(
+                ResourceBuilder::fungible().with_token_symbol(&self.symbol),
+                |builder, (key, value)| builder.add_metadata(*key, value),
+            )
+            .build()
+    }
+
+    /// Creates a non-fungible LP resource, owned by the calling component.
+    pub fn non_fungible(&self) -> ResourceAddress {
+        self.metadata
+            .iter()
+            .fold(
+                ResourceBuilder::non_fungible().with_token_symbol(&self.symbol),
+                |builder, (key, value)| builder.add_metadata(*key, value),
+            )
+            .build()
+    }
+}
+
+/// Fails unless the `symbol` can be a part of LP token symbols, which separate parts by dashes.
+pub fn assert_valid_symbol(symbol: &str) {
+    assert!(
+        !symbol.is_empty()
+            && symbol.len() <= MAX_SYMBOL_LENGTH
+            && symbol.chars().all(|c| c.is_ascii_alphanumeric()),
+        "invalid token symbol '{symbol}', 1 to {MAX_SYMBOL_LENGTH} ASCII letters and digits expected"
+    );
+}
+
+/// First 8 hex digits of the `resource` address.
+fn short_address(resource: &ResourceAddress) -> String {
+    let address = resource.to_string();
+    let hex = address.strip_prefix("resource_").unwrap_or(&address);
+    hex.chars().take(8).collect()
+}
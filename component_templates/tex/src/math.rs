@@ -0,0 +1,293 @@
+//   Copyright 2025. The Tari Project
+//
+//   Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//   following conditions are met:
+//
+//   1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//   disclaimer.
+//
+//   2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//   following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//   3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//   products derived from this software without specific prior written permission.
+//
+//   THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//   INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//   DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//   SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//   SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//   WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//   USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Checked integer math of liquidity pools.
+//! Amounts are widened to `i128`, so a product of two amounts never overflows, every other
+//! overflow fails the transaction, and every rounding is done in favour of the pool.
+//! Products of `u128` fixed point numbers are computed in 256 bits by `mul_div`.
+
+use tari_template_lib::prelude::Amount;
+
+use super::Fee;
+
+/// Scale of fixed point prices, i.e. prices have 9 decimal places.
+pub const PRICE_SCALE: i128 = 1_000_000_000;
+
+/// Fees are expressed in hundredths of a basis point, so `FEE_DENOMINATOR` is a 100% fee.
+pub const FEE_DENOMINATOR: i128 = 1_000_000;
+
+pub fn wide(amount: Amount) -> i128 {
+    i128::from(amount.value())
+}
+
+pub fn narrow(value: i128) -> Amount {
+    assert!(value >= 0, "negative amount {value}");
+    Amount::new(i64::try_from(value).unwrap_or_else(|_| panic!("amount {value} overflows")))
+}
+
+pub fn add(a: i128, b: i128) -> i128 {
+    a.checked_add(b)
+        .unwrap_or_else(|| panic!("overflow in {a} + {b}"))
+}
+
+pub fn sub(a: i128, b: i128) -> i128 {
+    a.checked_sub(b)
+        .unwrap_or_else(|| panic!("overflow in {a} - {b}"))
+}
+
+pub fn mul(a: i128, b: i128) -> i128 {
+    a.checked_mul(b)
+        .unwrap_or_else(|| panic!("overflow in {a} * {b}"))
+}
+
+pub fn div_floor(numerator: i128, denominator: i128) -> i128 {
+    assert!(denominator > 0, "division of {numerator} by {denominator}");
+    numerator.div_euclid(denominator)
+}
+
+pub fn div_ceil(numerator: i128, denominator: i128) -> i128 {
+    let quotient = div_floor(numerator, denominator);
+    if numerator.rem_euclid(denominator) == 0 {
+        quotient
+    } else {
+        add(quotient, 1)
+    }
+}
+
+/// Integer square root, rounded down.
+pub fn isqrt(value: i128) -> i128 {
+    assert!(value >= 0, "square root of negative {value}");
+    if value < 2 {
+        return value;
+    }
+    let mut x = value;
+    let mut y = x / 2 + 1;
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
+
+/// `part / total` share of the `amount`, rounded down.
+pub fn share(amount: Amount, part: Amount, total: Amount) -> Amount {
+    narrow(div_floor(mul(wide(amount), wide(part)), wide(total)))
+}
+
+/// Part of the `amount` charged as a fee, `fee` being in hundredths of a basis point. Rounded up.
+pub fn fee_amount(amount: Amount, fee: Fee) -> Amount {
+    narrow(div_ceil(
+        mul(wide(amount), i128::from(fee.get())),
+        FEE_DENOMINATOR,
+    ))
+}
+
+/// Part of collected `fees` taken by the protocol, `share` being in hundredths of a basis point.
+/// Rounded down, so the rounding goes to liquidity providers.
+pub fn protocol_fee_amount(fees: Amount, share: Fee) -> Amount {
+    narrow(div_floor(
+        mul(wide(fees), i128::from(share.get())),
+        FEE_DENOMINATOR,
+    ))
+}
+
+/// Smallest amount which still leaves at least `net_amount` after the `fee` is charged.
+pub fn gross_amount(net_amount: Amount, fee: Fee) -> Amount {
+    let fee_part = i128::from(fee.get());
+    assert!(
+        fee_part < FEE_DENOMINATOR,
+        "exact output swaps are not possible with a 100% fee"
+    );
+    let mut gross = div_ceil(
+        mul(wide(net_amount), FEE_DENOMINATOR),
+        FEE_DENOMINATOR - fee_part,
+    );
+    // rounding the fee up may take one more unit than the estimate above accounts for
+    while wide(narrow(gross)) - wide(fee_amount(narrow(gross), fee)) < wide(net_amount) {
+        gross = add(gross, 1);
+    }
+    narrow(gross)
+}
+
+/// Output of the constant product curve for a `net_input` (after fees), rounded down.
+pub fn amount_out(input_reserve: Amount, output_reserve: Amount, net_input: Amount) -> Amount {
+    narrow(div_floor(
+        mul(wide(net_input), wide(output_reserve)),
+        add(wide(input_reserve), wide(net_input)),
+    ))
+}
+
+/// Net input (before fees) the constant product curve requires for the `output`, rounded up.
+pub fn amount_in(input_reserve: Amount, output_reserve: Amount, output: Amount) -> Amount {
+    let new_output_reserve = sub(wide(output_reserve), wide(output));
+    assert!(
+        new_output_reserve > 0,
+        "pool holds {output_reserve}, can't provide {output}"
+    );
+    let k = mul(wide(input_reserve), wide(output_reserve));
+    narrow(sub(div_ceil(k, new_output_reserve), wide(input_reserve)))
+}
+
+/// Price of the `base` reserve expressed in the `quote` reserve, scaled by `PRICE_SCALE`.
+/// Saturates at `u64::MAX`, so extreme reserve ratios never fail pool operations updating the oracle.
+pub fn price(base_reserve: Amount, quote_reserve: Amount) -> u64 {
+    let price = div_floor(mul(wide(quote_reserve), PRICE_SCALE), wide(base_reserve));
+    u64::try_from(price).unwrap_or(u64::MAX)
+}
+
+/// `a * b / denominator`, rounded down, without overflowing in between.
+pub fn mul_div(a: u128, b: u128, denominator: u128) -> u128 {
+    mul_div_rem(a, b, denominator).0
+}
+
+/// `a * b / denominator`, rounded up, without overflowing in between.
+pub fn mul_div_ceil(a: u128, b: u128, denominator: u128) -> u128 {
+    match mul_div_rem(a, b, denominator) {
+        (quotient, 0) => quotient,
+        (quotient, _) => quotient
+            .checked_add(1)
+            .unwrap_or_else(|| panic!("overflow in {a} * {b} / {denominator}")),
+    }
+}
+
+/// Quotient and remainder of `a * b / denominator`, the product being 256 bits wide.
+fn mul_div_rem(a: u128, b: u128, denominator: u128) -> (u128, u128) {
+    assert!(denominator > 0, "division of {a} * {b} by zero");
+    let (high, low) = widening_mul(a, b);
+    assert!(high < denominator, "overflow in {a} * {b} / {denominator}");
+    // long division, bit by bit, the quotient fits 128 bits since `high < denominator`
+    let mut quotient = 0u128;
+    let mut remainder = high;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= 1;
+        }
+    }
+    (quotient, remainder)
+}
+
+/// Full 256 bit product of `a` and `b`, as `(high, low)` halves.
+fn widening_mul(a: u128, b: u128) -> (u128, u128) {
+    const MASK: u128 = u64::MAX as u128;
+    let (a_high, a_low) = (a >> 64, a & MASK);
+    let (b_high, b_low) = (b >> 64, b & MASK);
+    let low_low = a_low * b_low;
+    let high_low = a_high * b_low;
+    let low_high = a_low * b_high;
+    let high_high = a_high * b_high;
+    let middle = (low_low >> 64) + (high_low & MASK) + (low_high & MASK);
+    let low = (low_low & MASK) | (middle << 64);
+    let high = high_high + (high_low >> 64) + (low_high >> 64) + (middle >> 64);
+    (high, low)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fee(fee: u32) -> Fee {
+        Fee::new(fee).unwrap()
+    }
+
+    #[test]
+    fn divisions_round_towards_their_direction() {
+        assert_eq!(div_floor(7, 2), 3);
+        assert_eq!(div_ceil(7, 2), 4);
+        assert_eq!(div_floor(8, 2), 4);
+        assert_eq!(div_ceil(8, 2), 4);
+        assert_eq!(div_floor(-7, 2), -4);
+        assert_eq!(div_ceil(-7, 2), -3);
+    }
+
+    #[test]
+    fn fees_round_in_favour_of_liquidity_providers() {
+        // 0.3% of 999 is 2.997
+        assert_eq!(fee_amount(Amount::new(999), fee(3_000)), Amount::new(3));
+        assert_eq!(fee_amount(Amount::new(1), fee(1)), Amount::new(1));
+        assert_eq!(fee_amount(Amount::new(1_000), fee(0)), Amount::zero());
+        // 1/6 of 100 is 16.67
+        assert_eq!(
+            protocol_fee_amount(Amount::new(100), fee(166_667)),
+            Amount::new(16)
+        );
+    }
+
+    #[test]
+    fn gross_amount_is_the_smallest_covering_the_fee() {
+        for net in [1, 2, 3, 997, 1_000, 123_456_789] {
+            for fee_part in [0, 1, 3_000, 10_000, 999_999] {
+                let gross = gross_amount(Amount::new(net), fee(fee_part));
+                let net_of = |gross: Amount| gross - fee_amount(gross, fee(fee_part));
+                assert!(net_of(gross) >= Amount::new(net));
+                assert!(net_of(gross - Amount::new(1)) < Amount::new(net));
+            }
+        }
+    }
+
+    #[test]
+    fn constant_product_rounds_in_favour_of_the_pool() {
+        let (input_reserve, output_reserve) = (Amount::new(1_000_003), Amount::new(2_000_017));
+        for input in [1, 7, 1_000, 999_999].map(Amount::new) {
+            let output = amount_out(input_reserve, output_reserve, input);
+            let k = wide(input_reserve) * wide(output_reserve);
+            let new_k = (wide(input_reserve) + wide(input)) * (wide(output_reserve) - wide(output));
+            assert!(new_k >= k);
+            if output.is_positive() {
+                assert!(amount_in(input_reserve, output_reserve, output) <= input);
+            }
+        }
+        for output in [1, 7, 1_000, 1_999_999].map(Amount::new) {
+            let input = amount_in(input_reserve, output_reserve, output);
+            assert!(amount_out(input_reserve, output_reserve, input) >= output);
+        }
+    }
+
+    #[test]
+    fn mul_div_computes_256_bit_products() {
+        assert_eq!(mul_div(u128::MAX, u128::MAX, u128::MAX), u128::MAX);
+        assert_eq!(mul_div(u128::MAX, 3, 6), u128::MAX / 2);
+        assert_eq!(mul_div(1 << 100, 1 << 100, 1 << 120), 1 << 80);
+        assert_eq!(mul_div(10, 10, 3), 33);
+        assert_eq!(mul_div_ceil(10, 10, 3), 34);
+        assert_eq!(mul_div_ceil(10, 9, 3), 30);
+        assert_eq!(
+            mul_div_ceil(u128::MAX - 1, u128::MAX, u128::MAX),
+            u128::MAX - 1
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "overflow")]
+    fn mul_div_fails_on_overflowing_quotients() {
+        mul_div(u128::MAX, 2, 1);
+    }
+
+    #[test]
+    fn price_saturates() {
+        assert_eq!(price(Amount::new(2), Amount::new(1)), 500_000_000);
+        assert_eq!(price(Amount::new(1), Amount::new(i64::MAX)), u64::MAX);
+    }
+}
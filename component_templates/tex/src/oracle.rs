@@ -0,0 +1,310 @@
+//   Copyright 2025. The Tari Project
+//
+//   Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//   following conditions are met:
+//
+//   1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//   disclaimer.
+//
+//   2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//   following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//   3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//   products derived from this software without specific prior written permission.
+//
+//   THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//   INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//   DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//   SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//   SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//   WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//   USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Time-weighted average price oracle of a liquidity pool.
+//! Every pool accumulates its spot prices multiplied by the number of epochs they held,
+//! so the average price over a window is the difference of two accumulators divided by its length.
+//! Accumulators are 128 bits wide, so even saturated prices can't wrap them within a window,
+//! and they wrap around on overflow, differences stay correct.
+//! Keep in sync with `tari_exchange::oracle`, which computes the same off-chain.
+
+use tari_template_lib::template_dependencies::serde::{Deserialize, Serialize};
+
+use crate::math;
+
+const FEE_DENOMINATOR: u128 = math::FEE_DENOMINATOR as u128;
+
+/// Number of observations kept, i.e. how many epochs with pool activity the oracle can look back.
+pub const CAPACITY: usize = 64;
+/// Number of most recent changes of average prices `PriceOracle::volatility` takes the mean of.
+pub const VOLATILITY_WINDOW: usize = 8;
+
+/// Price accumulators of both resources of a pool at the start of an epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Observation {
+    pub epoch: u64,
+    /// Sum of `a` prices in `b`, scaled by `math::PRICE_SCALE`, over all elapsed epochs.
+    pub a_price_cumulative: u128,
+    /// Sum of `b` prices in `a`, scaled by `math::PRICE_SCALE`, over all elapsed epochs.
+    pub b_price_cumulative: u128,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PriceOracle {
+    /// Oldest first, at most one per epoch.
+    observations: Vec<Observation>,
+}
+
+impl Observation {
+    /// Accumulators at `epoch`, given `prices` of `(a, b)` held since this observation.
+    /// An empty pool has no prices and accumulates nothing.
+    fn advanced(&self, epoch: u64, prices: Option<(u64, u64)>) -> Self {
+        let elapsed = epoch - self.epoch;
+        let (a_price, b_price) = prices.unwrap_or_default();
+        Observation {
+            epoch,
+            a_price_cumulative: self
+                .a_price_cumulative
+                .wrapping_add(u128::from(a_price) * u128::from(elapsed)),
+            b_price_cumulative: self
+                .b_price_cumulative
+                .wrapping_add(u128::from(b_price) * u128::from(elapsed)),
+        }
+    }
+
+    /// Accumulators at `epoch` between this observation and the `next` one,
+    /// prices are constant in between, so the interpolation is exact.
+    fn interpolated(&self, next: &Observation, epoch: u64) -> Self {
+        let elapsed = u128::from(epoch - self.epoch);
+        let span = u128::from(next.epoch - self.epoch);
+        let interpolate = |from: u128, to: u128| {
+            let delta = to.wrapping_sub(from);
+            // `delta * elapsed / span` split, so the product can't overflow
+            let delta = delta / span * elapsed + delta % span * elapsed / span;
+            from.wrapping_add(delta)
+        };
+        Observation {
+            epoch,
+            a_price_cumulative: interpolate(self.a_price_cumulative, next.a_price_cumulative),
+            b_price_cumulative: interpolate(self.b_price_cumulative, next.b_price_cumulative),
+        }
+    }
+}
+
+impl PriceOracle {
+    /// Accumulates `prices` of `(a, b)` up to the `epoch`.
+    /// Must be called before every change of reserves, with prices before the change.
+    pub fn update(&mut self, epoch: u64, prices: Option<(u64, u64)>) {
+        let next = match self.observations.last() {
+            None => Observation {
+                epoch,
+                a_price_cumulative: 0,
+                b_price_cumulative: 0,
+            },
+            Some(last) if epoch > last.epoch => last.advanced(epoch, prices),
+            Some(_) => return,
+        };
+        self.observations.push(next);
+        if self.observations.len() > CAPACITY {
+            self.observations.remove(0);
+        }
+    }
+
+    /// Accumulators at the `epoch`, `prices` being current prices of `(a, b)`.
+    pub fn cumulative_at(&self, epoch: u64, prices: Option<(u64, u64)>) -> Observation {
+        let last = self
+            .observations
+            .last()
+            .expect("pool has no price observations yet");
+        if epoch >= last.epoch {
+            return last.advanced(epoch, prices);
+        }
+        let index = self
+            .observations
+            .partition_point(|observation| observation.epoch <= epoch);
+        assert!(
+            index > 0,
+            "epoch {epoch} is older than the oldest price observation at epoch {}",
+            self.observations[0].epoch
+        );
+        self.observations[index - 1].interpolated(&self.observations[index], epoch)
+    }
+
+    /// Mean relative change of the average `a` price from one interval between observations
+    /// to the next, over the last `VOLATILITY_WINDOW` changes up to the `epoch`, in hundredths
+    /// of a basis point like fees. `prices` being current prices of `(a, b)`, the estimate of the `epoch` is the
+    /// same before and after `update`, so quotes match swaps. Zero until prices of two
+    /// intervals between observations can be compared.
+    pub fn volatility(&self, epoch: u64, prices: Option<(u64, u64)>) -> u64 {
+        let Some(last) = self.observations.last() else {
+            return 0;
+        };
+        let current = (epoch > last.epoch).then(|| last.advanced(epoch, prices));
+        let observations: Vec<&Observation> = self.observations.iter().chain(&current).collect();
+        let start = observations.len().saturating_sub(VOLATILITY_WINDOW + 2);
+        let averages: Vec<u128> = observations[start..]
+            .windows(2)
+            .map(|pair| {
+                let elapsed = pair[1].epoch - pair[0].epoch;
+                let sum = pair[1]
+                    .a_price_cumulative
+                    .wrapping_sub(pair[0].a_price_cumulative);
+                sum / u128::from(elapsed)
+            })
+            .collect();
+        let changes: Vec<u128> = averages
+            .windows(2)
+            // an empty pool accumulates no price, there is nothing to compare to
+            .filter(|pair| pair[0] > 0 && pair[1] > 0)
+            .map(|pair| pair[0].abs_diff(pair[1]) * FEE_DENOMINATOR / pair[0])
+            .collect();
+        if changes.is_empty() {
+            return 0;
+        }
+        let mean = changes.iter().sum::<u128>() / changes.len() as u128;
+        u64::try_from(mean).unwrap_or(u64::MAX)
+    }
+
+    /// Average prices of `(a, b)` over `window` epochs up to the `epoch`,
+    /// `prices` being current prices of `(a, b)`.
+    pub fn observe(&self, epoch: u64, window: u64, prices: Option<(u64, u64)>) -> (u64, u64) {
+        assert!(window > 0, "TWAP window must be at least one epoch");
+        let start = epoch
+            .checked_sub(window)
+            .unwrap_or_else(|| panic!("TWAP window {window} is longer than the chain"));
+        let from = self.cumulative_at(start, prices);
+        let to = self.cumulative_at(epoch, prices);
+        (
+            average(from.a_price_cumulative, to.a_price_cumulative, window),
+            average(from.b_price_cumulative, to.b_price_cumulative, window),
+        )
+    }
+}
+
+/// Average price between accumulators `from` and `to` over `window` epochs. Prices are at most
+/// `u64::MAX`, so is their average.
+fn average(from: u128, to: u128, window: u64) -> u64 {
+    u64::try_from(to.wrapping_sub(from) / u128::from(window)).unwrap_or(u64::MAX)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn oracle_of(updates: &[(u64, Option<(u64, u64)>)]) -> PriceOracle {
+        let mut oracle = PriceOracle::default();
+        for (epoch, prices) in updates {
+            oracle.update(*epoch, *prices);
+        }
+        oracle
+    }
+
+    #[test]
+    fn twap_of_a_constant_price_is_the_price() {
+        let prices = Some((2_000_000_000, 500_000_000));
+        let oracle = oracle_of(&[(10, None), (12, prices), (20, prices)]);
+        assert_eq!(oracle.observe(20, 8, prices), (2_000_000_000, 500_000_000));
+        assert_eq!(oracle.observe(25, 5, prices), (2_000_000_000, 500_000_000));
+        // interpolated between observations
+        assert_eq!(oracle.observe(15, 2, prices), (2_000_000_000, 500_000_000));
+    }
+
+    #[test]
+    fn twap_weighs_prices_by_epochs_held() {
+        let oracle = oracle_of(&[(0, None), (1, Some((100, 100))), (4, Some((100, 100)))]);
+        // 100 held for 3 epochs, then 500 for 1
+        assert_eq!(oracle.observe(5, 4, Some((500, 20))), (200, 80));
+    }
+
+    #[test]
+    fn twap_survives_accumulator_wraparound() {
+        let mut oracle = PriceOracle {
+            observations: vec![Observation {
+                epoch: 100,
+                a_price_cumulative: u128::MAX - 1_000,
+                b_price_cumulative: u128::MAX,
+            }],
+        };
+        let prices = Some((u64::MAX / 4, 3_000));
+        oracle.update(102, prices);
+        let last = oracle.observations.last().unwrap();
+        assert!(last.a_price_cumulative < u128::MAX / 2);
+        assert_eq!(oracle.observe(104, 4, prices), (u64::MAX / 4, 3_000));
+        assert_eq!(oracle.observe(101, 1, prices), (u64::MAX / 4, 3_000));
+    }
+
+    #[test]
+    fn twap_of_a_saturated_price_is_the_saturated_price() {
+        // a price held for longer than an epoch no longer fits 64 bits once accumulated
+        let prices = Some((u64::MAX, 0));
+        let oracle = oracle_of(&[(0, None), (1, prices), (4, prices)]);
+        assert_eq!(oracle.observe(9, 8, prices), (u64::MAX, 0));
+        assert_eq!(oracle.observe(3, 2, prices), (u64::MAX, 0));
+        assert_eq!(oracle.volatility(9, prices), 0);
+    }
+
+    #[test]
+    fn oldest_observations_are_dropped() {
+        let prices = Some((1, 1));
+        let mut oracle = PriceOracle::default();
+        for epoch in 0..CAPACITY as u64 + 10 {
+            oracle.update(epoch, prices);
+        }
+        assert_eq!(oracle.observations.len(), CAPACITY);
+        assert_eq!(oracle.observations[0].epoch, 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "older than the oldest price observation")]
+    fn twap_before_the_oldest_observation_fails() {
+        let oracle = oracle_of(&[(10, None)]);
+        oracle.observe(12, 5, Some((1, 1)));
+    }
+
+    #[test]
+    fn volatility_of_a_constant_price_is_zero() {
+        let prices = Some((1_000, 1_000));
+        let oracle = oracle_of(&[(0, None), (1, prices), (3, prices), (4, prices)]);
+        assert_eq!(oracle.volatility(6, prices), 0);
+    }
+
+    #[test]
+    fn volatility_is_the_mean_relative_change() {
+        let oracle = oracle_of(&[
+            (0, None),
+            (1, Some((1_000, 1_000))),
+            (2, Some((1_000, 1_000))),
+            (3, Some((1_100, 909))),
+        ]);
+        // averages 1_000, 1_000, 1_100 and 990: changes of 0%, 10% and 10%
+        assert_eq!(oracle.volatility(4, Some((990, 1_010))), 66_666);
+    }
+
+    #[test]
+    fn volatility_is_the_same_before_and_after_an_update() {
+        let mut oracle = oracle_of(&[
+            (0, None),
+            (1, Some((1_000, 1_000))),
+            (3, Some((1_200, 833))),
+        ]);
+        let prices = Some((900, 1_111));
+        let before = oracle.volatility(5, prices);
+        oracle.update(5, prices);
+        assert_eq!(oracle.volatility(5, Some((1, 1))), before);
+        assert!(before > 0);
+    }
+
+    #[test]
+    fn volatility_survives_accumulator_wraparound() {
+        let mut oracle = PriceOracle {
+            observations: vec![Observation {
+                epoch: 0,
+                a_price_cumulative: u128::MAX - 500,
+                b_price_cumulative: 0,
+            }],
+        };
+        oracle.update(1, Some((1_000, 1_000)));
+        oracle.update(2, Some((1_000, 1_000)));
+        // averages 1_000, 1_000 and 1_500: changes of 0% and 50%
+        assert_eq!(oracle.volatility(3, Some((1_500, 666))), 250_000);
+    }
+}
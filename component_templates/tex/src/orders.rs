@@ -0,0 +1,178 @@
+//   Copyright 2025. The Tari Project
+//
+//   Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//   following conditions are met:
+//
+//   1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//   disclaimer.
+//
+//   2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//   following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//   3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//   products derived from this software without specific prior written permission.
+//
+//   THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//   INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//   DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//   SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//   SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//   WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//   USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Limit orders resting on liquidity pools.
+//! An order sells its input for the other resource of the pair once the pool pays at least its
+//! limit price for it, fees and price impact included. Orders are filled by swapping through
+//! the pool right after swaps moving its price, or when anyone calls `Tex::fill_orders`.
+//! Proceeds wait in the order book until the holder of the order receipt claims them.
+
+use tari_template_abi::rust::collections::{BTreeMap, BTreeSet, HashMap};
+use tari_template_lib::{
+    prelude::*,
+    template_dependencies::serde::{Deserialize, Serialize},
+};
+
+use crate::{math, Pair};
+
+/// Orders filled at a time at most. Every side of the book is checked up to its first order
+/// the pool can't fill, so swaps stay cheap however many orders rest on the pool.
+pub const MAX_FILLS: usize = 16;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Order {
+    pub input_resource: ResourceAddress,
+    pub amount: Amount,
+    /// Least output per unit of input, scaled by `math::PRICE_SCALE`.
+    pub limit_price: u64,
+    /// Output received, `None` while the order is open.
+    pub filled: Option<Amount>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderBook {
+    /// Orders by id, filled ones are kept until claimed.
+    orders: BTreeMap<u64, Order>,
+    /// Ids of open orders by input resource, keyed by `(limit_price, id)`, so the order
+    /// the pool fills most easily comes first, the oldest one among equal limit prices.
+    open: HashMap<ResourceAddress, BTreeSet<(u64, u64)>>,
+    /// Inputs of open orders and outputs of filled ones, one vault per resource of the pair.
+    vaults: HashMap<ResourceAddress, Vault>,
+}
+
+impl Order {
+    /// Least output the order accepts for its whole input, rounded up.
+    pub fn min_output(&self) -> Amount {
+        math::narrow(math::div_ceil(
+            math::mul(math::wide(self.amount), i128::from(self.limit_price)),
+            math::PRICE_SCALE,
+        ))
+    }
+}
+
+impl OrderBook {
+    pub fn new(pair: Pair) -> Self {
+        let resources = [pair.a(), pair.b()];
+        OrderBook {
+            orders: BTreeMap::new(),
+            open: resources
+                .into_iter()
+                .map(|resource| (resource, BTreeSet::new()))
+                .collect(),
+            vaults: resources
+                .into_iter()
+                .map(|resource| (resource, Vault::new_empty(resource)))
+                .collect(),
+        }
+    }
+
+    pub fn order(&self, id: u64) -> &Order {
+        self.orders
+            .get(&id)
+            .unwrap_or_else(|| panic!("order {id} not available"))
+    }
+
+    /// Id of the open order selling the `input_resource` with the lowest limit price,
+    /// the oldest one among equal limit prices.
+    pub fn best_open_order(&self, input_resource: ResourceAddress) -> Option<u64> {
+        self.open.get(&input_resource)?.first().map(|(_, id)| *id)
+    }
+
+    pub fn place(&mut self, id: u64, input: Bucket, limit_price: u64) {
+        assert!(
+            input.amount().is_positive(),
+            "limit order requires a positive amount"
+        );
+        assert!(limit_price > 0, "limit price must be positive");
+        let order = Order {
+            input_resource: input.resource_address(),
+            amount: input.amount(),
+            limit_price,
+            filled: None,
+        };
+        self.vault_mut(input.resource_address()).deposit(input);
+        self.open_mut(order.input_resource)
+            .insert((order.limit_price, id));
+        self.orders.insert(id, order);
+    }
+
+    /// Withdraws the input of the open order with the `id` to be swapped.
+    pub fn take_input(&mut self, id: u64) -> Bucket {
+        let order = self.order(id).clone();
+        self.vault_mut(order.input_resource).withdraw(order.amount)
+    }
+
+    /// Marks the order with the `id` filled by the `output` of its swap.
+    pub fn settle(&mut self, id: u64, output: Bucket) {
+        let amount = output.amount();
+        self.vault_mut(output.resource_address()).deposit(output);
+        let order = self
+            .orders
+            .get_mut(&id)
+            .unwrap_or_else(|| panic!("order {id} not available"));
+        order.filled = Some(amount);
+        let key = (order.limit_price, id);
+        let input_resource = order.input_resource;
+        self.open_mut(input_resource).remove(&key);
+    }
+
+    /// Removes the open order with the `id`, returning it together with its input.
+    pub fn cancel(&mut self, id: u64) -> (Order, Bucket) {
+        let order = self.remove(id);
+        assert!(
+            order.filled.is_none(),
+            "order {id} is already filled, claim it instead"
+        );
+        self.open_mut(order.input_resource)
+            .remove(&(order.limit_price, id));
+        let input = self.vault_mut(order.input_resource).withdraw(order.amount);
+        (order, input)
+    }
+
+    /// Removes the filled order with the `id`, returning it together with its output.
+    pub fn claim(&mut self, id: u64, output_resource: ResourceAddress) -> (Order, Bucket) {
+        let order = self.remove(id);
+        let output_amount = order
+            .filled
+            .unwrap_or_else(|| panic!("order {id} is not filled yet, cancel it instead"));
+        let output = self.vault_mut(output_resource).withdraw(output_amount);
+        (order, output)
+    }
+
+    fn remove(&mut self, id: u64) -> Order {
+        self.orders
+            .remove(&id)
+            .unwrap_or_else(|| panic!("order {id} not available"))
+    }
+
+    fn open_mut(&mut self, resource: ResourceAddress) -> &mut BTreeSet<(u64, u64)> {
+        self.open
+            .get_mut(&resource)
+            .unwrap_or_else(|| panic!("resource {resource} is not traded in this pool"))
+    }
+
+    fn vault_mut(&mut self, resource: ResourceAddress) -> &mut Vault {
+        self.vaults
+            .get_mut(&resource)
+            .unwrap_or_else(|| panic!("resource {resource} is not traded in this pool"))
+    }
+}
@@ -0,0 +1,300 @@
+//   Copyright 2025. The Tari Project
+//
+//   Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//   following conditions are met:
+//
+//   1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//   disclaimer.
+//
+//   2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//   following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//   3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//   products derived from this software without specific prior written permission.
+//
+//   THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//   INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//   DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//   SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//   SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//   WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//   USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! StableSwap curve of pools of pegged resources, e.g. two stablecoins.
+//! Reserves `x` and `y` keep the invariant `D` of
+//! `4A(x + y) + D = 4AD + D^3 / 4xy`, which is close to the constant sum `x + y = D` around
+//! the peg and turns into the constant product far from it. The higher the amplification `A`,
+//! the flatter the curve around the peg. `D` and reserves are solved with Newton's method,
+//! every rounding is done in favour of the pool.
+
+use tari_template_lib::{
+    prelude::*,
+    template_dependencies::serde::{Deserialize, Serialize},
+};
+
+use crate::math::{self, mul_div};
+
+pub const MIN_AMPLIFICATION: u64 = 1;
+pub const MAX_AMPLIFICATION: u64 = 1_000_000;
+/// A single ramp can change the amplification by this factor at most.
+pub const MAX_AMPLIFICATION_CHANGE: u64 = 10;
+
+const MAX_ITERATIONS: usize = 255;
+
+/// Amplification changing linearly from `initial` to `target` between two epochs,
+/// so changing it does not move prices abruptly.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Amplification {
+    initial: u64,
+    initial_epoch: u64,
+    target: u64,
+    target_epoch: u64,
+}
+
+impl Amplification {
+    pub fn new(amplification: u64) -> Self {
+        assert_amplification(amplification);
+        Amplification {
+            initial: amplification,
+            initial_epoch: 0,
+            target: amplification,
+            target_epoch: 0,
+        }
+    }
+
+    /// Amplification at the `epoch`.
+    pub fn at(&self, epoch: u64) -> u64 {
+        if epoch >= self.target_epoch {
+            return self.target;
+        }
+        let elapsed = u128::from(epoch.saturating_sub(self.initial_epoch));
+        let duration = u128::from(self.target_epoch - self.initial_epoch);
+        let (initial, target) = (u128::from(self.initial), u128::from(self.target));
+        let amplification = if target > initial {
+            initial + (target - initial) * elapsed / duration
+        } else {
+            initial - (initial - target) * elapsed / duration
+        };
+        amplification as u64
+    }
+
+    /// Starts changing the amplification from its value at the `epoch` to the `target`,
+    /// reached at the `target_epoch`.
+    pub fn ramp(&mut self, epoch: u64, target: u64, target_epoch: u64) {
+        assert_amplification(target);
+        assert!(
+            target_epoch > epoch,
+            "amplification ramp must end after the current epoch {epoch}"
+        );
+        let initial = self.at(epoch);
+        assert!(
+            target <= initial * MAX_AMPLIFICATION_CHANGE
+                && initial <= target * MAX_AMPLIFICATION_CHANGE,
+            "amplification can change at most {MAX_AMPLIFICATION_CHANGE} times per ramp, from {initial} to {target} requested"
+        );
+        *self = Amplification {
+            initial,
+            initial_epoch: epoch,
+            target,
+            target_epoch,
+        };
+    }
+}
+
+fn assert_amplification(amplification: u64) {
+    assert!(
+        (MIN_AMPLIFICATION..=MAX_AMPLIFICATION).contains(&amplification),
+        "amplification {amplification} is out of range [{MIN_AMPLIFICATION}, {MAX_AMPLIFICATION}]"
+    );
+}
+
+/// Invariant `D` of `x` and `y` reserves, rounded down.
+pub fn invariant(x: Amount, y: Amount, amplification: u64) -> u128 {
+    let (x, y) = (wide(x), wide(y));
+    if x == 0 || y == 0 {
+        return 0;
+    }
+    let ann = 4 * u128::from(amplification);
+    let sum = x + y;
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        // D^3 / 4xy, divided by the smaller reserve first to keep the precision when reserves
+        // are far apart, otherwise Newton's method may not converge
+        let d_product = mul_div(mul_div(d, d, 2 * x.min(y)), d, 2 * x.max(y));
+        let previous = d;
+        d = mul_div(ann * sum + 2 * d_product, d, (ann - 1) * d + 3 * d_product);
+        if d.abs_diff(previous) <= 1 {
+            return d;
+        }
+    }
+    panic!("stable curve invariant of ({x}, {y}) does not converge")
+}
+
+/// Output of the curve for a `net_input` (after fees), rounded down.
+pub fn amount_out(
+    input_reserve: Amount,
+    output_reserve: Amount,
+    net_input: Amount,
+    amplification: u64,
+) -> Amount {
+    let d = invariant(input_reserve, output_reserve, amplification);
+    let new_input_reserve = wide(input_reserve) + wide(net_input);
+    let new_output_reserve = reserve(new_input_reserve, d, amplification);
+    // one more unit stays in the pool to cover the precision of Newton's method
+    narrow(
+        wide(output_reserve)
+            .saturating_sub(new_output_reserve)
+            .saturating_sub(1),
+    )
+}
+
+/// Net input (before fees) the curve requires for the `output`, rounded up.
+pub fn amount_in(
+    input_reserve: Amount,
+    output_reserve: Amount,
+    output: Amount,
+    amplification: u64,
+) -> Amount {
+    let new_output_reserve = wide(output_reserve)
+        .checked_sub(wide(output))
+        .filter(|reserve| *reserve > 0)
+        .unwrap_or_else(|| panic!("pool holds {output_reserve}, can't provide {output}"));
+    let d = invariant(input_reserve, output_reserve, amplification);
+    let new_input_reserve = reserve(new_output_reserve, d, amplification);
+    narrow(new_input_reserve.saturating_sub(wide(input_reserve)) + 1)
+}
+
+/// Price of the `base` reserve expressed in the `quote` reserve at the margin, i.e. the slope
+/// of the curve, scaled by `math::PRICE_SCALE`. Saturates like `math::price`.
+pub fn price(base_reserve: Amount, quote_reserve: Amount, amplification: u64) -> u64 {
+    let d = invariant(base_reserve, quote_reserve, amplification);
+    let (x, y) = (wide(base_reserve), wide(quote_reserve));
+    // (4A * 4xy + D^3 / x) / (4A * 4xy + D^3 / y), both terms divided by D to fit 128 bits
+    let ann = 4 * u128::from(amplification);
+    let common = mul_div(4 * ann * x, y, d);
+    let price = mul_div(
+        common + mul_div(d, d, x),
+        math::PRICE_SCALE as u128,
+        common + mul_div(d, d, y),
+    );
+    u64::try_from(price).unwrap_or(u64::MAX)
+}
+
+/// Reserve balancing the `other` one on the curve of invariant `d`, rounded up.
+fn reserve(other: u128, d: u128, amplification: u64) -> u128 {
+    let ann = 4 * u128::from(amplification);
+    // y^2 + (x + D / 4A - D) y = D^3 / (4A * 4x)
+    let c = mul_div(mul_div(d, d, 2 * other), d, 2 * ann);
+    let b = other + d / ann;
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let previous = y;
+        let denominator = (2 * y + b)
+            .checked_sub(d)
+            .unwrap_or_else(|| panic!("stable curve reserve of {other} for {d} is not defined"));
+        y = mul_div(y, y, denominator) + c / denominator + 1;
+        if y.abs_diff(previous) <= 1 {
+            return y;
+        }
+    }
+    panic!("stable curve reserve of {other} for {d} does not converge")
+}
+
+fn wide(amount: Amount) -> u128 {
+    math::wide(amount) as u128
+}
+
+fn narrow(value: u128) -> Amount {
+    math::narrow(i128::try_from(value).unwrap_or_else(|_| panic!("amount {value} overflows")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const AMPLIFICATIONS: [u64; 3] = [MIN_AMPLIFICATION, 100, MAX_AMPLIFICATION];
+
+    #[test]
+    fn invariant_of_balanced_reserves_is_their_sum() {
+        for amplification in AMPLIFICATIONS {
+            let d = invariant(
+                Amount::new(1_000_000),
+                Amount::new(1_000_000),
+                amplification,
+            );
+            assert!(d.abs_diff(2_000_000) <= 1);
+        }
+    }
+
+    #[test]
+    fn invariant_converges_at_extreme_balances() {
+        let large = Amount::new(i64::MAX / 4);
+        for amplification in AMPLIFICATIONS {
+            for (x, y) in [
+                (Amount::new(1), large),
+                (large, Amount::new(1)),
+                (large, large),
+                (Amount::new(1), Amount::new(1)),
+            ] {
+                let d = invariant(x, y, amplification);
+                // between the constant product and the constant sum
+                assert!(d <= wide(x) + wide(y));
+                assert!(d + 1 >= 2 * math::isqrt(math::wide(x) * math::wide(y)) as u128);
+            }
+        }
+        assert_eq!(invariant(Amount::zero(), large, 100), 0);
+    }
+
+    #[test]
+    fn swaps_never_decrease_the_invariant() {
+        for amplification in AMPLIFICATIONS {
+            for (input_reserve, output_reserve) in [
+                (Amount::new(1_000_000), Amount::new(1_000_000)),
+                (Amount::new(10), Amount::new(1_000_000_000)),
+                (Amount::new(1_000_000_000), Amount::new(10)),
+            ] {
+                let d = invariant(input_reserve, output_reserve, amplification);
+                for amount in [1, 1_000, 999_999].map(Amount::new) {
+                    let output = amount_out(input_reserve, output_reserve, amount, amplification);
+                    let new_d = invariant(
+                        input_reserve + amount,
+                        output_reserve - output,
+                        amplification,
+                    );
+                    assert!(new_d >= d);
+                    if amount >= output_reserve {
+                        continue;
+                    }
+                    let input = amount_in(input_reserve, output_reserve, amount, amplification);
+                    let new_d = invariant(
+                        input_reserve + input,
+                        output_reserve - amount,
+                        amplification,
+                    );
+                    assert!(new_d >= d);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn amplified_curve_is_flat_around_the_peg() {
+        let reserve = Amount::new(1_000_000_000);
+        let output = amount_out(reserve, reserve, Amount::new(1_000_000), MAX_AMPLIFICATION);
+        assert!(output >= Amount::new(999_990));
+        assert!(price(reserve, reserve, 100).abs_diff(math::PRICE_SCALE as u64) <= 1);
+    }
+
+    #[test]
+    fn amplification_ramps_linearly() {
+        let mut amplification = Amplification::new(100);
+        amplification.ramp(10, 1_000, 20);
+        assert_eq!(amplification.at(10), 100);
+        assert_eq!(amplification.at(15), 550);
+        assert_eq!(amplification.at(20), 1_000);
+        assert_eq!(amplification.at(30), 1_000);
+        amplification.ramp(15, 55, 25);
+        assert_eq!(amplification.at(20), 303);
+        assert_eq!(amplification.at(25), 55);
+    }
+}
@@ -0,0 +1,404 @@
+//   Copyright 2025. The Tari Project
+//
+//   Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//   following conditions are met:
+//
+//   1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//   disclaimer.
+//
+//   2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//   following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//   3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//   products derived from this software without specific prior written permission.
+//
+//   THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//   INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//   DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//   SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//   SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//   WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//   USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Fixed point math of concentrated liquidity pools.
+//! Price at tick `i` is `1.0001^i` of `b` per `a`. Pools keep the square root of the price as
+//! a `Q64` fixed point number, i.e. scaled by `2^64`, so amounts of both resources are linear
+//! in liquidity. Products are computed in 256 bits, overflows of results fail the transaction,
+//! and every rounding is done in favour of the pool.
+
+use crate::math::{mul_div, mul_div_ceil, FEE_DENOMINATOR};
+
+/// Ticks are limited to prices between about `2e-9` and `5e8`.
+pub const MIN_TICK: i32 = -200_000;
+pub const MAX_TICK: i32 = 200_000;
+
+/// Fixed point `1.0`.
+pub const Q64: u128 = 1 << 64;
+
+/// Liquidity of a position or a pool can't exceed it, so shifting it by 64 bits never overflows.
+pub const MAX_LIQUIDITY: u128 = i64::MAX as u128;
+
+/// `2^64 / sqrt(1.0001)^(2^i)`, rounded down, for every bit `i` of a tick.
+const TICK_FACTORS: [u128; 18] = [
+    18445821805675392311,
+    18444899583751176498,
+    18443055278223354162,
+    18439367220385604838,
+    18431993317065449817,
+    18417254355718160513,
+    18387811781193591352,
+    18329067761203520168,
+    18212142134806087854,
+    17980523815641551639,
+    17526086738831147013,
+    16651378430235024244,
+    15030750278693429944,
+    12247334978882834399,
+    8131365268884726200,
+    3584323654723342297,
+    696457651847595233,
+    26294789957452057,
+];
+
+/// Square root of the price at the `tick`.
+pub fn sqrt_price_at_tick(tick: i32) -> u128 {
+    assert!(
+        (MIN_TICK..=MAX_TICK).contains(&tick),
+        "tick {tick} is out of range [{MIN_TICK}, {MAX_TICK}]"
+    );
+    let abs_tick = tick.unsigned_abs();
+    let ratio = TICK_FACTORS
+        .iter()
+        .enumerate()
+        .filter(|(bit, _)| abs_tick & (1 << bit) != 0)
+        .fold(Q64, |ratio, (_, factor)| ratio * factor >> 64);
+    if tick > 0 {
+        u128::MAX / ratio
+    } else {
+        ratio
+    }
+}
+
+/// Greatest tick with the square root of the price not above the `sqrt_price`.
+pub fn tick_at_sqrt_price(sqrt_price: u128) -> i32 {
+    assert!(
+        (sqrt_price_at_tick(MIN_TICK)..=sqrt_price_at_tick(MAX_TICK)).contains(&sqrt_price),
+        "square root of price {sqrt_price} is out of range"
+    );
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while low < high {
+        let middle = low + (high - low + 1) / 2;
+        if sqrt_price_at_tick(middle) <= sqrt_price {
+            low = middle;
+        } else {
+            high = middle - 1;
+        }
+    }
+    low
+}
+
+/// Square root of the price of `b_amount` for `a_amount`, rounded down.
+pub fn sqrt_price_of_amounts(a_amount: u128, b_amount: u128) -> u128 {
+    assert!(
+        a_amount > 0 && b_amount > 0,
+        "price is not defined for {b_amount} per {a_amount}"
+    );
+    let min_price = mul_div_ceil(
+        sqrt_price_at_tick(MIN_TICK),
+        sqrt_price_at_tick(MIN_TICK),
+        Q64,
+    );
+    let max_price = mul_div(
+        sqrt_price_at_tick(MAX_TICK),
+        sqrt_price_at_tick(MAX_TICK),
+        Q64,
+    );
+    assert!(
+        b_amount >= mul_div_ceil(a_amount, min_price, Q64)
+            && b_amount <= mul_div(a_amount, max_price, Q64),
+        "price of {b_amount} per {a_amount} is out of range"
+    );
+    // the price in Q96 keeps enough precision for the square root in Q48
+    isqrt(mul_div(b_amount, 1 << 96, a_amount)) << 16
+}
+
+/// Amount of `a` covering `liquidity` between two square roots of prices.
+pub fn amount_a_delta(lower: u128, upper: u128, liquidity: u128, round_up: bool) -> u128 {
+    let (lower, upper) = sorted(lower, upper);
+    let numerator = liquidity << 64;
+    if round_up {
+        div_ceil(mul_div_ceil(numerator, upper - lower, upper), lower)
+    } else {
+        mul_div(numerator, upper - lower, upper) / lower
+    }
+}
+
+/// Amount of `b` covering `liquidity` between two square roots of prices.
+pub fn amount_b_delta(lower: u128, upper: u128, liquidity: u128, round_up: bool) -> u128 {
+    let (lower, upper) = sorted(lower, upper);
+    if round_up {
+        mul_div_ceil(liquidity, upper - lower, Q64)
+    } else {
+        mul_div(liquidity, upper - lower, Q64)
+    }
+}
+
+/// Largest liquidity of the `[lower, upper)` range amounts of `a` and `b` are enough for,
+/// at the current `sqrt_price`.
+pub fn liquidity_for_amounts(
+    sqrt_price: u128,
+    lower: u128,
+    upper: u128,
+    a_amount: u128,
+    b_amount: u128,
+) -> u128 {
+    if sqrt_price <= lower {
+        liquidity_for_a(lower, upper, a_amount)
+    } else if sqrt_price >= upper {
+        liquidity_for_b(lower, upper, b_amount)
+    } else {
+        liquidity_for_a(sqrt_price, upper, a_amount)
+            .min(liquidity_for_b(lower, sqrt_price, b_amount))
+    }
+}
+
+fn liquidity_for_a(lower: u128, upper: u128, a_amount: u128) -> u128 {
+    mul_div(a_amount, mul_div(lower, upper, Q64), upper - lower)
+}
+
+fn liquidity_for_b(lower: u128, upper: u128, b_amount: u128) -> u128 {
+    mul_div(b_amount, Q64, upper - lower)
+}
+
+/// Result of swapping within a single range of constant liquidity.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SwapStep {
+    pub sqrt_price: u128,
+    /// Input spent, fee excluded.
+    pub amount_in: u128,
+    pub amount_out: u128,
+    pub fee_amount: u128,
+}
+
+/// Swaps up to `amount_remaining` of the input, fee included, moving the price from the
+/// `sqrt_price` towards the `target` one, but not past it.
+/// Input is `a` when `a_to_b`, so the `target` is at or below the `sqrt_price`, and `b` otherwise.
+pub fn swap_step(
+    sqrt_price: u128,
+    target: u128,
+    liquidity: u128,
+    amount_remaining: u128,
+    fee: u32,
+    a_to_b: bool,
+) -> SwapStep {
+    assert!(
+        if a_to_b {
+            target <= sqrt_price
+        } else {
+            target >= sqrt_price
+        },
+        "target {target} is on the wrong side of {sqrt_price}"
+    );
+    let fee = u128::from(fee);
+    let denominator = FEE_DENOMINATOR as u128;
+    let amount_less_fee = mul_div(amount_remaining, denominator - fee, denominator);
+    let max_amount_in = if a_to_b {
+        amount_a_delta(target, sqrt_price, liquidity, true)
+    } else {
+        amount_b_delta(sqrt_price, target, liquidity, true)
+    };
+    let (next_sqrt_price, amount_in, fee_amount) = if amount_less_fee >= max_amount_in {
+        let fee_amount = mul_div_ceil(max_amount_in, fee, denominator - fee)
+            .min(amount_remaining - max_amount_in);
+        (target, max_amount_in, fee_amount)
+    } else {
+        let next_sqrt_price = if a_to_b {
+            next_sqrt_price_from_a(sqrt_price, liquidity, amount_less_fee)
+        } else {
+            next_sqrt_price_from_b(sqrt_price, liquidity, amount_less_fee)
+        };
+        // the whole remainder is spent, whatever the price moved by goes to the pool as a fee
+        (
+            next_sqrt_price,
+            amount_less_fee,
+            amount_remaining - amount_less_fee,
+        )
+    };
+    let amount_out = if a_to_b {
+        amount_b_delta(next_sqrt_price, sqrt_price, liquidity, false)
+    } else {
+        amount_a_delta(sqrt_price, next_sqrt_price, liquidity, false)
+    };
+    SwapStep {
+        sqrt_price: next_sqrt_price,
+        amount_in,
+        amount_out,
+        fee_amount,
+    }
+}
+
+/// Square root of the price after `amount` of `a` is added, rounded up.
+fn next_sqrt_price_from_a(sqrt_price: u128, liquidity: u128, amount: u128) -> u128 {
+    if amount == 0 {
+        return sqrt_price;
+    }
+    let numerator = liquidity << 64;
+    div_ceil(numerator, numerator / sqrt_price + amount)
+}
+
+/// Square root of the price after `amount` of `b` is added, rounded down.
+fn next_sqrt_price_from_b(sqrt_price: u128, liquidity: u128, amount: u128) -> u128 {
+    sqrt_price + mul_div(amount, Q64, liquidity)
+}
+
+fn sorted(a: u128, b: u128) -> (u128, u128) {
+    assert!(a > 0, "square root of price must be positive");
+    if a <= b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+fn div_ceil(numerator: u128, denominator: u128) -> u128 {
+    numerator.div_ceil(denominator)
+}
+
+/// Integer square root, rounded down.
+fn isqrt(value: u128) -> u128 {
+    if value < 2 {
+        return value;
+    }
+    let mut x = value;
+    let mut y = x / 2 + 1;
+    while y < x {
+        x = y;
+        y = (x + value / x) / 2;
+    }
+    x
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TICKS: [i32; 11] = [
+        MIN_TICK,
+        MIN_TICK + 1,
+        -100_000,
+        -887,
+        -1,
+        0,
+        1,
+        887,
+        100_000,
+        MAX_TICK - 1,
+        MAX_TICK,
+    ];
+
+    #[test]
+    fn sqrt_price_at_tick_zero_is_one() {
+        assert_eq!(sqrt_price_at_tick(0), Q64);
+    }
+
+    #[test]
+    fn sqrt_prices_increase_with_ticks() {
+        for pair in TICKS.windows(2) {
+            assert!(sqrt_price_at_tick(pair[0]) < sqrt_price_at_tick(pair[1]));
+        }
+    }
+
+    #[test]
+    fn ticks_round_trip_through_sqrt_prices() {
+        for tick in TICKS {
+            let sqrt_price = sqrt_price_at_tick(tick);
+            assert_eq!(tick_at_sqrt_price(sqrt_price), tick);
+            if tick < MAX_TICK {
+                // any price below the next tick belongs to this one
+                let next = sqrt_price_at_tick(tick + 1);
+                assert_eq!(tick_at_sqrt_price(next - 1), tick);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "out of range")]
+    fn ticks_beyond_the_range_are_rejected() {
+        sqrt_price_at_tick(MAX_TICK + 1);
+    }
+
+    #[test]
+    fn sqrt_price_of_amounts_is_rounded_down() {
+        assert_eq!(sqrt_price_of_amounts(1_000, 1_000), Q64);
+        // the square root of 2 is irrational, its fixed point approximation is below it
+        let sqrt_two = sqrt_price_of_amounts(1, 2);
+        assert!(mul_div(sqrt_two, sqrt_two, Q64) <= 2 * Q64);
+        let tick = tick_at_sqrt_price(sqrt_price_of_amounts(1_000_000, 4_000_000));
+        // 1.0001^13863 is 4.0000
+        assert!((13_862..=13_863).contains(&tick));
+    }
+
+    #[test]
+    fn amount_deltas_round_in_favour_of_the_pool() {
+        let (lower, upper) = (sqrt_price_at_tick(-887), sqrt_price_at_tick(1_234));
+        for liquidity in [1, 3, 1_000_007, MAX_LIQUIDITY] {
+            let a_up = amount_a_delta(lower, upper, liquidity, true);
+            let a_down = amount_a_delta(lower, upper, liquidity, false);
+            assert!(a_up >= a_down && a_up - a_down <= 1);
+            let b_up = amount_b_delta(upper, lower, liquidity, true);
+            let b_down = amount_b_delta(upper, lower, liquidity, false);
+            assert!(b_up >= b_down && b_up - b_down <= 1);
+        }
+    }
+
+    #[test]
+    fn liquidity_for_amounts_does_not_exceed_them() {
+        let (lower, upper) = (sqrt_price_at_tick(-600), sqrt_price_at_tick(600));
+        let sqrt_price = sqrt_price_at_tick(100);
+        let liquidity = liquidity_for_amounts(sqrt_price, lower, upper, 1_000_000, 1_000_000);
+        assert!(amount_a_delta(sqrt_price, upper, liquidity, true) <= 1_000_000);
+        assert!(amount_b_delta(lower, sqrt_price, liquidity, true) <= 1_000_000);
+    }
+
+    #[test]
+    fn swap_step_never_spends_more_than_the_remainder() {
+        let sqrt_price = sqrt_price_at_tick(0);
+        let liquidity = 1_000_000_000;
+        for (target, amount_remaining, a_to_b) in [
+            (sqrt_price_at_tick(-10), 1_000, true),
+            (sqrt_price_at_tick(-10), 10_000_000, true),
+            (sqrt_price_at_tick(10), 1_000, false),
+            (sqrt_price_at_tick(10), 10_000_000, false),
+        ] {
+            let step = swap_step(
+                sqrt_price,
+                target,
+                liquidity,
+                amount_remaining,
+                3_000,
+                a_to_b,
+            );
+            assert!(step.amount_in + step.fee_amount <= amount_remaining);
+            assert!(step.fee_amount > 0);
+            // the output is worth less than the input at the price before the swap
+            assert!(step.amount_out <= step.amount_in);
+            if step.sqrt_price != target {
+                assert_eq!(step.amount_in + step.fee_amount, amount_remaining);
+            }
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "wrong side")]
+    fn swap_step_rejects_a_target_against_the_direction() {
+        // a swap of `b` only moves the price up
+        swap_step(
+            sqrt_price_at_tick(10),
+            sqrt_price_at_tick(0),
+            1_000_000_000,
+            1_000,
+            3_000,
+            false,
+        );
+    }
+}
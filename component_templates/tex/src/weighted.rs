@@ -0,0 +1,483 @@
+//   Copyright 2025. The Tari Project
+//
+//   Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//   following conditions are met:
+//
+//   1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//   disclaimer.
+//
+//   2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//   following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//   3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//   products derived from this software without specific prior written permission.
+//
+//   THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//   INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//   DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//   SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//   SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//   WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//   USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Weighted pools of 2 to `MAX_ASSETS` resources.
+//! Value of each reserve is kept at a fixed share of the pool, its weight, so a pool works as
+//! a self-rebalancing index of its assets. Swaps work between any two assets of the pool,
+//! liquidity is added and removed either in all assets proportionally or in a single one.
+//! Fees stay in the reserves, so they accrue to LP tokens without any bookkeeping.
+
+use tari_template_abi::rust::collections::HashMap;
+use tari_template_lib::{
+    prelude::*,
+    template_dependencies::serde::{Deserialize, Serialize},
+};
+
+use crate::{events, lp_token, math, weighted_math, Fee, Treasury, MINIMUM_LIQUIDITY};
+
+pub const MAX_ASSETS: usize = 8;
+
+/// LP supply minted for the initial deposit, `MINIMUM_LIQUIDITY` of it is locked forever.
+const INITIAL_LP_SUPPLY: i128 = 1_000_000_000_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WeightedPool {
+    /// Reserves sorted by resource, the same order as in `Assets`.
+    reserves: Vec<Vault>,
+    /// Weights of reserves, in the same order, scaled by `weighted_math::ONE`.
+    weights: Vec<u128>,
+    lp_resource: ResourceAddress,
+    pub fee: Fee,
+    locked_liquidity: Vault,
+    /// Whether the `Treasury` takes its share of swap fees charged by this pool.
+    pub protocol_fee_on: bool,
+    /// Paused pools accept neither swaps nor joins until the admin unpauses them,
+    /// providers can still exit.
+    pub paused: bool,
+}
+
+/// State of the pool an operation must not make worse, see `WeightedPool::assert_invariant`.
+struct PoolSnapshot {
+    /// Reserves, in the order of `WeightedPool::reserves`.
+    balances: Vec<u128>,
+    lp_supply: u128,
+}
+
+impl WeightedPool {
+    /// Creates a pool of `deposits` with `weights` relative to each other, in the same order,
+    /// e.g. `[80, 20]`. Deposits set initial prices, so they should follow the weights in value.
+    /// Returns the pool together with LP tokens minted for the deposits.
+    pub fn new(
+        deposits: Vec<Bucket>,
+        weights: Vec<u32>,
+        fee: Fee,
+        lp_token: lp_token::LpToken,
+    ) -> (Self, Bucket) {
+        assert!(
+            deposits.len() == weights.len(),
+            "{} weights provided for {} assets",
+            weights.len(),
+            deposits.len()
+        );
+        assert!(
+            fee.get() > 0 && i128::from(fee.get()) < math::FEE_DENOMINATOR,
+            "weighted pools must charge a fee below 100%, got {fee}"
+        );
+        let total_weight: u128 = weights.iter().copied().map(u128::from).sum();
+        let mut assets: Vec<(Bucket, u128)> = deposits
+            .into_iter()
+            .zip(weights)
+            .map(|(deposit, weight)| {
+                let weight = u128::from(weight) * weighted_math::ONE / total_weight.max(1);
+                assert!(
+                    weight >= weighted_math::MIN_WEIGHT,
+                    "weight of {} must be at least 1% of the pool",
+                    deposit.resource_address()
+                );
+                assert!(
+                    deposit.amount().is_positive(),
+                    "initial deposit of {} must be positive",
+                    deposit.resource_address()
+                );
+                (deposit, weight)
+            })
+            .collect();
+        assets.sort_by_key(|(deposit, _)| deposit.resource_address());
+        let lp_resource = lp_token.fungible();
+        let lp_manager = ResourceManager::get(lp_resource);
+        let mut locked_liquidity = Vault::new_empty(lp_resource);
+        locked_liquidity.deposit(lp_manager.mint_fungible(math::narrow(MINIMUM_LIQUIDITY)));
+        let (reserves, weights) = assets
+            .into_iter()
+            .map(|(deposit, weight)| {
+                let mut reserve = Vault::new_empty(deposit.resource_address());
+                reserve.deposit(deposit);
+                (reserve, weight)
+            })
+            .unzip();
+        let pool = WeightedPool {
+            reserves,
+            weights,
+            lp_resource,
+            fee,
+            locked_liquidity,
+            protocol_fee_on: false,
+            paused: false,
+        };
+        let lp_bucket =
+            lp_manager.mint_fungible(math::narrow(INITIAL_LP_SUPPLY - MINIMUM_LIQUIDITY));
+        pool.event(events::WEIGHTED_POOL_CREATED).emit();
+        (pool, lp_bucket)
+    }
+
+    pub fn lp_resource(&self) -> ResourceAddress {
+        self.lp_resource
+    }
+
+    /// Event of the pool, prefilled with the pool identity and reserves at the moment.
+    /// Lists are comma separated, in the order of `resources`.
+    fn event(&self, topic: &'static str) -> events::PoolEvent {
+        events::PoolEvent::new(topic)
+            .with("lp_resource", self.lp_resource)
+            .with("fee_tier", self.fee)
+            .with(
+                "resources",
+                list(
+                    self.reserves
+                        .iter()
+                        .map(|reserve| reserve.resource_address()),
+                ),
+            )
+            .with("weights", list(self.weights.iter()))
+            .with(
+                "reserves",
+                list(self.reserves.iter().map(|reserve| reserve.balance())),
+            )
+    }
+
+    /// Swaps the `input`, the treasury takes its share of the fee if the protocol fee is on,
+    /// the rest of the fee stays in the reserves.
+    pub fn swap(
+        &mut self,
+        mut input: Bucket,
+        output_resource: ResourceAddress,
+        treasury: &mut Treasury,
+    ) -> Bucket {
+        self.assert_not_paused();
+        let snapshot = self.snapshot();
+        let input_resource = input.resource_address();
+        let input_amount = input.amount();
+        let output_amount = self.quote_amount_out(input_resource, input_amount, output_resource);
+        assert!(
+            output_amount.is_positive(),
+            "swap of {input_amount} {input_resource} is too small to return anything"
+        );
+        let fee_amount = math::fee_amount(input_amount, self.fee);
+        let protocol_fee_amount = if self.protocol_fee_on {
+            let mut fees = input.take(fee_amount);
+            let protocol_fee_amount = treasury.collect(&mut fees);
+            input.deposit(fees);
+            protocol_fee_amount
+        } else {
+            Amount::zero()
+        };
+        let input_index = self.index_of(input_resource);
+        let output_index = self.index_of(output_resource);
+        self.reserves[input_index].deposit(input);
+        let output = self.reserves[output_index].withdraw(output_amount);
+        self.assert_invariant(snapshot);
+        self.event(events::WEIGHTED_SWAP)
+            .with("input_resource", input_resource)
+            .with("input_amount", input_amount)
+            .with("output_resource", output_resource)
+            .with("output_amount", output_amount)
+            .with("fee", fee_amount)
+            .with("protocol_fee", protocol_fee_amount)
+            .emit();
+        output
+    }
+
+    /// Output of a `swap` of `input_amount`.
+    pub fn quote_amount_out(
+        &self,
+        input_resource: ResourceAddress,
+        input_amount: Amount,
+        output_resource: ResourceAddress,
+    ) -> Amount {
+        assert!(
+            input_resource != output_resource,
+            "swap requires two different resources, got {input_resource} twice"
+        );
+        let input_index = self.index_of(input_resource);
+        let output_index = self.index_of(output_resource);
+        narrow(weighted_math::amount_out(
+            self.balance(input_index),
+            self.weights[input_index],
+            self.balance(output_index),
+            self.weights[output_index],
+            wide(input_amount),
+            self.fee,
+        ))
+    }
+
+    /// Deposits all assets of the pool in the current ratio of reserves and mints LP tokens for it.
+    /// Returns minted LP tokens together with refunds of deposits above the ratio, sorted by resource.
+    pub fn join(&mut self, mut deposits: Vec<Bucket>) -> (Bucket, Vec<Bucket>) {
+        self.assert_not_paused();
+        deposits.sort_by_key(|deposit| deposit.resource_address());
+        assert!(
+            deposits.len() == self.reserves.len()
+                && deposits
+                    .iter()
+                    .zip(&self.reserves)
+                    .all(|(deposit, reserve)| {
+                        deposit.resource_address() == reserve.resource_address()
+                    }),
+            "joining {} requires a deposit of each of its {} assets",
+            self.lp_resource,
+            self.reserves.len()
+        );
+        let snapshot = self.snapshot();
+        let lp_supply = math::wide(self.lp_supply());
+        let lp_amount = deposits
+            .iter()
+            .zip(&self.reserves)
+            .map(|(deposit, reserve)| {
+                math::div_floor(
+                    math::mul(math::wide(deposit.amount()), lp_supply),
+                    math::wide(reserve.balance()),
+                )
+            })
+            .min()
+            .unwrap_or_default();
+        assert!(
+            lp_amount > 0,
+            "provided liquidity is too small to mint any LP tokens"
+        );
+        let mut amounts = Vec::with_capacity(deposits.len());
+        let refunds = deposits
+            .into_iter()
+            .zip(&mut self.reserves)
+            .map(|(mut deposit, reserve)| {
+                // rounded up, so rounding never dilutes existing providers
+                let amount = math::narrow(math::div_ceil(
+                    math::mul(lp_amount, math::wide(reserve.balance())),
+                    lp_supply,
+                ));
+                reserve.deposit(deposit.take(amount));
+                amounts.push(amount);
+                deposit
+            })
+            .collect();
+        let lp_bucket =
+            ResourceManager::get(self.lp_resource).mint_fungible(math::narrow(lp_amount));
+        self.assert_invariant(snapshot);
+        self.event(events::WEIGHTED_LIQUIDITY_ADDED)
+            .with("amounts", list(amounts.iter()))
+            .with("lp_amount", lp_bucket.amount())
+            .emit();
+        (lp_bucket, refunds)
+    }
+
+    /// Deposits a single asset of the pool and mints LP tokens for it, charging the fee on the
+    /// part of the `input` a proportional join would have to swap for other assets.
+    pub fn join_single(&mut self, input: Bucket) -> Bucket {
+        self.assert_not_paused();
+        let snapshot = self.snapshot();
+        let index = self.index_of(input.resource_address());
+        let lp_amount = narrow(weighted_math::lp_out_for_single_in(
+            self.balance(index),
+            self.weights[index],
+            weighted_math::ONE,
+            wide(self.lp_supply()),
+            wide(input.amount()),
+            self.fee,
+        ));
+        assert!(
+            lp_amount.is_positive(),
+            "provided liquidity is too small to mint any LP tokens"
+        );
+        let amounts = self.single_asset_amounts(index, input.amount());
+        self.reserves[index].deposit(input);
+        let lp_bucket = ResourceManager::get(self.lp_resource).mint_fungible(lp_amount);
+        self.assert_invariant(snapshot);
+        self.event(events::WEIGHTED_LIQUIDITY_ADDED)
+            .with("amounts", list(amounts.iter()))
+            .with("lp_amount", lp_amount)
+            .emit();
+        lp_bucket
+    }
+
+    /// Burns LP tokens of the `lp_bucket`, returning their share of each reserve, sorted by resource.
+    pub fn exit(&mut self, lp_bucket: Bucket) -> Vec<Bucket> {
+        self.assert_lp_resource(&lp_bucket);
+        let snapshot = self.snapshot();
+        let lp_amount = lp_bucket.amount();
+        let lp_supply = self.lp_supply();
+        lp_bucket.burn();
+        let amounts: Vec<Amount> = self
+            .reserves
+            .iter()
+            .map(|reserve| math::share(reserve.balance(), lp_amount, lp_supply))
+            .collect();
+        let outputs = self
+            .reserves
+            .iter_mut()
+            .zip(&amounts)
+            .map(|(reserve, amount)| reserve.withdraw(*amount))
+            .collect();
+        self.assert_invariant(snapshot);
+        self.event(events::WEIGHTED_LIQUIDITY_REMOVED)
+            .with("amounts", list(amounts.iter()))
+            .with("lp_amount", lp_amount)
+            .emit();
+        outputs
+    }
+
+    /// Burns LP tokens of the `lp_bucket`, returning their value in the `output_resource` only,
+    /// charging the fee on the part a proportional exit would have returned in other assets.
+    pub fn exit_single(&mut self, lp_bucket: Bucket, output_resource: ResourceAddress) -> Bucket {
+        self.assert_lp_resource(&lp_bucket);
+        let snapshot = self.snapshot();
+        let index = self.index_of(output_resource);
+        let lp_amount = lp_bucket.amount();
+        let output_amount = narrow(weighted_math::single_out_for_lp_in(
+            self.balance(index),
+            self.weights[index],
+            weighted_math::ONE,
+            wide(self.lp_supply()),
+            wide(lp_amount),
+            self.fee,
+        ));
+        lp_bucket.burn();
+        let output = self.reserves[index].withdraw(output_amount);
+        self.assert_invariant(snapshot);
+        self.event(events::WEIGHTED_LIQUIDITY_REMOVED)
+            .with(
+                "amounts",
+                list(self.single_asset_amounts(index, output_amount).iter()),
+            )
+            .with("lp_amount", lp_amount)
+            .emit();
+        output
+    }
+
+    /// Current reserves, per resource.
+    pub fn balances(&self) -> HashMap<ResourceAddress, Amount> {
+        self.reserves
+            .iter()
+            .map(|reserve| (reserve.resource_address(), reserve.balance()))
+            .collect()
+    }
+
+    /// Price of the `base` resource expressed in the `quote` one, fee excluded,
+    /// as a fixed point number with 9 decimal places.
+    pub fn spot_price(&self, base: ResourceAddress, quote: ResourceAddress) -> u64 {
+        let base_index = self.index_of(base);
+        let quote_index = self.index_of(quote);
+        weighted_math::price(
+            self.balance(base_index),
+            self.weights[base_index],
+            self.balance(quote_index),
+            self.weights[quote_index],
+        )
+    }
+
+    fn index_of(&self, resource: ResourceAddress) -> usize {
+        self.reserves
+            .iter()
+            .position(|reserve| reserve.resource_address() == resource)
+            .unwrap_or_else(|| {
+                panic!(
+                    "resource {resource} is not an asset of weighted pool {}",
+                    self.lp_resource
+                )
+            })
+    }
+
+    fn balance(&self, index: usize) -> u128 {
+        wide(self.reserves[index].balance())
+    }
+
+    fn lp_supply(&self) -> Amount {
+        ResourceManager::get(self.lp_resource).total_supply()
+    }
+
+    /// Amounts of all assets with the `amount` of the asset at the `index` and zeros elsewhere.
+    fn single_asset_amounts(&self, index: usize, amount: Amount) -> Vec<Amount> {
+        let mut amounts = vec![Amount::zero(); self.reserves.len()];
+        amounts[index] = amount;
+        amounts
+    }
+
+    fn snapshot(&self) -> PoolSnapshot {
+        PoolSnapshot {
+            balances: (0..self.reserves.len())
+                .map(|index| self.balance(index))
+                .collect(),
+            lp_supply: wide(self.lp_supply()),
+        }
+    }
+
+    /// Proportional joins and exits must never decrease reserves backing a single LP token,
+    /// while swaps and single asset joins and exits must never decrease the invariant
+    /// `prod(balance_i ^ weight_i)` per LP token.
+    fn assert_invariant(&self, before: PoolSnapshot) {
+        let after = self.snapshot();
+        let backing_holds =
+            before
+                .balances
+                .iter()
+                .zip(&after.balances)
+                .all(|(balance_before, balance_after)| {
+                    balance_after * before.lp_supply >= balance_before * after.lp_supply
+                });
+        assert!(
+            backing_holds
+                || weighted_math::invariant_per_lp_holds(
+                    &before.balances,
+                    &after.balances,
+                    &self.weights,
+                    before.lp_supply,
+                    after.lp_supply,
+                ),
+            "pool invariant violated: reserves {:?} per {} LP became {:?} per {} LP",
+            before.balances,
+            before.lp_supply,
+            after.balances,
+            after.lp_supply
+        );
+    }
+
+    fn assert_not_paused(&self) {
+        assert!(
+            !self.paused,
+            "pool paused: {} allows only liquidity removal",
+            self.lp_resource
+        );
+    }
+
+    fn assert_lp_resource(&self, lp_bucket: &Bucket) {
+        assert!(
+            lp_bucket.resource_address() == self.lp_resource,
+            "invalid lp resource {}, {} expected",
+            lp_bucket.resource_address(),
+            self.lp_resource
+        );
+    }
+}
+
+/// Comma separated list of event values.
+fn list<T: ToString>(values: impl Iterator<Item = T>) -> String {
+    values
+        .map(|value| value.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn wide(amount: Amount) -> u128 {
+    math::wide(amount) as u128
+}
+
+fn narrow(value: u128) -> Amount {
+    math::narrow(i128::try_from(value).unwrap_or_else(|_| panic!("amount {value} overflows")))
+}
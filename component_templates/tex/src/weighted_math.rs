@@ -0,0 +1,459 @@
+//   Copyright 2025. The Tari Project
+//
+//   Redistribution and use in source and binary forms, with or without modification, are permitted provided that the
+//   following conditions are met:
+//
+//   1. Redistributions of source code must retain the above copyright notice, this list of conditions and the following
+//   disclaimer.
+//
+//   2. Redistributions in binary form must reproduce the above copyright notice, this list of conditions and the
+//   following disclaimer in the documentation and/or other materials provided with the distribution.
+//
+//   3. Neither the name of the copyright holder nor the names of its contributors may be used to endorse or promote
+//   products derived from this software without specific prior written permission.
+//
+//   THIS SOFTWARE IS PROVIDED BY THE COPYRIGHT HOLDERS AND CONTRIBUTORS "AS IS" AND ANY EXPRESS OR IMPLIED WARRANTIES,
+//   INCLUDING, BUT NOT LIMITED TO, THE IMPLIED WARRANTIES OF MERCHANTABILITY AND FITNESS FOR A PARTICULAR PURPOSE ARE
+//   DISCLAIMED. IN NO EVENT SHALL THE COPYRIGHT HOLDER OR CONTRIBUTORS BE LIABLE FOR ANY DIRECT, INDIRECT, INCIDENTAL,
+//   SPECIAL, EXEMPLARY, OR CONSEQUENTIAL DAMAGES (INCLUDING, BUT NOT LIMITED TO, PROCUREMENT OF SUBSTITUTE GOODS OR
+//   SERVICES; LOSS OF USE, DATA, OR PROFITS; OR BUSINESS INTERRUPTION) HOWEVER CAUSED AND ON ANY THEORY OF LIABILITY,
+//   WHETHER IN CONTRACT, STRICT LIABILITY, OR TORT (INCLUDING NEGLIGENCE OR OTHERWISE) ARISING IN ANY WAY OUT OF THE
+//   USE OF THIS SOFTWARE, EVEN IF ADVISED OF THE POSSIBILITY OF SUCH DAMAGE.
+
+//! Fixed point math of weighted pools, which keep the value of each reserve at a fixed share
+//! of the pool, i.e. the invariant is `prod(balance_i ^ weight_i)`, see `weighted`.
+//! Ratios and weights are fixed point numbers scaled by `ONE`, balances are plain amounts.
+//! Fractional powers are approximated by a binomial series to `POW_PRECISION`,
+//! so weighted pools must charge a fee, which covers the approximation error.
+//! Trades are limited to a fraction of reserves, keeping power bases in the series' range.
+//! Every step rounds in favour of the pool: outputs and minted LP tokens down, the rest the way
+//! that makes them smaller.
+
+use crate::{
+    math::{self, mul_div},
+    Fee,
+};
+
+/// Fixed point `1.0`.
+pub const ONE: u128 = 1_000_000_000_000_000_000;
+/// Each asset must weigh at least 1% of the pool.
+pub const MIN_WEIGHT: u128 = ONE / 100;
+/// Swaps and single asset joins can't add more than half of the input reserve.
+pub const MAX_IN_RATIO: u128 = ONE / 2;
+/// Single asset exits can't take more than a third of the output reserve.
+pub const MAX_OUT_RATIO: u128 = ONE / 3 + 1;
+
+const POW_PRECISION: u128 = ONE / 10_000_000_000;
+/// Bound of the relative error of `pow`, the series is cut off at `POW_PRECISION`, but its
+/// remainder may add up to more for bases far from one.
+const POW_MAX_ERROR: u128 = 100 * POW_PRECISION;
+
+/// Output of swapping `amount_in` of an asset with the `balance_in` and the `weight_in`
+/// for an asset with the `balance_out` and the `weight_out`, rounded down.
+/// The fee is charged on the input and stays in the pool.
+pub fn amount_out(
+    balance_in: u128,
+    weight_in: u128,
+    balance_out: u128,
+    weight_out: u128,
+    amount_in: u128,
+    fee: Fee,
+) -> u128 {
+    assert!(
+        amount_in <= mul_down(balance_in, MAX_IN_RATIO),
+        "swap of {amount_in} exceeds half of the reserve of {balance_in}"
+    );
+    let adjusted_in = mul_down(amount_in, ONE - fee_ratio(fee));
+    let base = div_up(balance_in, balance_in + adjusted_in);
+    let ratio = ONE.saturating_sub(pow_up(base, div_down(weight_in, weight_out)));
+    mul_down(balance_out, ratio)
+}
+
+/// LP tokens minted for joining with `amount_in` of a single asset of the `weight` out of
+/// the `total_weight`, rounded down. The part of the input which would have to be swapped
+/// for other assets to join proportionally is charged the swap fee.
+pub fn lp_out_for_single_in(
+    balance: u128,
+    weight: u128,
+    total_weight: u128,
+    lp_supply: u128,
+    amount_in: u128,
+    fee: Fee,
+) -> u128 {
+    assert!(
+        amount_in <= mul_down(balance, MAX_IN_RATIO),
+        "join with {amount_in} exceeds half of the reserve of {balance}"
+    );
+    let normalized_weight = div_down(weight, total_weight);
+    let fee_part = mul_up(ONE - normalized_weight, fee_ratio(fee));
+    let amount_in_after_fee = mul_down(amount_in, ONE - fee_part);
+    let ratio = pow_down(
+        div_down(balance + amount_in_after_fee, balance),
+        normalized_weight,
+    );
+    mul_down(lp_supply, ratio.saturating_sub(ONE))
+}
+
+/// Amount of a single asset paid out for `lp_in` LP tokens, rounded down, see
+/// `lp_out_for_single_in`.
+pub fn single_out_for_lp_in(
+    balance: u128,
+    weight: u128,
+    total_weight: u128,
+    lp_supply: u128,
+    lp_in: u128,
+    fee: Fee,
+) -> u128 {
+    // the output share before the fee is at least the LP share, so up to the fee this only
+    // rejects exits the limit below would, before the power series spends gas on them
+    assert!(
+        lp_in <= mul_down(lp_supply, MAX_OUT_RATIO),
+        "exit with {lp_in} exceeds a third of the LP supply of {lp_supply}"
+    );
+    // a heavier weight makes a smaller exponent, and so a smaller output
+    let normalized_weight = div_up(weight, total_weight);
+    let lp_ratio = div_up(lp_supply - lp_in, lp_supply);
+    let balance_ratio = pow_up(lp_ratio, div_down(ONE, normalized_weight));
+    let amount_before_fee = mul_down(balance, ONE.saturating_sub(balance_ratio));
+    let fee_part = mul_up(ONE - normalized_weight, fee_ratio(fee));
+    let amount_out = mul_down(amount_before_fee, ONE - fee_part);
+    assert!(
+        amount_out <= mul_down(balance, MAX_OUT_RATIO),
+        "exit with {amount_out} exceeds a third of the reserve of {balance}"
+    );
+    amount_out
+}
+
+/// Whether the invariant per LP token did not decrease when reserves of `weights` changed from
+/// `balances` to `new_balances` and the LP supply from `lp_supply` to `new_lp_supply`.
+/// Rounded in favour of the change, so the approximation of powers, which the fee covers,
+/// can't reject a valid one.
+pub fn invariant_per_lp_holds(
+    balances: &[u128],
+    new_balances: &[u128],
+    weights: &[u128],
+    lp_supply: u128,
+    new_lp_supply: u128,
+) -> bool {
+    let changes: Vec<(u128, u128, u128)> = balances
+        .iter()
+        .zip(new_balances)
+        .zip(weights)
+        .filter(|((balance, new_balance), _)| balance != new_balance)
+        .map(|((balance, new_balance), weight)| (*balance, *new_balance, *weight))
+        .collect();
+    // `prod((new_i / balance_i) ^ weight_i) >= new_lp_supply / lp_supply` is solved for the most
+    // shrunk reserve, a heavy weight may drain it close to zero, out of the series' range,
+    // while ratios of other reserves are limited to a fraction of themselves
+    let Some(&(balance, new_balance, weight)) = changes
+        .iter()
+        .min_by(|(a, new_a, _), (b, new_b, _)| (new_a * b).cmp(&(new_b * a)))
+    else {
+        return new_lp_supply <= lp_supply;
+    };
+    let required = changes
+        .iter()
+        .filter(|(other, new_other, _)| (*other, *new_other) != (balance, new_balance))
+        .fold(
+            pow_down(div_down(new_lp_supply, lp_supply), div_down(ONE, weight)),
+            |required, (other, new_other, other_weight)| {
+                mul_down(
+                    required,
+                    pow_down(
+                        div_down(*other, *new_other),
+                        div_down(*other_weight, weight),
+                    ),
+                )
+            },
+        );
+    div_up(new_balance, balance) >= required
+}
+
+/// Price of the `base` asset expressed in the `quote` one, fee excluded,
+/// scaled by `math::PRICE_SCALE`. Saturates like `math::price`.
+pub fn price(
+    base_balance: u128,
+    base_weight: u128,
+    quote_balance: u128,
+    quote_weight: u128,
+) -> u64 {
+    let price = mul_div(
+        mul_div(quote_balance, base_weight, quote_weight),
+        math::PRICE_SCALE as u128,
+        base_balance,
+    );
+    u64::try_from(price).unwrap_or(u64::MAX)
+}
+
+fn fee_ratio(fee: Fee) -> u128 {
+    u128::from(fee.get()) * (ONE / math::FEE_DENOMINATOR as u128)
+}
+
+/// `base ^ exponent`, the whole part of the exponent by squaring, the fractional one by
+/// the binomial series.
+/// Rounded down, by more than `pow` may err.
+fn pow_down(base: u128, exponent: u128) -> u128 {
+    let power = pow(base, exponent);
+    power.saturating_sub(mul_up(power, POW_MAX_ERROR) + 1)
+}
+
+/// Rounded up, by more than `pow` may err.
+fn pow_up(base: u128, exponent: u128) -> u128 {
+    let power = pow(base, exponent);
+    power + mul_up(power, POW_MAX_ERROR) + 1
+}
+
+fn pow(base: u128, exponent: u128) -> u128 {
+    assert!(
+        base > 0 && base < 2 * ONE,
+        "power base {base} is out of range (0, 2)"
+    );
+    let whole = exponent / ONE;
+    let remainder = exponent % ONE;
+    let whole_pow = pow_whole(base, whole);
+    if remainder == 0 {
+        return whole_pow;
+    }
+    mul_down(whole_pow, pow_approx(base, remainder))
+}
+
+fn pow_whole(mut base: u128, mut exponent: u128) -> u128 {
+    let mut result = if exponent % 2 != 0 { base } else { ONE };
+    exponent /= 2;
+    while exponent != 0 {
+        base = mul_down(base, base);
+        if exponent % 2 != 0 {
+            result = mul_down(result, base);
+        }
+        exponent /= 2;
+    }
+    result
+}
+
+/// `base ^ exponent` for an `exponent` below one, as the sum of the binomial series
+/// `(1 + x) ^ a = 1 + a x + a (a - 1) x^2 / 2! + ...` with `x = base - 1`.
+fn pow_approx(base: u128, exponent: u128) -> u128 {
+    let (x, x_negative) = sub_sign(base, ONE);
+    let mut term = ONE;
+    let mut sum = ONE;
+    let mut negative = false;
+    let mut k = 1;
+    while term >= POW_PRECISION {
+        let big_k = k * ONE;
+        let (c, c_negative) = sub_sign(exponent, big_k - ONE);
+        term = div_down(mul_down(term, mul_down(c, x)), big_k);
+        if term == 0 {
+            break;
+        }
+        negative ^= x_negative ^ c_negative;
+        if negative {
+            sum = sum
+                .checked_sub(term)
+                .unwrap_or_else(|| panic!("power series of {base} ^ {exponent} diverges"));
+        } else {
+            sum += term;
+        }
+        k += 1;
+    }
+    sum
+}
+
+fn sub_sign(a: u128, b: u128) -> (u128, bool) {
+    if a >= b {
+        (a - b, false)
+    } else {
+        (b - a, true)
+    }
+}
+
+/// Product of fixed point numbers, rounded down.
+fn mul_down(a: u128, b: u128) -> u128 {
+    product(a, b) / ONE
+}
+
+/// Product of fixed point numbers, rounded up.
+fn mul_up(a: u128, b: u128) -> u128 {
+    product(a, b).div_ceil(ONE)
+}
+
+/// Quotient of fixed point numbers, rounded down.
+fn div_down(a: u128, b: u128) -> u128 {
+    numerator(a, b) / b
+}
+
+/// Quotient of fixed point numbers, rounded up.
+fn div_up(a: u128, b: u128) -> u128 {
+    numerator(a, b).div_ceil(b)
+}
+
+fn product(a: u128, b: u128) -> u128 {
+    a.checked_mul(b)
+        .unwrap_or_else(|| panic!("overflow in {a} * {b}"))
+}
+
+fn numerator(a: u128, b: u128) -> u128 {
+    assert!(b > 0, "division of {a} by zero");
+    a.checked_mul(ONE)
+        .unwrap_or_else(|| panic!("overflow in {a} / {b}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fee(fee: u32) -> Fee {
+        Fee::new(fee).unwrap()
+    }
+
+    fn assert_close(actual: u128, expected: u128, tolerance: u128) {
+        assert!(
+            actual.abs_diff(expected) <= tolerance,
+            "{actual} differs from {expected} by more than {tolerance}"
+        );
+    }
+
+    #[test]
+    fn pow_of_whole_exponents_is_exact() {
+        assert_eq!(pow(ONE / 2, 0), ONE);
+        assert_eq!(pow(ONE / 2, 3 * ONE), ONE / 8);
+        assert_eq!(pow(3 * ONE / 2, 2 * ONE), 9 * ONE / 4);
+    }
+
+    #[test]
+    fn pow_of_fractional_exponents_is_accurate() {
+        // 0.5^0.5, 0.75^0.25, 1.5^0.5, 0.9^1.5 and 0.6^(1/0.2) to 18 decimals
+        for (base, exponent, expected) in [
+            (ONE / 2, ONE / 2, 707_106_781_186_547_524),
+            (3 * ONE / 4, ONE / 4, 930_604_859_102_099_616),
+            (3 * ONE / 2, ONE / 2, 1_224_744_871_391_589_049),
+            (9 * ONE / 10, 3 * ONE / 2, 853_814_968_245_462_314),
+            (6 * ONE / 10, 5 * ONE, 77_760_000_000_000_000),
+        ] {
+            assert_close(pow(base, exponent), expected, POW_PRECISION);
+        }
+    }
+
+    #[test]
+    fn equal_weights_swap_like_constant_product() {
+        let weight = ONE / 2;
+        let output = amount_out(1_000_000, weight, 2_000_000, weight, 10_000, fee(0));
+        // 2_000_000 * 10_000 / 1_010_000 is 19_801.98
+        assert_close(output, 19_801, 1);
+        assert!(output <= 19_801);
+    }
+
+    #[test]
+    fn heavier_input_weight_pays_more() {
+        let output = amount_out(1_000_000, 4 * ONE / 5, 1_000_000, ONE / 5, 10_000, fee(0));
+        // 1_000_000 * (1 - (1_000_000 / 1_010_000)^4) is 39_019.66
+        assert_close(output, 39_019, 1);
+        let with_fee = amount_out(
+            1_000_000,
+            4 * ONE / 5,
+            1_000_000,
+            ONE / 5,
+            10_000,
+            fee(3_000),
+        );
+        assert!(with_fee < output);
+    }
+
+    #[test]
+    fn single_asset_join_and_exit_do_not_profit() {
+        let (balance, weight, total_weight, lp_supply) = (1_000_000, ONE / 5, ONE, 1_000_000);
+        let lp_out =
+            lp_out_for_single_in(balance, weight, total_weight, lp_supply, 100_000, fee(0));
+        let amount_out = single_out_for_lp_in(
+            balance + 100_000,
+            weight,
+            total_weight,
+            lp_supply + lp_out,
+            lp_out,
+            fee(0),
+        );
+        assert!(amount_out <= 100_000);
+        // minted LP tokens are rounded down, each is worth about 5 units of the asset
+        assert_close(amount_out, 100_000, 6);
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds half of the reserve")]
+    fn swaps_are_limited_to_half_of_the_input_reserve() {
+        amount_out(1_000_000, ONE / 2, 1_000_000, ONE / 2, 500_001, fee(0));
+    }
+
+    #[test]
+    #[should_panic(expected = "exceeds a third of the LP supply")]
+    fn exits_are_limited_before_the_power_series() {
+        single_out_for_lp_in(1_000_000, ONE / 2, ONE, 1_000_000, 400_000, fee(0));
+    }
+
+    #[test]
+    fn swaps_never_decrease_the_invariant() {
+        let balances = [1_000_000, 3_000_000];
+        for weights in [
+            [ONE / 2, ONE / 2],
+            [4 * ONE / 5, ONE / 5],
+            [ONE / 100, 99 * ONE / 100],
+            [99 * ONE / 100, ONE / 100],
+        ] {
+            for amount_in in [1, 1_000, 100_000, 500_000] {
+                for fee in [fee(1), fee(3_000)] {
+                    let output = amount_out(
+                        balances[0],
+                        weights[0],
+                        balances[1],
+                        weights[1],
+                        amount_in,
+                        fee,
+                    );
+                    let new_balances = [balances[0] + amount_in, balances[1] - output];
+                    assert!(invariant_per_lp_holds(
+                        &balances,
+                        &new_balances,
+                        &weights,
+                        1_000,
+                        1_000
+                    ));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn invariant_rejects_draining_the_pool() {
+        let (balances, weights) = ([1_000_000, 1_000_000], [ONE / 2, ONE / 2]);
+        // 10_000 in is worth 9_900.99 out without a fee
+        let holds = |output: u128| {
+            invariant_per_lp_holds(
+                &balances,
+                &[1_010_000, 1_000_000 - output],
+                &weights,
+                1_000,
+                1_000,
+            )
+        };
+        assert!(holds(9_900));
+        assert!(!holds(9_910));
+        // a single asset join minting more LP tokens than it adds value
+        let lp_out = lp_out_for_single_in(1_000_000, ONE / 2, ONE, 1_000_000, 10_000, fee(3_000));
+        let joined = |lp_out: u128| {
+            invariant_per_lp_holds(
+                &balances,
+                &[1_010_000, 1_000_000],
+                &weights,
+                1_000_000,
+                1_000_000 + lp_out,
+            )
+        };
+        assert!(joined(lp_out));
+        assert!(!joined(lp_out + 100));
+    }
+
+    #[test]
+    fn price_accounts_for_weights() {
+        assert_eq!(price(1_000, ONE / 2, 2_000, ONE / 2), 2_000_000_000);
+        assert_eq!(price(1_000, 4 * ONE / 5, 1_000, ONE / 5), 4_000_000_000);
+        assert_eq!(price(1, ONE / 2, u128::from(u64::MAX), ONE / 2), u64::MAX);
+    }
+}
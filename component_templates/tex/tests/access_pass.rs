@@ -0,0 +1,217 @@
+mod support;
+
+use support::{TexTest, User, FEE};
+use tari_template_lib::{
+    args,
+    models::{ComponentAddress, ResourceAddress},
+    prelude::Amount,
+};
+use tari_transaction::{Transaction, TransactionBuilder};
+
+const SWAP: i64 = 1_000;
+const LIQUIDITY: i64 = 10_000;
+
+/// Adds a swap of `SWAP` of `a` to `b` on behalf of the `user` to the `builder`.
+fn swap(tex_test: &TexTest, builder: TransactionBuilder, user: &User) -> TransactionBuilder {
+    builder
+        .call_method(
+            tex_test.coin_of(tex_test.a),
+            "take_free_coins",
+            args![Amount::new(SWAP)],
+        )
+        .put_last_instruction_output_on_workspace("input")
+        .call_method(
+            tex_test.tex,
+            "swap",
+            args![Workspace("input"), tex_test.b, FEE],
+        )
+        .put_last_instruction_output_on_workspace("output")
+        .call_method(user.account, "deposit", args![Workspace("output")])
+}
+
+/// Adds taking a proof of the `pass` of the `member` to the `builder`,
+/// calls that follow are made with it until the proof is dropped.
+fn with_pass(
+    builder: TransactionBuilder,
+    member: &User,
+    pass: ResourceAddress,
+) -> TransactionBuilder {
+    builder
+        .call_method(member.account, "create_proof_for_resource", args![pass])
+        .put_last_instruction_output_on_workspace("pass_proof")
+}
+
+/// Adds a join of a weighted pool of the coins with `SWAP` of `a` to the `builder`,
+/// its LP tokens left on the workspace as `joined`.
+fn join_weighted_pool(tex_test: &TexTest, builder: TransactionBuilder) -> TransactionBuilder {
+    builder
+        .call_method(
+            tex_test.coin_of(tex_test.a),
+            "take_free_coins",
+            args![Amount::new(SWAP)],
+        )
+        .put_last_instruction_output_on_workspace("input")
+        .call_method(
+            tex_test.tex,
+            "join_weighted_pool_single",
+            args![
+                Workspace("input"),
+                vec![tex_test.a, tex_test.b],
+                FEE,
+                Amount::new(1)
+            ],
+        )
+        .put_last_instruction_output_on_workspace("joined")
+}
+
+/// Sets up an exchange gated by a new pass resource, held by the returned member only.
+fn gated_exchange() -> (TexTest, ResourceAddress, User) {
+    let mut tex_test = TexTest::new();
+    let pass_coin = tex_test.test.call_function::<ComponentAddress>(
+        "Coin",
+        "new",
+        args![Amount::new(10), "TEXPASS"],
+        vec![],
+    );
+    let pass =
+        tex_test
+            .test
+            .call_method::<ResourceAddress>(pass_coin, "vault_address", args![], vec![]);
+    let member = tex_test.new_user();
+    tex_test.test.execute_expect_success(
+        Transaction::builder()
+            .call_method(pass_coin, "take_free_coins", args![Amount::new(1)])
+            .put_last_instruction_output_on_workspace("pass")
+            .call_method(member.account, "deposit", args![Workspace("pass")])
+            .build_and_seal(&member.secret_key),
+        vec![member.owner_proof.clone()],
+    );
+    tex_test.admin_call("set_access_pass", args![Some(pass)]);
+    (tex_test, pass, member)
+}
+
+#[test]
+fn caller_without_access_pass_is_rejected() {
+    let (mut tex_test, _, _) = gated_exchange();
+    let outsider = tex_test.new_user();
+    let transaction =
+        swap(&tex_test, Transaction::builder(), &outsider).build_and_seal(&outsider.secret_key);
+    tex_test
+        .test
+        .execute_expect_failure(transaction, vec![outsider.owner_proof.clone()]);
+}
+
+#[test]
+fn caller_with_access_pass_trades() {
+    let (mut tex_test, pass, member) = gated_exchange();
+    let builder = Transaction::builder()
+        .call_method(member.account, "create_proof_for_resource", args![pass])
+        .put_last_instruction_output_on_workspace("pass_proof");
+    let transaction = swap(&tex_test, builder, &member)
+        .drop_all_proofs_in_workspace()
+        .build_and_seal(&member.secret_key);
+    tex_test
+        .test
+        .execute_expect_success(transaction, vec![member.owner_proof.clone()]);
+}
+
+#[test]
+fn admin_methods_stay_reachable_with_admin_badge() {
+    let (mut tex_test, _, member) = gated_exchange();
+    tex_test.admin_call("set_paused", args![true]);
+    tex_test.admin_call("set_paused", args![false]);
+    tex_test.admin_call("set_access_pass", args![None::<ResourceAddress>]);
+    // the pass does not grant admin access
+    let transaction = Transaction::builder()
+        .call_method(tex_test.tex, "set_paused", args![true])
+        .build_and_seal(&member.secret_key);
+    tex_test
+        .test
+        .execute_expect_failure(transaction, vec![member.owner_proof.clone()]);
+}
+
+#[test]
+fn lifting_access_pass_opens_trading() {
+    let (mut tex_test, _, _) = gated_exchange();
+    tex_test.admin_call("set_access_pass", args![None::<ResourceAddress>]);
+    let outsider = tex_test.new_user();
+    let transaction =
+        swap(&tex_test, Transaction::builder(), &outsider).build_and_seal(&outsider.secret_key);
+    tex_test
+        .test
+        .execute_expect_success(transaction, vec![outsider.owner_proof.clone()]);
+}
+
+#[test]
+fn zap_out_requires_access_pass() {
+    let (mut tex_test, pass, member) = gated_exchange();
+    let (tex, a) = (tex_test.tex, tex_test.a);
+    let add_liquidity = |tex_test: &TexTest| {
+        tex_test
+            .take_coins(with_pass(Transaction::builder(), &member, pass), LIQUIDITY)
+            .call_method(
+                tex,
+                "add_liquidity",
+                args![Workspace("coin_0"), Workspace("coin_1"), FEE],
+            )
+            .put_last_instruction_output_on_workspace("added")
+            .call_method(member.account, "deposit", args![Workspace("added.1")])
+            .drop_all_proofs_in_workspace()
+    };
+    let builder = add_liquidity(&tex_test)
+        .call_method(
+            tex,
+            "zap_out",
+            args![Workspace("added.0"), a, Amount::new(1)],
+        )
+        .put_last_instruction_output_on_workspace("output")
+        .call_method(member.account, "deposit", args![Workspace("output")]);
+    tex_test.execute_expect_failure(builder, &member);
+    // removing liquidity in both resources is never gated
+    let builder = add_liquidity(&tex_test)
+        .call_method(tex, "remove_liquidity", args![Workspace("added.0")])
+        .put_last_instruction_output_on_workspace("removed")
+        .call_method(member.account, "deposit_all", args![Workspace("removed")]);
+    tex_test.execute(builder, &member);
+}
+
+#[test]
+fn single_asset_weighted_exit_requires_access_pass() {
+    let (mut tex_test, pass, member) = gated_exchange();
+    let (tex, a) = (tex_test.tex, tex_test.a);
+    let builder = tex_test
+        .take_coins(with_pass(Transaction::builder(), &member, pass), LIQUIDITY)
+        .call_method(
+            tex,
+            "add_liquidity",
+            args![Workspace("coin_0"), Workspace("coin_1"), FEE],
+        )
+        .put_last_instruction_output_on_workspace("added")
+        .call_method(member.account, "deposit", args![Workspace("added.1")])
+        .call_method(tex, "remove_liquidity", args![Workspace("added.0")])
+        .put_last_instruction_output_on_workspace("coins")
+        .call_method(
+            tex,
+            "create_weighted_pool",
+            args![Workspace("coins"), vec![80u32, 20u32], FEE],
+        )
+        .put_last_instruction_output_on_workspace("lp")
+        .call_method(member.account, "deposit", args![Workspace("lp")]);
+    // exiting in all assets is never gated
+    let builder = join_weighted_pool(&tex_test, builder)
+        .drop_all_proofs_in_workspace()
+        .call_method(tex, "exit_weighted_pool", args![Workspace("joined")])
+        .put_last_instruction_output_on_workspace("exited")
+        .call_method(member.account, "deposit_all", args![Workspace("exited")]);
+    tex_test.execute(builder, &member);
+    let builder = join_weighted_pool(&tex_test, with_pass(Transaction::builder(), &member, pass))
+        .drop_all_proofs_in_workspace()
+        .call_method(
+            tex,
+            "exit_weighted_pool_single",
+            args![Workspace("joined"), a, Amount::new(1)],
+        )
+        .put_last_instruction_output_on_workspace("output")
+        .call_method(member.account, "deposit", args![Workspace("output")]);
+    tex_test.execute_expect_failure(builder, &member);
+}
@@ -0,0 +1,221 @@
+mod support;
+
+use std::collections::HashMap;
+
+use support::{TexTest, User, FEE, POOL_LIQUIDITY};
+use tari_template_lib::{args, args::Arg, models::ResourceAddress, prelude::Amount};
+use tari_transaction::{Transaction, TransactionBuilder};
+
+/// Fee tier not enabled by `TexTest`, 0.05%.
+const OTHER_FEE: u32 = 500;
+const SWAP: i64 = 1_000;
+
+/// Transaction of the `user` swapping `SWAP` of `a` to `b` in the pool with the `fee`.
+fn swap(tex_test: &TexTest, user: &User, fee: u32) -> TransactionBuilder {
+    Transaction::builder()
+        .call_method(
+            tex_test.coin_of(tex_test.a),
+            "take_free_coins",
+            args![Amount::new(SWAP)],
+        )
+        .put_last_instruction_output_on_workspace("input")
+        .call_method(
+            tex_test.tex,
+            "swap",
+            args![Workspace("input"), tex_test.b, fee],
+        )
+        .put_last_instruction_output_on_workspace("output")
+        .call_method(user.account, "deposit", args![Workspace("output")])
+}
+
+/// Transaction of the `user` adding `SWAP` of both coins to the pool with the `fee`.
+fn add_liquidity(tex_test: &TexTest, user: &User, fee: u32) -> TransactionBuilder {
+    tex_test
+        .take_coins(Transaction::builder(), SWAP)
+        .call_method(
+            tex_test.tex,
+            "add_liquidity",
+            args![Workspace("coin_0"), Workspace("coin_1"), fee],
+        )
+        .put_last_instruction_output_on_workspace("added")
+        .call_method(user.account, "deposit_all", args![Workspace("added")])
+}
+
+/// Transaction of the admin adding `SWAP` of both coins to the pool with the `fee`, calling
+/// the admin `method` of Tex and removing the added liquidity after it.
+fn add_call_and_remove(
+    tex_test: &TexTest,
+    fee: u32,
+    method: &str,
+    args: Vec<Arg>,
+) -> TransactionBuilder {
+    let (tex, admin_account) = (tex_test.tex, tex_test.admin.account);
+    tex_test
+        .take_coins(tex_test.with_admin_proof(Transaction::builder()), SWAP)
+        .call_method(
+            tex,
+            "add_liquidity",
+            args![Workspace("coin_0"), Workspace("coin_1"), fee],
+        )
+        .put_last_instruction_output_on_workspace("added")
+        .call_method(tex, method, args)
+        .call_method(tex, "remove_liquidity", args![Workspace("added.0")])
+        .put_last_instruction_output_on_workspace("removed")
+        .call_method(admin_account, "deposit", args![Workspace("added.1")])
+        .call_method(admin_account, "deposit_all", args![Workspace("removed")])
+        .drop_all_proofs_in_workspace()
+}
+
+#[test]
+fn admin_methods_require_the_admin_badge() {
+    let mut tex_test = TexTest::new();
+    let outsider = tex_test.new_user();
+    let builder =
+        Transaction::builder().call_method(tex_test.tex, "add_fee_tier", args![OTHER_FEE]);
+    tex_test.execute_expect_failure(builder, &outsider);
+    let pair = tex_test.pair();
+    let builder = Transaction::builder().call_method(tex_test.tex, "delist_pool", args![pair, FEE]);
+    tex_test.execute_expect_failure(builder, &outsider);
+    tex_test.admin_call("add_fee_tier", args![OTHER_FEE]);
+}
+
+#[test]
+fn delisted_pool_only_allows_withdrawals() {
+    let mut tex_test = TexTest::new();
+    let pair = tex_test.pair();
+    let builder = add_call_and_remove(&tex_test, FEE, "delist_pool", args![pair, FEE]);
+    tex_test.execute_as_admin(builder);
+    let trader = tex_test.new_user();
+    let builder = swap(&tex_test, &trader, FEE);
+    tex_test.execute_expect_failure(builder, &trader);
+    let builder = add_liquidity(&tex_test, &trader, FEE);
+    tex_test.execute_expect_failure(builder, &trader);
+}
+
+#[test]
+fn paused_exchange_lets_providers_withdraw() {
+    let mut tex_test = TexTest::new();
+    let builder = add_call_and_remove(&tex_test, FEE, "set_paused", args![true]);
+    tex_test.execute_as_admin(builder);
+    let trader = tex_test.new_user();
+    let builder = swap(&tex_test, &trader, FEE);
+    tex_test.execute_expect_failure(builder, &trader);
+    let builder = add_liquidity(&tex_test, &trader, FEE);
+    tex_test.execute_expect_failure(builder, &trader);
+    tex_test.admin_call("set_paused", args![false]);
+    let builder = swap(&tex_test, &trader, FEE);
+    tex_test.execute(builder, &trader);
+}
+
+#[test]
+fn paused_pool_does_not_stop_other_pools() {
+    let mut tex_test = TexTest::new();
+    tex_test.admin_call("add_fee_tier", args![OTHER_FEE]);
+    tex_test.add_liquidity(OTHER_FEE, POOL_LIQUIDITY);
+    let pair = tex_test.pair();
+    let builder = add_call_and_remove(
+        &tex_test,
+        OTHER_FEE,
+        "set_pool_paused",
+        args![pair, OTHER_FEE, true],
+    );
+    tex_test.execute_as_admin(builder);
+    let trader = tex_test.new_user();
+    let builder = swap(&tex_test, &trader, OTHER_FEE);
+    tex_test.execute_expect_failure(builder, &trader);
+    let builder = swap(&tex_test, &trader, FEE);
+    tex_test.execute(builder, &trader);
+}
+
+#[test]
+fn fee_tiers_limit_new_pools() {
+    let mut tex_test = TexTest::new();
+    let provider = tex_test.new_user();
+    // no pool can be created with a fee outside of the tiers
+    tex_test.admin_call_expect_failure("remove_fee_tier", args![OTHER_FEE]);
+    let builder = add_liquidity(&tex_test, &provider, OTHER_FEE);
+    tex_test.execute_expect_failure(builder, &provider);
+    tex_test.admin_call("add_fee_tier", args![OTHER_FEE]);
+    let builder = add_liquidity(&tex_test, &provider, OTHER_FEE);
+    tex_test.execute(builder, &provider);
+    // existing pools keep working without their tier
+    tex_test.admin_call("remove_fee_tier", args![FEE]);
+    let builder = swap(&tex_test, &provider, FEE);
+    tex_test.execute(builder, &provider);
+    let tiers = tex_test
+        .test
+        .call_method::<Vec<u32>>(tex_test.tex, "fee_tiers", args![], vec![]);
+    assert_eq!(tiers, vec![OTHER_FEE]);
+}
+
+#[test]
+fn protocol_fees_are_withdrawn_by_the_admin_only() {
+    let mut tex_test = TexTest::new();
+    let (tex, pair, a) = (tex_test.tex, tex_test.pair(), tex_test.a);
+    let trader = tex_test.new_user();
+    // the whole swap fee goes to the treasury
+    tex_test.admin_call("set_protocol_fee_share", args![1_000_000u32]);
+    tex_test.admin_call("set_protocol_fee", args![pair, FEE, true]);
+    let builder = swap(&tex_test, &trader, FEE);
+    tex_test.execute(builder, &trader);
+    let treasury = tex_test
+        .test
+        .call_method::<HashMap<ResourceAddress, Amount>>(tex, "treasury_balances", args![], vec![]);
+    // 0.3% of 1_000
+    assert_eq!(treasury[&a], Amount::new(3));
+    let builder = Transaction::builder()
+        .call_method(tex, "withdraw_treasury", args![a, Amount::new(3)])
+        .put_last_instruction_output_on_workspace("withdrawn")
+        .call_method(trader.account, "deposit", args![Workspace("withdrawn")]);
+    tex_test.execute_expect_failure(builder, &trader);
+    let admin_account = tex_test.admin.account;
+    let builder = tex_test
+        .with_admin_proof(Transaction::builder())
+        .call_method(tex, "withdraw_treasury", args![a, Amount::new(3)])
+        .put_last_instruction_output_on_workspace("withdrawn")
+        .call_method(admin_account, "deposit", args![Workspace("withdrawn")])
+        .drop_all_proofs_in_workspace();
+    tex_test.execute_as_admin(builder);
+}
+
+#[test]
+fn listing_policy_gates_new_pools() {
+    let mut tex_test = TexTest::new();
+    let (a, b) = (tex_test.a, tex_test.b);
+    let creator = tex_test.new_user();
+    tex_test.admin_call("add_fee_tier", args![OTHER_FEE]);
+    tex_test.admin_call("set_permissionless", args![false]);
+    tex_test.admin_call("set_resource_allowed", args![a, true]);
+    let builder = add_liquidity(&tex_test, &creator, OTHER_FEE);
+    tex_test.execute_expect_failure(builder, &creator);
+    tex_test.admin_call("set_resource_allowed", args![b, true]);
+    tex_test.admin_call("set_resource_denied", args![b, true]);
+    let builder = add_liquidity(&tex_test, &creator, OTHER_FEE);
+    tex_test.execute_expect_failure(builder, &creator);
+    // existing pools are not gated
+    let builder = add_liquidity(&tex_test, &creator, FEE);
+    tex_test.execute(builder, &creator);
+    tex_test.admin_call("set_resource_denied", args![b, false]);
+    let builder = add_liquidity(&tex_test, &creator, OTHER_FEE);
+    tex_test.execute(builder, &creator);
+}
+
+#[test]
+fn dynamic_fee_is_kept_within_bounds() {
+    let mut tex_test = TexTest::new();
+    let (tex, pair) = (tex_test.tex, tex_test.pair());
+    let swap_fee = |tex_test: &mut TexTest| {
+        tex_test
+            .test
+            .call_method::<u32>(tex, "swap_fee", args![pair, FEE], vec![])
+    };
+    assert_eq!(swap_fee(&mut tex_test), FEE);
+    tex_test.admin_call_expect_failure("set_dynamic_fee", args![pair, FEE, 20_000u32, 10_000u32]);
+    tex_test
+        .admin_call_expect_failure("set_dynamic_fee", args![pair, FEE, 10_000u32, 1_000_000u32]);
+    // without price changes the volatility is zero, the fee tier is raised to the minimum
+    tex_test.admin_call("set_dynamic_fee", args![pair, FEE, 10_000u32, 50_000u32]);
+    assert_eq!(swap_fee(&mut tex_test), 10_000);
+    tex_test.admin_call("clear_dynamic_fee", args![pair, FEE]);
+    assert_eq!(swap_fee(&mut tex_test), FEE);
+}
@@ -0,0 +1,244 @@
+mod support;
+
+use std::collections::HashMap;
+
+use support::{TexTest, User, FEE};
+use tari_template_lib::{args, models::ResourceAddress, prelude::Amount};
+use tari_transaction::{Transaction, TransactionBuilder};
+
+const DEPOSIT: i64 = 1_000_000;
+const SWAP: i64 = 10_000;
+
+/// Adds opening a position of `DEPOSIT` of both coins between the ticks to the `builder`,
+/// the position token left on the workspace as `position.0`.
+fn open_position(
+    tex_test: &TexTest,
+    builder: TransactionBuilder,
+    lower_tick: i32,
+    upper_tick: i32,
+) -> TransactionBuilder {
+    tex_test
+        .take_coins(builder, DEPOSIT)
+        .call_method(
+            tex_test.tex,
+            "add_concentrated_liquidity",
+            args![
+                Workspace("coin_0"),
+                Workspace("coin_1"),
+                FEE,
+                lower_tick,
+                upper_tick
+            ],
+        )
+        .put_last_instruction_output_on_workspace("position")
+}
+
+/// Transaction of the `user` swapping `SWAP` of `a` to `b` in the concentrated liquidity pool.
+fn swap(tex_test: &TexTest, user: &User) -> TransactionBuilder {
+    Transaction::builder()
+        .call_method(
+            tex_test.coin_of(tex_test.a),
+            "take_free_coins",
+            args![Amount::new(SWAP)],
+        )
+        .put_last_instruction_output_on_workspace("input")
+        .call_method(
+            tex_test.tex,
+            "swap_concentrated",
+            args![Workspace("input"), tex_test.b, FEE],
+        )
+        .put_last_instruction_output_on_workspace("output")
+        .call_method(user.account, "deposit", args![Workspace("output")])
+}
+
+#[test]
+fn position_earns_fees_of_swaps_in_its_range() {
+    let mut tex_test = TexTest::new();
+    let provider = tex_test.new_user();
+    let (tex, a, b) = (tex_test.tex, tex_test.a, tex_test.b);
+    let builder = open_position(&tex_test, Transaction::builder(), -600, 600)
+        .call_method(provider.account, "deposit", args![Workspace("position.1")])
+        .call_method(provider.account, "deposit", args![Workspace("position.2")])
+        .call_method(
+            tex_test.coin_of(a),
+            "take_free_coins",
+            args![Amount::new(SWAP)],
+        )
+        .put_last_instruction_output_on_workspace("input")
+        .call_method(tex, "swap_concentrated", args![Workspace("input"), b, FEE])
+        .put_last_instruction_output_on_workspace("output")
+        .call_method(provider.account, "deposit", args![Workspace("output")])
+        .call_method(
+            tex,
+            "remove_concentrated_liquidity",
+            args![Workspace("position.0")],
+        )
+        .put_last_instruction_output_on_workspace("closed")
+        .call_method(provider.account, "deposit_all", args![Workspace("closed")]);
+    tex_test.execute(builder, &provider);
+}
+
+#[test]
+fn swaps_move_the_tick() {
+    let mut tex_test = TexTest::new();
+    let provider = tex_test.new_user();
+    let (tex, pair, a, b) = (tex_test.tex, tex_test.pair(), tex_test.a, tex_test.b);
+    let builder = open_position(&tex_test, Transaction::builder(), -600, 600).call_method(
+        provider.account,
+        "deposit_all",
+        args![Workspace("position")],
+    );
+    tex_test.execute(builder, &provider);
+    let tick = |tex_test: &mut TexTest| {
+        tex_test
+            .test
+            .call_method::<Option<i32>>(tex, "concentrated_pool_tick", args![pair, FEE], vec![])
+            .expect("pool price is set by the first position")
+    };
+    assert_eq!(tick(&mut tex_test), 0);
+    let quote = tex_test.test.call_method::<Amount>(
+        tex,
+        "get_amount_out_concentrated",
+        args![a, Amount::new(SWAP), b, FEE],
+        vec![],
+    );
+    // concentrated between the ticks, the liquidity pays more than the constant product pool
+    let constant_product_quote = tex_test.test.call_method::<Amount>(
+        tex,
+        "get_amount_out",
+        args![a, Amount::new(SWAP), b, FEE],
+        vec![],
+    );
+    assert!(quote > constant_product_quote);
+    let builder = swap(&tex_test, &provider);
+    tex_test.execute(builder, &provider);
+    // selling `a` lowers its price in `b`
+    assert!(tick(&mut tex_test) < 0);
+}
+
+#[test]
+fn ticks_off_the_spacing_are_rejected() {
+    let mut tex_test = TexTest::new();
+    let provider = tex_test.new_user();
+    // the 0.3% tier spaces ticks by 60
+    let builder = open_position(&tex_test, Transaction::builder(), -610, 600).call_method(
+        provider.account,
+        "deposit_all",
+        args![Workspace("position")],
+    );
+    tex_test.execute_expect_failure(builder, &provider);
+}
+
+#[test]
+fn protocol_fee_is_taken_from_swaps() {
+    let mut tex_test = TexTest::new();
+    let (tex, pair, a, b) = (tex_test.tex, tex_test.pair(), tex_test.a, tex_test.b);
+    let admin_account = tex_test.admin.account;
+    tex_test.admin_call("set_protocol_fee_share", args![500_000u32]);
+    // positions must still be paid out in full what the treasury left them
+    let builder = open_position(
+        &tex_test,
+        tex_test.with_admin_proof(Transaction::builder()),
+        -600,
+        600,
+    )
+    .call_method(admin_account, "deposit", args![Workspace("position.1")])
+    .call_method(admin_account, "deposit", args![Workspace("position.2")])
+    .call_method(tex, "set_protocol_fee", args![pair, FEE, true])
+    .call_method(
+        tex_test.coin_of(a),
+        "take_free_coins",
+        args![Amount::new(SWAP)],
+    )
+    .put_last_instruction_output_on_workspace("input")
+    .call_method(tex, "swap_concentrated", args![Workspace("input"), b, FEE])
+    .put_last_instruction_output_on_workspace("output")
+    .call_method(admin_account, "deposit", args![Workspace("output")])
+    .call_method(
+        tex,
+        "remove_concentrated_liquidity",
+        args![Workspace("position.0")],
+    )
+    .put_last_instruction_output_on_workspace("closed")
+    .call_method(admin_account, "deposit_all", args![Workspace("closed")])
+    .drop_all_proofs_in_workspace();
+    tex_test.execute_as_admin(builder);
+    let treasury = tex_test
+        .test
+        .call_method::<HashMap<ResourceAddress, Amount>>(tex, "treasury_balances", args![], vec![]);
+    // half of the 0.3% fee of 10_000
+    assert_eq!(treasury[&a], Amount::new(15));
+}
+
+#[test]
+fn paused_pool_lets_providers_withdraw() {
+    let mut tex_test = TexTest::new();
+    let provider = tex_test.new_user();
+    let (tex, pair) = (tex_test.tex, tex_test.pair());
+    let admin_account = tex_test.admin.account;
+    let builder = open_position(&tex_test, Transaction::builder(), -600, 600).call_method(
+        provider.account,
+        "deposit_all",
+        args![Workspace("position")],
+    );
+    tex_test.execute(builder, &provider);
+    // the admin closes a position of their own while the pool is paused
+    let builder = open_position(
+        &tex_test,
+        tex_test.with_admin_proof(Transaction::builder()),
+        -600,
+        600,
+    )
+    .call_method(admin_account, "deposit", args![Workspace("position.1")])
+    .call_method(admin_account, "deposit", args![Workspace("position.2")])
+    .call_method(tex, "set_pool_paused", args![pair, FEE, true])
+    .call_method(
+        tex,
+        "remove_concentrated_liquidity",
+        args![Workspace("position.0")],
+    )
+    .put_last_instruction_output_on_workspace("closed")
+    .call_method(admin_account, "deposit_all", args![Workspace("closed")])
+    .drop_all_proofs_in_workspace();
+    tex_test.execute_as_admin(builder);
+    let builder = swap(&tex_test, &provider);
+    tex_test.execute_expect_failure(builder, &provider);
+    let builder = open_position(&tex_test, Transaction::builder(), -600, 600).call_method(
+        provider.account,
+        "deposit_all",
+        args![Workspace("position")],
+    );
+    tex_test.execute_expect_failure(builder, &provider);
+    tex_test.admin_call("set_pool_paused", args![pair, FEE, false]);
+    let builder = swap(&tex_test, &provider);
+    tex_test.execute(builder, &provider);
+}
+
+#[test]
+fn dynamic_fee_leaves_the_fee_tier_of_concentrated_pools() {
+    let mut tex_test = TexTest::new();
+    let provider = tex_test.new_user();
+    let (tex, pair, a, b) = (tex_test.tex, tex_test.pair(), tex_test.a, tex_test.b);
+    let builder = open_position(&tex_test, Transaction::builder(), -600, 600).call_method(
+        provider.account,
+        "deposit_all",
+        args![Workspace("position")],
+    );
+    tex_test.execute(builder, &provider);
+    let quote = |tex_test: &mut TexTest| {
+        tex_test.test.call_method::<Amount>(
+            tex,
+            "get_amount_out_concentrated",
+            args![a, Amount::new(SWAP), b, FEE],
+            vec![],
+        )
+    };
+    let fee_tier_quote = quote(&mut tex_test);
+    // the constant product pool of the pair and fee now charges at least 10 times its fee tier
+    tex_test.admin_call("set_dynamic_fee", args![pair, FEE, 10 * FEE, 20 * FEE]);
+    let swap_fee = tex_test
+        .test
+        .call_method::<u32>(tex, "swap_fee", args![pair, FEE], vec![]);
+    assert_eq!(swap_fee, 10 * FEE);
+    assert_eq!(quote(&mut tex_test), fee_tier_quote);
+}
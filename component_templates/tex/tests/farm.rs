@@ -0,0 +1,144 @@
+mod support;
+
+use support::{TexTest, POOL_LIQUIDITY};
+use tari_template_lib::{args, models::ResourceAddress, prelude::Amount};
+use tari_transaction::{Transaction, TransactionBuilder};
+
+const STABLE_FEE: u32 = 500;
+const REWARDS: i64 = 10_000;
+const STAKE: i64 = 1_000;
+
+/// Enables the `STABLE_FEE` tier and creates a stable pool of the coins with it, holding
+/// `POOL_LIQUIDITY` of both. Returns its LP resource.
+fn create_stable_pool(tex_test: &mut TexTest) -> ResourceAddress {
+    tex_test.admin_call("add_fee_tier", args![STABLE_FEE]);
+    let (tex, pair) = (tex_test.tex, tex_test.pair());
+    let lp_resource = tex_test.test.call_method::<ResourceAddress>(
+        tex,
+        "create_stable_pool",
+        args![pair, STABLE_FEE, 100u64],
+        vec![],
+    );
+    tex_test.add_liquidity(STABLE_FEE, POOL_LIQUIDITY);
+    lp_resource
+}
+
+/// Creates a stable pool with a farm of its LP tokens funded with `REWARDS` of `a`,
+/// emitting 100 per epoch. The admin keeps the LP tokens. Returns the LP resource.
+fn funded_farm(tex_test: &mut TexTest) -> ResourceAddress {
+    let lp_resource = create_stable_pool(tex_test);
+    let builder = tex_test
+        .with_admin_proof(Transaction::builder())
+        .call_method(
+            tex_test.coin_of(tex_test.a),
+            "take_free_coins",
+            args![Amount::new(REWARDS)],
+        )
+        .put_last_instruction_output_on_workspace("rewards")
+        .call_method(
+            tex_test.tex,
+            "fund_farm",
+            args![lp_resource, Workspace("rewards")],
+        )
+        .call_method(
+            tex_test.tex,
+            "set_farm_emission",
+            args![lp_resource, Amount::new(100)],
+        )
+        .drop_all_proofs_in_workspace();
+    tex_test.execute_as_admin(builder);
+    lp_resource
+}
+
+/// Adds staking `STAKE` LP tokens of the admin to the `builder`, the stake receipt left on
+/// the workspace as `receipt`.
+fn stake(
+    tex_test: &TexTest,
+    builder: TransactionBuilder,
+    lp_resource: ResourceAddress,
+) -> TransactionBuilder {
+    builder
+        .call_method(
+            tex_test.admin.account,
+            "withdraw",
+            args![lp_resource, Amount::new(STAKE)],
+        )
+        .put_last_instruction_output_on_workspace("lp")
+        .call_method(tex_test.tex, "stake", args![Workspace("lp")])
+        .put_last_instruction_output_on_workspace("receipt")
+}
+
+#[test]
+fn stake_claim_and_unstake() {
+    let mut tex_test = TexTest::new();
+    let lp_resource = funded_farm(&mut tex_test);
+    let (tex, admin_account) = (tex_test.tex, tex_test.admin.account);
+    let builder = stake(&tex_test, Transaction::builder(), lp_resource)
+        .call_method(tex, "claim_rewards", args![Workspace("receipt")])
+        .put_last_instruction_output_on_workspace("claimed")
+        .call_method(admin_account, "deposit", args![Workspace("claimed.1")])
+        .call_method(tex, "unstake", args![Workspace("claimed.0")])
+        .put_last_instruction_output_on_workspace("unstaked")
+        .call_method(admin_account, "deposit_all", args![Workspace("unstaked")]);
+    tex_test.execute_as_admin(builder);
+}
+
+#[test]
+fn stake_receipt_is_not_claimable_twice() {
+    let mut tex_test = TexTest::new();
+    let lp_resource = funded_farm(&mut tex_test);
+    let (tex, admin_account) = (tex_test.tex, tex_test.admin.account);
+    // claiming burns the receipt, a new one is returned
+    let builder = stake(&tex_test, Transaction::builder(), lp_resource)
+        .call_method(tex, "claim_rewards", args![Workspace("receipt")])
+        .put_last_instruction_output_on_workspace("claimed")
+        .call_method(tex, "claim_rewards", args![Workspace("receipt")])
+        .put_last_instruction_output_on_workspace("claimed_again")
+        .call_method(admin_account, "deposit_all", args![Workspace("claimed")])
+        .call_method(
+            admin_account,
+            "deposit_all",
+            args![Workspace("claimed_again")],
+        );
+    tex_test.execute_as_admin_expect_failure(builder);
+}
+
+#[test]
+fn staking_needs_a_farm() {
+    let mut tex_test = TexTest::new();
+    let lp_resource = create_stable_pool(&mut tex_test);
+    let admin_account = tex_test.admin.account;
+    let builder = stake(&tex_test, Transaction::builder(), lp_resource).call_method(
+        admin_account,
+        "deposit",
+        args![Workspace("receipt")],
+    );
+    tex_test.execute_as_admin_expect_failure(builder);
+}
+
+#[test]
+fn farms_are_funded_and_configured_by_the_admin_only() {
+    let mut tex_test = TexTest::new();
+    let lp_resource = funded_farm(&mut tex_test);
+    tex_test.admin_call_expect_failure("set_farm_emission", args![lp_resource, Amount::new(-1)]);
+    let outsider = tex_test.new_user();
+    let builder = Transaction::builder()
+        .call_method(
+            tex_test.coin_of(tex_test.a),
+            "take_free_coins",
+            args![Amount::new(REWARDS)],
+        )
+        .put_last_instruction_output_on_workspace("rewards")
+        .call_method(
+            tex_test.tex,
+            "fund_farm",
+            args![lp_resource, Workspace("rewards")],
+        );
+    tex_test.execute_expect_failure(builder, &outsider);
+    let builder = Transaction::builder().call_method(
+        tex_test.tex,
+        "set_farm_emission",
+        args![lp_resource, Amount::new(1_000)],
+    );
+    tex_test.execute_expect_failure(builder, &outsider);
+}
@@ -0,0 +1,74 @@
+mod support;
+
+use support::{TexTest, FEE};
+use tari_template_lib::{args, prelude::Amount};
+use tari_transaction::Transaction;
+
+const LOAN: i64 = 10_000;
+
+#[test]
+fn flash_loan_repaid_in_the_same_transaction_succeeds() {
+    let mut tex_test = TexTest::new();
+    let borrower = tex_test.new_user();
+    let coin_a = tex_test.coin_of(tex_test.a);
+    let transaction = Transaction::builder()
+        .call_method(
+            tex_test.tex,
+            "flash_borrow",
+            args![tex_test.pair(), FEE, tex_test.a, Amount::new(LOAN)],
+        )
+        .put_last_instruction_output_on_workspace("loan")
+        .call_method(borrower.account, "deposit", args![Workspace("loan.0")])
+        // the loan plus more than the pool fee on it
+        .call_method(coin_a, "take_free_coins", args![Amount::new(LOAN + 100)])
+        .put_last_instruction_output_on_workspace("repayment")
+        .call_method(
+            tex_test.tex,
+            "flash_repay",
+            args![Workspace("loan.1"), Workspace("repayment")],
+        )
+        .put_last_instruction_output_on_workspace("change")
+        .call_method(borrower.account, "deposit", args![Workspace("change")])
+        .build_and_seal(&borrower.secret_key);
+    tex_test
+        .test
+        .execute_expect_success(transaction, vec![borrower.owner_proof.clone()]);
+}
+
+#[test]
+fn flash_loan_not_repaid_is_rejected() {
+    let mut tex_test = TexTest::new();
+    let borrower = tex_test.new_user();
+    // the debt receipt is left over, which fails the transaction
+    let transaction = Transaction::builder()
+        .call_method(
+            tex_test.tex,
+            "flash_borrow",
+            args![tex_test.pair(), FEE, tex_test.a, Amount::new(LOAN)],
+        )
+        .put_last_instruction_output_on_workspace("loan")
+        .call_method(borrower.account, "deposit", args![Workspace("loan.0")])
+        .build_and_seal(&borrower.secret_key);
+    tex_test
+        .test
+        .execute_expect_failure(transaction, vec![borrower.owner_proof.clone()]);
+}
+
+#[test]
+fn flash_loan_receipt_is_not_depositable() {
+    let mut tex_test = TexTest::new();
+    let borrower = tex_test.new_user();
+    let transaction = Transaction::builder()
+        .call_method(
+            tex_test.tex,
+            "flash_borrow",
+            args![tex_test.pair(), FEE, tex_test.a, Amount::new(LOAN)],
+        )
+        .put_last_instruction_output_on_workspace("loan")
+        .call_method(borrower.account, "deposit", args![Workspace("loan.0")])
+        .call_method(borrower.account, "deposit", args![Workspace("loan.1")])
+        .build_and_seal(&borrower.secret_key);
+    tex_test
+        .test
+        .execute_expect_failure(transaction, vec![borrower.owner_proof.clone()]);
+}
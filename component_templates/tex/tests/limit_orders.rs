@@ -0,0 +1,121 @@
+mod support;
+
+use support::{TexTest, User, FEE};
+use tari_template_lib::{args, prelude::Amount};
+use tari_transaction::{Transaction, TransactionBuilder};
+
+const ORDER: i64 = 1_000;
+
+/// Adds placing an order selling `ORDER` of `b` for `a` at the `limit_price` to the `builder`,
+/// the order receipt left on the workspace as `receipt`.
+fn place_order(
+    tex_test: &TexTest,
+    builder: TransactionBuilder,
+    limit_price: u64,
+) -> TransactionBuilder {
+    builder
+        .call_method(
+            tex_test.coin_of(tex_test.b),
+            "take_free_coins",
+            args![Amount::new(ORDER)],
+        )
+        .put_last_instruction_output_on_workspace("order_input")
+        .call_method(
+            tex_test.tex,
+            "place_limit_order",
+            args![Workspace("order_input"), tex_test.a, FEE, limit_price],
+        )
+        .put_last_instruction_output_on_workspace("receipt")
+}
+
+/// Adds a swap of `amount` of `a` to `b` on behalf of the `user` to the `builder`.
+fn swap(
+    tex_test: &TexTest,
+    builder: TransactionBuilder,
+    user: &User,
+    amount: i64,
+) -> TransactionBuilder {
+    builder
+        .call_method(
+            tex_test.coin_of(tex_test.a),
+            "take_free_coins",
+            args![Amount::new(amount)],
+        )
+        .put_last_instruction_output_on_workspace("input")
+        .call_method(
+            tex_test.tex,
+            "swap",
+            args![Workspace("input"), tex_test.b, FEE],
+        )
+        .put_last_instruction_output_on_workspace("output")
+        .call_method(user.account, "deposit", args![Workspace("output")])
+}
+
+#[test]
+fn order_is_filled_by_a_swap_moving_the_price() {
+    let mut tex_test = TexTest::new();
+    let trader = tex_test.new_user();
+    // `b` sells for about 0.997 `a` now, selling `a` raises it above the limit of 1.02
+    let builder = place_order(&tex_test, Transaction::builder(), 1_020_000_000);
+    let builder = swap(&tex_test, builder, &trader, 50_000)
+        .call_method(
+            tex_test.tex,
+            "claim_limit_order",
+            args![Workspace("receipt")],
+        )
+        .put_last_instruction_output_on_workspace("filled")
+        .call_method(trader.account, "deposit", args![Workspace("filled")]);
+    tex_test.execute(builder, &trader);
+}
+
+#[test]
+fn order_the_pool_pays_enough_for_is_filled_right_away() {
+    let mut tex_test = TexTest::new();
+    let trader = tex_test.new_user();
+    let builder = place_order(&tex_test, Transaction::builder(), 900_000_000)
+        .call_method(
+            tex_test.tex,
+            "claim_limit_order",
+            args![Workspace("receipt")],
+        )
+        .put_last_instruction_output_on_workspace("filled")
+        .call_method(trader.account, "deposit", args![Workspace("filled")]);
+    tex_test.execute(builder, &trader);
+}
+
+#[test]
+fn open_order_is_cancelled_not_claimed() {
+    let mut tex_test = TexTest::new();
+    let trader = tex_test.new_user();
+    let builder = place_order(&tex_test, Transaction::builder(), 2_000_000_000)
+        .call_method(
+            tex_test.tex,
+            "claim_limit_order",
+            args![Workspace("receipt")],
+        )
+        .put_last_instruction_output_on_workspace("filled")
+        .call_method(trader.account, "deposit", args![Workspace("filled")]);
+    tex_test.execute_expect_failure(builder, &trader);
+    let builder = place_order(&tex_test, Transaction::builder(), 2_000_000_000)
+        .call_method(
+            tex_test.tex,
+            "cancel_limit_order",
+            args![Workspace("receipt")],
+        )
+        .put_last_instruction_output_on_workspace("refund")
+        .call_method(trader.account, "deposit", args![Workspace("refund")]);
+    tex_test.execute(builder, &trader);
+}
+
+#[test]
+fn fill_orders_is_paused_with_the_exchange() {
+    let mut tex_test = TexTest::new();
+    let trader = tex_test.new_user();
+    tex_test.admin_call("set_paused", args![true]);
+    let builder = Transaction::builder().call_method(
+        tex_test.tex,
+        "fill_orders",
+        args![tex_test.pair(), FEE],
+    );
+    tex_test.execute_expect_failure(builder, &trader);
+}
@@ -0,0 +1,119 @@
+mod support;
+
+use std::collections::HashMap;
+
+use support::{TexTest, FEE, POOL_LIQUIDITY};
+use tari_template_lib::{args, models::ResourceAddress, prelude::Amount};
+use tari_transaction::Transaction;
+
+/// Fee tier of stable pools, 0.05%.
+const STABLE_FEE: u32 = 500;
+const AMPLIFICATION: u64 = 100;
+const SWAP: i64 = 10_000;
+
+/// Enables the `STABLE_FEE` tier and creates a stable pool of the coins with it, holding
+/// `POOL_LIQUIDITY` of both. Returns its LP resource.
+fn create_stable_pool(tex_test: &mut TexTest) -> ResourceAddress {
+    tex_test.admin_call("add_fee_tier", args![STABLE_FEE]);
+    let (tex, pair) = (tex_test.tex, tex_test.pair());
+    let lp_resource = tex_test.test.call_method::<ResourceAddress>(
+        tex,
+        "create_stable_pool",
+        args![pair, STABLE_FEE, AMPLIFICATION],
+        vec![],
+    );
+    tex_test.add_liquidity(STABLE_FEE, POOL_LIQUIDITY);
+    lp_resource
+}
+
+fn amount_out(tex_test: &mut TexTest, fee: u32) -> Amount {
+    let (tex, a, b) = (tex_test.tex, tex_test.a, tex_test.b);
+    tex_test.test.call_method::<Amount>(
+        tex,
+        "get_amount_out",
+        args![a, Amount::new(SWAP), b, fee],
+        vec![],
+    )
+}
+
+#[test]
+fn stable_pool_trades_close_to_the_peg() {
+    let mut tex_test = TexTest::new();
+    create_stable_pool(&mut tex_test);
+    let stable_out = amount_out(&mut tex_test, STABLE_FEE);
+    // less slippage than the constant product pool of the same reserves
+    assert!(stable_out > amount_out(&mut tex_test, FEE));
+    assert!(stable_out >= Amount::new(SWAP * 9_990 / 10_000));
+    let trader = tex_test.new_user();
+    let (tex, a, b) = (tex_test.tex, tex_test.a, tex_test.b);
+    let builder = Transaction::builder()
+        .call_method(
+            tex_test.coin_of(a),
+            "take_free_coins",
+            args![Amount::new(SWAP)],
+        )
+        .put_last_instruction_output_on_workspace("input")
+        .call_method(
+            tex,
+            "swap_with_min_out",
+            args![Workspace("input"), b, STABLE_FEE, stable_out],
+        )
+        .put_last_instruction_output_on_workspace("output")
+        .call_method(trader.account, "deposit", args![Workspace("output")]);
+    tex_test.execute(builder, &trader);
+}
+
+#[test]
+fn stable_pool_liquidity_is_removable() {
+    let mut tex_test = TexTest::new();
+    let lp_resource = create_stable_pool(&mut tex_test);
+    let (tex, admin_account) = (tex_test.tex, tex_test.admin.account);
+    let share = tex_test
+        .test
+        .call_method::<HashMap<ResourceAddress, Amount>>(
+            tex,
+            "lp_share_value",
+            args![lp_resource, Amount::new(1_000)],
+            vec![],
+        );
+    assert_eq!(share[&tex_test.a], Amount::new(1_000));
+    assert_eq!(share[&tex_test.b], Amount::new(1_000));
+    let builder = Transaction::builder()
+        .call_method(
+            admin_account,
+            "withdraw",
+            args![lp_resource, Amount::new(1_000)],
+        )
+        .put_last_instruction_output_on_workspace("lp")
+        .call_method(tex, "remove_liquidity", args![Workspace("lp")])
+        .put_last_instruction_output_on_workspace("removed")
+        .call_method(admin_account, "deposit_all", args![Workspace("removed")]);
+    tex_test.execute_as_admin(builder);
+}
+
+#[test]
+fn amplification_is_ramped_by_the_admin_only() {
+    let mut tex_test = TexTest::new();
+    create_stable_pool(&mut tex_test);
+    let (tex, pair) = (tex_test.tex, tex_test.pair());
+    let amplification = tex_test.test.call_method::<Option<u64>>(
+        tex,
+        "amplification",
+        args![pair, STABLE_FEE],
+        vec![],
+    );
+    assert_eq!(amplification, Some(AMPLIFICATION));
+    // the constant product pool has no amplification to ramp
+    tex_test.admin_call_expect_failure("ramp_amplification", args![pair, FEE, 200u64, 1_000u64]);
+    tex_test.admin_call(
+        "ramp_amplification",
+        args![pair, STABLE_FEE, 200u64, 1_000u64],
+    );
+    let outsider = tex_test.new_user();
+    let builder = Transaction::builder().call_method(
+        tex,
+        "ramp_amplification",
+        args![pair, STABLE_FEE, 300u64, 2_000u64],
+    );
+    tex_test.execute_expect_failure(builder, &outsider);
+}
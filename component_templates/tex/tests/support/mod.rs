@@ -0,0 +1,214 @@
+//! Shared setup of Tex integration tests: an exchange with a funded pool of two test coins,
+//! operated by an account which keeps the admin badge.
+#![allow(dead_code)]
+
+use tari_crypto::ristretto::RistrettoSecretKey;
+use tari_engine_types::commit_result::{ExecuteResult, RejectReason};
+use tari_template_lib::{
+    args,
+    args::Arg,
+    models::{BucketId, ComponentAddress, NonFungibleAddress, ResourceAddress},
+    prelude::Amount,
+};
+use tari_template_test_tooling::TemplateTest;
+use tari_transaction::{Transaction, TransactionBuilder};
+
+/// Fee tier of the pool, 0.3%.
+pub const FEE: u32 = 3_000;
+pub const COIN_SUPPLY: i64 = 1_000_000_000;
+pub const POOL_LIQUIDITY: i64 = 1_000_000;
+
+pub struct User {
+    pub account: ComponentAddress,
+    pub owner_proof: NonFungibleAddress,
+    pub secret_key: RistrettoSecretKey,
+}
+
+pub struct TexTest {
+    pub test: TemplateTest,
+    pub tex: ComponentAddress,
+    pub admin_badge: ResourceAddress,
+    /// Keeps the admin badge and provided the initial liquidity.
+    pub admin: User,
+    pub coins: [ComponentAddress; 2],
+    /// Resources of `coins`, in the same order.
+    pub coin_resources: [ResourceAddress; 2],
+    /// Resources of the pool, sorted like the `Pair` of the template.
+    pub a: ResourceAddress,
+    pub b: ResourceAddress,
+}
+
+impl TexTest {
+    /// Deploys Tex and the coin template, creates an exchange with the `FEE` tier and a pool
+    /// holding `POOL_LIQUIDITY` of both coins.
+    pub fn new() -> Self {
+        let mut test = TemplateTest::new(env!("CARGO_MANIFEST_DIR"), [".", "../coin"]);
+        let (account, owner_proof, secret_key) = test.create_funded_account();
+        let admin = User {
+            account,
+            owner_proof,
+            secret_key,
+        };
+        let tex_template = test.get_template_address("Tex");
+        let result = test.execute_expect_success(
+            Transaction::builder()
+                .call_function(tex_template, "new", args![vec![FEE], 0u32])
+                .put_last_instruction_output_on_workspace("tex")
+                .call_method(admin.account, "deposit", args![Workspace("tex.1")])
+                .build_and_seal(&admin.secret_key),
+            vec![admin.owner_proof.clone()],
+        );
+        let (tex, _) = result.finalize.execution_results[0]
+            .decode::<(ComponentAddress, BucketId)>()
+            .unwrap();
+        let admin_badge = test.call_method::<ResourceAddress>(tex, "admin_badge", args![], vec![]);
+        let coins = ["TEXA", "TEXB"].map(|symbol| {
+            test.call_function::<ComponentAddress>(
+                "Coin",
+                "new",
+                args![Amount::new(COIN_SUPPLY), symbol],
+                vec![],
+            )
+        });
+        let coin_resources = coins.map(|coin| {
+            test.call_method::<ResourceAddress>(coin, "vault_address", args![], vec![])
+        });
+        let mut resources = coin_resources;
+        resources.sort();
+        let mut tex_test = TexTest {
+            test,
+            tex,
+            admin_badge,
+            admin,
+            coins,
+            coin_resources,
+            a: resources[0],
+            b: resources[1],
+        };
+        tex_test.add_liquidity(FEE, POOL_LIQUIDITY);
+        tex_test
+    }
+
+    /// The `Pair` of the pool, encoded the same way as the sorted tuple struct of the template.
+    pub fn pair(&self) -> (ResourceAddress, ResourceAddress) {
+        (self.a, self.b)
+    }
+
+    pub fn new_user(&mut self) -> User {
+        let (account, owner_proof, secret_key) = self.test.create_funded_account();
+        User {
+            account,
+            owner_proof,
+            secret_key,
+        }
+    }
+
+    /// Coin component minting the `resource`.
+    pub fn coin_of(&self, resource: ResourceAddress) -> ComponentAddress {
+        let index = self
+            .coin_resources
+            .iter()
+            .position(|coin_resource| *coin_resource == resource)
+            .expect("resource of a test coin");
+        self.coins[index]
+    }
+
+    /// Takes `amount` of both coins and adds them to the pool with the `fee`,
+    /// the admin keeps the LP tokens.
+    pub fn add_liquidity(&mut self, fee: u32, amount: i64) -> ExecuteResult {
+        let transaction = self
+            .take_coins(Transaction::builder(), amount)
+            .call_method(
+                self.tex,
+                "add_liquidity",
+                args![Workspace("coin_0"), Workspace("coin_1"), fee],
+            )
+            .put_last_instruction_output_on_workspace("added")
+            .call_method(self.admin.account, "deposit", args![Workspace("added.0")])
+            .call_method(self.admin.account, "deposit", args![Workspace("added.1")])
+            .build_and_seal(&self.admin.secret_key);
+        self.test
+            .execute_expect_success(transaction, vec![self.admin.owner_proof.clone()])
+    }
+
+    /// Puts `amount` of each coin on the workspace, as `coin_0` and `coin_1`.
+    pub fn take_coins(&self, builder: TransactionBuilder, amount: i64) -> TransactionBuilder {
+        self.coins
+            .iter()
+            .enumerate()
+            .fold(builder, |builder, (index, coin)| {
+                builder
+                    .call_method(*coin, "take_free_coins", args![Amount::new(amount)])
+                    .put_last_instruction_output_on_workspace(format!("coin_{index}"))
+            })
+    }
+
+    /// Seals the transaction of the `builder` by the `user` and executes it.
+    pub fn execute(&mut self, builder: TransactionBuilder, user: &User) -> ExecuteResult {
+        self.test.execute_expect_success(
+            builder.build_and_seal(&user.secret_key),
+            vec![user.owner_proof.clone()],
+        )
+    }
+
+    /// Same as `execute`, but expects the transaction to fail.
+    pub fn execute_expect_failure(
+        &mut self,
+        builder: TransactionBuilder,
+        user: &User,
+    ) -> RejectReason {
+        self.test.execute_expect_failure(
+            builder.build_and_seal(&user.secret_key),
+            vec![user.owner_proof.clone()],
+        )
+    }
+
+    /// Same as `execute`, the admin sealing the transaction, see `with_admin_proof`.
+    pub fn execute_as_admin(&mut self, builder: TransactionBuilder) -> ExecuteResult {
+        self.test.execute_expect_success(
+            builder.build_and_seal(&self.admin.secret_key),
+            vec![self.admin.owner_proof.clone()],
+        )
+    }
+
+    /// Same as `execute_as_admin`, but expects the transaction to fail.
+    pub fn execute_as_admin_expect_failure(&mut self, builder: TransactionBuilder) -> RejectReason {
+        self.test.execute_expect_failure(
+            builder.build_and_seal(&self.admin.secret_key),
+            vec![self.admin.owner_proof.clone()],
+        )
+    }
+
+    /// Calls the `method` of Tex with a proof of the admin badge.
+    pub fn admin_call(&mut self, method: &str, args: Vec<Arg>) -> ExecuteResult {
+        let transaction = self.admin_transaction(method, args);
+        self.test
+            .execute_expect_success(transaction, vec![self.admin.owner_proof.clone()])
+    }
+
+    /// Same as `admin_call`, but expects the call to fail.
+    pub fn admin_call_expect_failure(&mut self, method: &str, args: Vec<Arg>) -> RejectReason {
+        let transaction = self.admin_transaction(method, args);
+        self.test
+            .execute_expect_failure(transaction, vec![self.admin.owner_proof.clone()])
+    }
+
+    fn admin_transaction(&self, method: &str, args: Vec<Arg>) -> Transaction {
+        self.with_admin_proof(Transaction::builder())
+            .call_method(self.tex, method, args)
+            .drop_all_proofs_in_workspace()
+            .build_and_seal(&self.admin.secret_key)
+    }
+
+    /// Adds a proof of the admin badge to the `builder`, for `execute_as_admin`.
+    /// The transaction must drop it before it ends.
+    pub fn with_admin_proof(&self, builder: TransactionBuilder) -> TransactionBuilder {
+        builder
+            .call_method(
+                self.admin.account,
+                "create_proof_for_resource",
+                args![self.admin_badge],
+            )
+            .put_last_instruction_output_on_workspace("admin_proof")
+    }
+}
@@ -0,0 +1,290 @@
+mod support;
+
+use std::collections::HashMap;
+
+use support::{TexTest, User, FEE};
+use tari_template_lib::{args, models::ResourceAddress, prelude::Amount};
+use tari_transaction::{Transaction, TransactionBuilder};
+
+const DEPOSIT: i64 = 100_000;
+const SWAP: i64 = 1_000;
+
+/// Puts about `DEPOSIT` of both coins on the workspace as `coins`, a pair of buckets sorted
+/// like the `Pair`, which methods taking a `Vec<Bucket>` accept. The pair is the output of
+/// `remove_liquidity` of LP tokens minted for the coins in the same transaction, rounding
+/// leftovers go to the `user`.
+fn take_coin_pair(
+    tex_test: &TexTest,
+    builder: TransactionBuilder,
+    user: &User,
+) -> TransactionBuilder {
+    tex_test
+        .take_coins(builder, DEPOSIT)
+        .call_method(
+            tex_test.tex,
+            "add_liquidity",
+            args![Workspace("coin_0"), Workspace("coin_1"), FEE],
+        )
+        .put_last_instruction_output_on_workspace("coins_added")
+        .call_method(user.account, "deposit", args![Workspace("coins_added.1")])
+        .call_method(
+            tex_test.tex,
+            "remove_liquidity",
+            args![Workspace("coins_added.0")],
+        )
+        .put_last_instruction_output_on_workspace("coins")
+}
+
+/// Adds creating a weighted pool of about `DEPOSIT` of both coins, weighing 80% and 20% in
+/// the sorted order, to the `builder`, its LP tokens left on the workspace as `lp`.
+fn create_pool(tex_test: &TexTest, builder: TransactionBuilder, user: &User) -> TransactionBuilder {
+    take_coin_pair(tex_test, builder, user)
+        .call_method(
+            tex_test.tex,
+            "create_weighted_pool",
+            args![Workspace("coins"), vec![80u32, 20u32], FEE],
+        )
+        .put_last_instruction_output_on_workspace("lp")
+}
+
+fn assets(tex_test: &TexTest) -> Vec<ResourceAddress> {
+    vec![tex_test.a, tex_test.b]
+}
+
+/// Transaction of the `user` swapping `SWAP` of `b` to `a` in the weighted pool.
+fn swap(tex_test: &TexTest, user: &User) -> TransactionBuilder {
+    Transaction::builder()
+        .call_method(
+            tex_test.coin_of(tex_test.b),
+            "take_free_coins",
+            args![Amount::new(SWAP)],
+        )
+        .put_last_instruction_output_on_workspace("input")
+        .call_method(
+            tex_test.tex,
+            "swap_weighted",
+            args![Workspace("input"), tex_test.a, assets(tex_test), FEE],
+        )
+        .put_last_instruction_output_on_workspace("output")
+        .call_method(user.account, "deposit", args![Workspace("output")])
+}
+
+#[test]
+fn weighted_pool_swaps_and_exits() {
+    let mut tex_test = TexTest::new();
+    let provider = tex_test.new_user();
+    let (tex, a, b) = (tex_test.tex, tex_test.a, tex_test.b);
+    let builder = create_pool(&tex_test, Transaction::builder(), &provider)
+        .call_method(
+            tex_test.coin_of(b),
+            "take_free_coins",
+            args![Amount::new(SWAP)],
+        )
+        .put_last_instruction_output_on_workspace("input")
+        .call_method(
+            tex,
+            "swap_weighted",
+            args![Workspace("input"), a, assets(&tex_test), FEE],
+        )
+        .put_last_instruction_output_on_workspace("output")
+        .call_method(provider.account, "deposit", args![Workspace("output")])
+        .call_method(tex, "exit_weighted_pool", args![Workspace("lp")])
+        .put_last_instruction_output_on_workspace("exited")
+        .call_method(provider.account, "deposit_all", args![Workspace("exited")]);
+    tex_test.execute(builder, &provider);
+}
+
+#[test]
+fn weighted_pool_prices_follow_weights() {
+    let mut tex_test = TexTest::new();
+    let provider = tex_test.new_user();
+    let (tex, a, b) = (tex_test.tex, tex_test.a, tex_test.b);
+    let builder = create_pool(&tex_test, Transaction::builder(), &provider).call_method(
+        provider.account,
+        "deposit",
+        args![Workspace("lp")],
+    );
+    tex_test.execute(builder, &provider);
+    let reserves = tex_test
+        .test
+        .call_method::<HashMap<ResourceAddress, Amount>>(
+            tex,
+            "weighted_pool_reserves",
+            args![assets(&tex_test), FEE],
+            vec![],
+        );
+    assert_eq!(reserves[&a], reserves[&b]);
+    // equal reserves, `a` weighing 4 times `b`, so `a` is worth 4 of `b`
+    let price = tex_test.test.call_method::<u64>(
+        tex,
+        "weighted_spot_price",
+        args![assets(&tex_test), FEE, a, b],
+        vec![],
+    );
+    assert_eq!(price, 4_000_000_000);
+    let quote = tex_test.test.call_method::<Amount>(
+        tex,
+        "get_amount_out_weighted",
+        args![a, Amount::new(SWAP), b, assets(&tex_test), FEE],
+        vec![],
+    );
+    assert!(quote > Amount::new(SWAP * 3) && quote < Amount::new(SWAP * 4));
+}
+
+#[test]
+fn single_asset_join_and_exit() {
+    let mut tex_test = TexTest::new();
+    let provider = tex_test.new_user();
+    let (tex, a) = (tex_test.tex, tex_test.a);
+    let builder = create_pool(&tex_test, Transaction::builder(), &provider)
+        .call_method(provider.account, "deposit", args![Workspace("lp")])
+        .call_method(
+            tex_test.coin_of(a),
+            "take_free_coins",
+            args![Amount::new(SWAP)],
+        )
+        .put_last_instruction_output_on_workspace("input")
+        .call_method(
+            tex,
+            "join_weighted_pool_single",
+            args![Workspace("input"), assets(&tex_test), FEE, Amount::new(1)],
+        )
+        .put_last_instruction_output_on_workspace("joined")
+        .call_method(
+            tex,
+            "exit_weighted_pool_single",
+            args![Workspace("joined"), a, Amount::new(1)],
+        )
+        .put_last_instruction_output_on_workspace("output")
+        .call_method(provider.account, "deposit", args![Workspace("output")]);
+    tex_test.execute(builder, &provider);
+}
+
+#[test]
+fn swaps_above_half_of_the_input_reserve_are_rejected() {
+    let mut tex_test = TexTest::new();
+    let provider = tex_test.new_user();
+    let (tex, a, b) = (tex_test.tex, tex_test.a, tex_test.b);
+    let builder = create_pool(&tex_test, Transaction::builder(), &provider)
+        .call_method(provider.account, "deposit", args![Workspace("lp")])
+        .call_method(
+            tex_test.coin_of(a),
+            "take_free_coins",
+            args![Amount::new(DEPOSIT)],
+        )
+        .put_last_instruction_output_on_workspace("input")
+        .call_method(
+            tex,
+            "swap_weighted",
+            args![Workspace("input"), b, assets(&tex_test), FEE],
+        )
+        .put_last_instruction_output_on_workspace("output")
+        .call_method(provider.account, "deposit", args![Workspace("output")]);
+    tex_test.execute_expect_failure(builder, &provider);
+}
+
+#[test]
+fn protocol_fee_is_taken_from_swaps() {
+    let mut tex_test = TexTest::new();
+    let (tex, a, b) = (tex_test.tex, tex_test.a, tex_test.b);
+    let admin_account = tex_test.admin.account;
+    // the whole swap fee goes to the treasury
+    tex_test.admin_call("set_protocol_fee_share", args![1_000_000u32]);
+    let builder = create_pool(
+        &tex_test,
+        tex_test.with_admin_proof(Transaction::builder()),
+        &tex_test.admin,
+    )
+    .call_method(
+        tex,
+        "set_weighted_protocol_fee",
+        args![assets(&tex_test), FEE, true],
+    )
+    .call_method(
+        tex_test.coin_of(b),
+        "take_free_coins",
+        args![Amount::new(SWAP)],
+    )
+    .put_last_instruction_output_on_workspace("input")
+    .call_method(
+        tex,
+        "swap_weighted",
+        args![Workspace("input"), a, assets(&tex_test), FEE],
+    )
+    .put_last_instruction_output_on_workspace("output")
+    .call_method(admin_account, "deposit", args![Workspace("output")])
+    .call_method(tex, "exit_weighted_pool", args![Workspace("lp")])
+    .put_last_instruction_output_on_workspace("exited")
+    .call_method(admin_account, "deposit_all", args![Workspace("exited")])
+    .drop_all_proofs_in_workspace();
+    tex_test.execute_as_admin(builder);
+    let treasury = tex_test
+        .test
+        .call_method::<HashMap<ResourceAddress, Amount>>(tex, "treasury_balances", args![], vec![]);
+    // 0.3% of 1_000
+    assert_eq!(treasury[&b], Amount::new(3));
+}
+
+#[test]
+fn paused_pool_lets_providers_withdraw() {
+    let mut tex_test = TexTest::new();
+    let trader = tex_test.new_user();
+    let (tex, a) = (tex_test.tex, tex_test.a);
+    let admin_account = tex_test.admin.account;
+    // the admin exits what they joined with while the pool is paused
+    let builder = create_pool(
+        &tex_test,
+        tex_test.with_admin_proof(Transaction::builder()),
+        &tex_test.admin,
+    )
+    .call_method(admin_account, "deposit", args![Workspace("lp")])
+    .call_method(
+        tex_test.coin_of(a),
+        "take_free_coins",
+        args![Amount::new(SWAP)],
+    )
+    .put_last_instruction_output_on_workspace("input")
+    .call_method(
+        tex,
+        "join_weighted_pool_single",
+        args![Workspace("input"), assets(&tex_test), FEE, Amount::new(1)],
+    )
+    .put_last_instruction_output_on_workspace("joined")
+    .call_method(
+        tex,
+        "set_weighted_pool_paused",
+        args![assets(&tex_test), FEE, true],
+    )
+    .call_method(
+        tex,
+        "exit_weighted_pool_single",
+        args![Workspace("joined"), a, Amount::new(1)],
+    )
+    .put_last_instruction_output_on_workspace("output")
+    .call_method(admin_account, "deposit", args![Workspace("output")])
+    .drop_all_proofs_in_workspace();
+    tex_test.execute_as_admin(builder);
+    let builder = swap(&tex_test, &trader);
+    tex_test.execute_expect_failure(builder, &trader);
+    let builder = Transaction::builder()
+        .call_method(
+            tex_test.coin_of(a),
+            "take_free_coins",
+            args![Amount::new(SWAP)],
+        )
+        .put_last_instruction_output_on_workspace("input")
+        .call_method(
+            tex,
+            "join_weighted_pool_single",
+            args![Workspace("input"), assets(&tex_test), FEE, Amount::new(1)],
+        )
+        .put_last_instruction_output_on_workspace("joined")
+        .call_method(trader.account, "deposit", args![Workspace("joined")]);
+    tex_test.execute_expect_failure(builder, &trader);
+    tex_test.admin_call(
+        "set_weighted_pool_paused",
+        args![assets(&tex_test), FEE, false],
+    );
+    let builder = swap(&tex_test, &trader);
+    tex_test.execute(builder, &trader);
+}
@@ -0,0 +1,83 @@
+mod support;
+
+use support::{TexTest, FEE};
+use tari_template_lib::{args, models::ComponentAddress, prelude::Amount};
+use tari_transaction::Transaction;
+
+const ZAP: i64 = 10_000;
+
+#[test]
+fn zap_in_and_out_in_a_single_resource() {
+    let mut tex_test = TexTest::new();
+    let provider = tex_test.new_user();
+    let (tex, pair, a) = (tex_test.tex, tex_test.pair(), tex_test.a);
+    let builder = Transaction::builder()
+        .call_method(
+            tex_test.coin_of(a),
+            "take_free_coins",
+            args![Amount::new(ZAP)],
+        )
+        .put_last_instruction_output_on_workspace("input")
+        .call_method(
+            tex,
+            "zap_in",
+            args![Workspace("input"), pair, FEE, Amount::new(1)],
+        )
+        .put_last_instruction_output_on_workspace("zapped")
+        .call_method(provider.account, "deposit", args![Workspace("zapped.1")])
+        // about half of the input is swapped twice, so a bit less than it comes back
+        .call_method(
+            tex,
+            "zap_out",
+            args![Workspace("zapped.0"), a, Amount::new(ZAP * 98 / 100)],
+        )
+        .put_last_instruction_output_on_workspace("output")
+        .call_method(provider.account, "deposit", args![Workspace("output")]);
+    tex_test.execute(builder, &provider);
+}
+
+#[test]
+fn zap_in_below_min_lp_is_rejected() {
+    let mut tex_test = TexTest::new();
+    let provider = tex_test.new_user();
+    let (tex, pair, a) = (tex_test.tex, tex_test.pair(), tex_test.a);
+    // zapping in one resource mints less than depositing both at the pool ratio
+    let builder = Transaction::builder()
+        .call_method(
+            tex_test.coin_of(a),
+            "take_free_coins",
+            args![Amount::new(ZAP)],
+        )
+        .put_last_instruction_output_on_workspace("input")
+        .call_method(
+            tex,
+            "zap_in",
+            args![Workspace("input"), pair, FEE, Amount::new(ZAP / 2)],
+        )
+        .put_last_instruction_output_on_workspace("zapped")
+        .call_method(provider.account, "deposit_all", args![Workspace("zapped")]);
+    tex_test.execute_expect_failure(builder, &provider);
+}
+
+#[test]
+fn zap_in_of_a_resource_outside_the_pair_is_rejected() {
+    let mut tex_test = TexTest::new();
+    let provider = tex_test.new_user();
+    let outsider_coin = tex_test.test.call_function::<ComponentAddress>(
+        "Coin",
+        "new",
+        args![Amount::new(ZAP), "TEXC"],
+        vec![],
+    );
+    let builder = Transaction::builder()
+        .call_method(outsider_coin, "take_free_coins", args![Amount::new(ZAP)])
+        .put_last_instruction_output_on_workspace("input")
+        .call_method(
+            tex_test.tex,
+            "zap_in",
+            args![Workspace("input"), tex_test.pair(), FEE, Amount::new(1)],
+        )
+        .put_last_instruction_output_on_workspace("zapped")
+        .call_method(provider.account, "deposit_all", args![Workspace("zapped")]);
+    tex_test.execute_expect_failure(builder, &provider);
+}
@@ -3,7 +3,9 @@ use std::error::Error;
 use scripts::templates;
 use tari_all_in_one_rust_sdk::wallet_daemon;
 use tari_engine_types::TemplateAddress;
-use tari_exchange::{ADMIN, COIN_TEMPLATE_HEX, TEX_TEMPLATE_HEX};
+use tari_exchange::{
+    ADMIN, COIN_TEMPLATE_HEX, DEFAULT_FEE_TIERS, DEFAULT_PROTOCOL_FEE_SHARE, TEX_TEMPLATE_HEX,
+};
 use tari_template_lib::args;
 
 #[tokio::main]
@@ -14,13 +16,16 @@ async fn main() -> Result<(), Box<dyn Error>> {
     let tex_template_address = TemplateAddress::from_hex(TEX_TEMPLATE_HEX).unwrap();
     let coin_template_address = TemplateAddress::from_hex(COIN_TEMPLATE_HEX).unwrap();
     {
-        let (component_instance_address, _component_instance_version) = templates::call_new(
-            account_name.clone(),
-            tex_template_address,
-            args![10],
-            &mut wallet_daemon_client,
-        )
-        .await;
+        let fee_tiers = DEFAULT_FEE_TIERS.to_vec();
+        // admin badge is deposited into the ADMIN account
+        let (component_instance_address, _component_instance_version) =
+            templates::call_new_and_deposit(
+                account_name.clone(),
+                tex_template_address,
+                args![fee_tiers, DEFAULT_PROTOCOL_FEE_SHARE],
+                &mut wallet_daemon_client,
+            )
+            .await;
         println!("TEX: {component_instance_address}");
     }
     {
@@ -1,9 +1,9 @@
 use std::{error::Error, str::FromStr};
 
-use tari_all_in_one_rust_sdk::{indexer, wallet_daemon};
+use tari_all_in_one_rust_sdk::{exchange, indexer, wallet_daemon};
 use tari_exchange::{
     LiquidityPools, COIN_COMPONENT_INSTANCE_ADDRESS_STR, LIQUIDITY_PROVIDER,
-    TEX_COMPONENT_INSTANCE_ADDRESS_STR, TEX_TEMPLATE_HEX, TRADER,
+    TEX_COMPONENT_INSTANCE_ADDRESS_STR, TRADER,
 };
 use tari_indexer_client::types::GetSubstateRequest;
 use tari_template_lib::prelude::*;
@@ -118,16 +118,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
     //     .unwrap_or_default();
     // dbg!(&decode);
     let mut indexer_client = indexer::client_connect(None).await?;
-    let substates = indexer::substates(
-        TemplateAddress::from_hex(TEX_TEMPLATE_HEX).unwrap(),
-        &mut indexer_client,
-    )
-    .await?;
-    for substate in substates {
-        let state = &substate.substate_value().as_component().unwrap().body.state;
-        dbg!(&state);
-        let exchange: tari_exchange::Exchange = state.deserialized().unwrap();
-        dbg!(exchange);
-    }
+    let exchange =
+        exchange::exchange_state(tex_component_instance_address, &mut indexer_client).await?;
+    dbg!(exchange);
     Ok(())
 }
@@ -27,9 +27,11 @@ pub mod templates {
 
     use tari_engine_types::{substate::SubstateId, TemplateAddress};
     use tari_template_lib::{
+        args,
         args::Arg,
         prelude::{Amount, ComponentAddress},
     };
+    use tari_transaction::UnsignedTransaction;
     use tari_wallet_daemon_client::{
         types::{AccountGetResponse, TransactionSubmitRequest, TransactionWaitResultRequest},
         ComponentAddressOrName, WalletDaemonClient,
@@ -52,9 +54,39 @@ pub mod templates {
             )
             .call_function(template_address, String::from("new"), args)
             .build_unsigned_transaction();
+        submit_and_find_component(account.key_index, transaction, client).await
+    }
+
+    /// Calls `new` of a template returning `(Component<Self>, Bucket)`, for example with an admin
+    /// badge, and deposits the bucket into the calling account.
+    pub async fn call_new_and_deposit(
+        account_name: String,
+        template_address: TemplateAddress,
+        args: Vec<Arg>,
+        client: &mut WalletDaemonClient,
+    ) -> (ComponentAddress, u32) {
+        let AccountGetResponse { account, .. } = client
+            .accounts_get(ComponentAddressOrName::Name(account_name.clone()))
+            .await
+            .unwrap();
+        let account_address = account.address.as_component_address().unwrap();
+        let transaction = tari_transaction::Transaction::builder()
+            .fee_transaction_pay_from_component(account_address, Amount(2000))
+            .call_function(template_address, String::from("new"), args)
+            .put_last_instruction_output_on_workspace("new")
+            .call_method(account_address, "deposit", args![Workspace("new.1")])
+            .build_unsigned_transaction();
+        submit_and_find_component(account.key_index, transaction, client).await
+    }
+
+    async fn submit_and_find_component(
+        key_index: u64,
+        transaction: UnsignedTransaction,
+        client: &mut WalletDaemonClient,
+    ) -> (ComponentAddress, u32) {
         let transaction_submit_req = TransactionSubmitRequest {
             transaction,
-            signing_key_index: Some(account.key_index),
+            signing_key_index: Some(key_index),
             detect_inputs: true,
             detect_inputs_use_unversioned: false,
             proof_ids: vec![],
@@ -76,8 +108,9 @@ pub mod templates {
             .expect("Failed to obtain substate diffs");
         let mut component_substate_id: Option<ComponentAddress> = None;
         let mut component_version: Option<u32> = None;
+        // depositing into the account may update its component too, so look for a new one only
         for (addr, data) in component_substate_diff.up_iter() {
-            if let SubstateId::Component(_) = addr {
+            if matches!(addr, SubstateId::Component(_)) && data.version() == 0 {
                 component_substate_id = Some(ComponentAddress::try_from(addr.clone()).unwrap());
                 component_version = Some(data.version());
                 break;
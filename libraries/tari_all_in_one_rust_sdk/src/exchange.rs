@@ -0,0 +1,148 @@
+//! On-chain layout of the Tex component state, converted into the `tari_exchange` mirror with vault
+//! balances read through the indexer.
+
+use std::collections::{BTreeMap, HashMap};
+
+use serde::{Deserialize, Serialize};
+use tari_exchange::{Curve, DynamicFee, ListingPolicy, oracle::PriceOracle, orders::LimitOrder};
+use tari_indexer_client::json_rpc_client::IndexerJsonRpcClient;
+use tari_template_lib::prelude::{Amount, ComponentAddress, ResourceAddress, Vault};
+
+use crate::indexer::{self, Result};
+
+/// Latest state of the Tex component instance, with pools and the treasury holding vault balances
+/// and the epoch the state was read at.
+pub async fn exchange_state(
+    component_address: ComponentAddress,
+    client: &mut IndexerJsonRpcClient,
+) -> Result<tari_exchange::Exchange> {
+    let exchange = indexer::component_state::<Exchange>(component_address, client).await?;
+    let mut liquidity_pools = HashMap::with_capacity(exchange.liquidity_pools.inner.len());
+    for (Pair(a, b), pools) in exchange.liquidity_pools.inner {
+        let pair = tari_exchange::Pair::new(a, b).expect("on-chain pair is always valid");
+        let mut mirrored_pools = Vec::with_capacity(pools.len());
+        for pool in pools {
+            mirrored_pools.push(liquidity_pool(pool, client).await);
+        }
+        liquidity_pools.insert(pair, mirrored_pools);
+    }
+    Ok(tari_exchange::Exchange {
+        liquidity_pools: tari_exchange::LiquidityPools {
+            inner: liquidity_pools,
+        },
+        fee_tiers: exchange.fee_tiers,
+        protocol_fee_share: exchange.treasury.share,
+        treasury: balances(&exchange.treasury.vaults, client).await,
+        paused: exchange.paused,
+        access_pass: exchange.access_pass,
+        listing: exchange.listing,
+        epoch: indexer::current_epoch(client).await?,
+    })
+}
+
+async fn liquidity_pool(
+    pool: LiquidityPool,
+    client: &mut IndexerJsonRpcClient,
+) -> tari_exchange::LiquidityPool {
+    tari_exchange::LiquidityPool {
+        a: indexer::get_vault(pool.a.vault_id(), client)
+            .await
+            .balance()
+            .0,
+        b: indexer::get_vault(pool.b.vault_id(), client)
+            .await
+            .balance()
+            .0,
+        lp_resource: pool.lp_resource,
+        fee: pool.fee,
+        curve: pool.curve,
+        fees_collected: balances(&pool.fees_collected, client).await,
+        protocol_fee_on: pool.protocol_fee_on,
+        paused: pool.paused,
+        delisted: pool.delisted,
+        oracle: pool.oracle,
+        orders: pool
+            .order_book
+            .orders
+            .into_iter()
+            .map(|(id, order)| LimitOrder {
+                id,
+                input_resource: order.input_resource,
+                amount: order.amount.0,
+                limit_price: order.limit_price,
+                filled: order.filled.map(|amount| amount.0),
+            })
+            .collect(),
+        dynamic_fee: pool.dynamic_fee,
+    }
+}
+
+/// Balances of `vaults` keyed by resource address.
+async fn balances(
+    vaults: &HashMap<ResourceAddress, Vault>,
+    client: &mut IndexerJsonRpcClient,
+) -> HashMap<String, i64> {
+    let mut balances = HashMap::with_capacity(vaults.len());
+    for (resource, vault) in vaults {
+        let balance = indexer::get_vault(vault.vault_id(), client)
+            .await
+            .balance()
+            .0;
+        balances.insert(resource.to_string(), balance);
+    }
+    balances
+}
+
+/// On-chain `Pair` is a sorted tuple struct, encoded the same way as a plain tuple.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+struct Pair(ResourceAddress, ResourceAddress);
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct LiquidityPool {
+    a: Vault,
+    b: Vault,
+    lp_resource: ResourceAddress,
+    fee: u32,
+    curve: Curve,
+    fees_collected: HashMap<ResourceAddress, Vault>,
+    protocol_fee_on: bool,
+    paused: bool,
+    delisted: bool,
+    oracle: PriceOracle,
+    order_book: OrderBook,
+    dynamic_fee: Option<DynamicFee>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct OrderBook {
+    orders: BTreeMap<u64, Order>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Order {
+    input_resource: ResourceAddress,
+    amount: Amount,
+    limit_price: u64,
+    filled: Option<Amount>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct LiquidityPools {
+    inner: HashMap<Pair, Vec<LiquidityPool>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Treasury {
+    share: u32,
+    vaults: HashMap<ResourceAddress, Vault>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Exchange {
+    liquidity_pools: LiquidityPools,
+    fee_tiers: Vec<u32>,
+    treasury: Treasury,
+    paused: bool,
+    access_pass: Option<ResourceAddress>,
+    listing: ListingPolicy,
+}
@@ -1,12 +1,19 @@
 use std::result::Result as StdResult;
 
 use derive_more::derive::{Display, Error as DeriveError, From};
-use tari_engine_types::{TemplateAddress, substate::Substate, vault::Vault};
+use serde::de::DeserializeOwned;
+use tari_bor::BorError;
+use tari_engine_types::{
+    TemplateAddress,
+    substate::{Substate, SubstateId},
+    vault::Vault,
+};
 use tari_indexer_client::{
     error::IndexerClientError,
     json_rpc_client::IndexerJsonRpcClient,
     types::{GetSubstateRequest, ListSubstatesRequest},
 };
+use tari_template_lib::prelude::ComponentAddress;
 use tokio::task::JoinError;
 
 pub type Result<T> = StdResult<T, Error>;
@@ -55,11 +62,43 @@ pub async fn substates(
     Ok(result)
 }
 
+/// Latest state of the component decoded as `T`, read through the indexer without a transaction,
+/// so it's free and does not require a wallet.
+pub async fn component_state<T: DeserializeOwned>(
+    component_address: ComponentAddress,
+    client: &mut IndexerJsonRpcClient,
+) -> Result<T> {
+    let component = client
+        .get_substate(GetSubstateRequest {
+            address: SubstateId::Component(component_address),
+            version: None,
+            local_search_only: false,
+        })
+        .await?
+        .substate
+        .into_substate_value()
+        .into_component()
+        .ok_or(Error::NotAComponent(component_address))?;
+    Ok(component.body.state.deserialized::<T>()?)
+}
+
+/// Current epoch of the network, as seen by the indexer.
+pub async fn current_epoch(client: &mut IndexerJsonRpcClient) -> Result<u64> {
+    Ok(client
+        .get_epoch_manager_stats()
+        .await?
+        .current_epoch
+        .as_u64())
+}
+
 #[derive(Debug, Display, DeriveError, From)]
 pub enum Error {
     TransactionNotFinilized,
     ClientFailed(IndexerClientError),
     TaskPanicked(JoinError),
+    #[display("NotAComponent: {_0}")]
+    NotAComponent(#[error(not(source))] ComponentAddress),
+    StateDecodeFailed(BorError),
 }
 
 pub async fn get_vault(
@@ -1,5 +1,6 @@
 pub type MicroMinotari = u64;
 
+pub mod exchange;
 pub mod indexer;
 pub mod wallet_daemon;
 
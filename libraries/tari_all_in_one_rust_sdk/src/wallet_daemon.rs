@@ -6,6 +6,7 @@ use tari_engine_types::{
     commit_result::FinalizeResult,
     substate::{InvalidSubstateIdVariant, SubstateId},
 };
+use tari_exchange::error::TexError;
 use tari_template_lib::prelude::{
     Amount, ComponentAddress, NonFungibleId, ResourceAddress, ResourceType, VaultId,
 };
@@ -16,8 +17,8 @@ use tari_wallet_daemon_client::{
     error::WalletDaemonClientError,
     types::{
         AccountsGetBalancesRequest, AuthLoginAcceptRequest, AuthLoginRequest, AuthLoginResponse,
-        ListAccountNftRequest, TransactionGetAllRequest, TransactionSubmitRequest,
-        TransactionWaitResultRequest,
+        ListAccountNftRequest, TransactionGetAllRequest, TransactionSubmitDryRunRequest,
+        TransactionSubmitRequest, TransactionWaitResultRequest,
     },
 };
 
@@ -95,6 +96,34 @@ pub async fn transaction_call_and_wait(
         .ok_or_else(|| Error::TransactionNotFinilized)
 }
 
+/// Executes the transaction without committing it, useful to call read-only methods of components.
+/// Fails with `Error::TransactionRejected` carrying the reject reason, e.g. a template panic message,
+/// mapped to the error Tex failed with.
+pub async fn transaction_dry_run(
+    key_index: u64,
+    transaction: UnsignedTransaction,
+    client: &mut WalletDaemonClient,
+) -> Result<FinalizeResult> {
+    let result = client
+        .submit_transaction_dry_run(TransactionSubmitDryRunRequest {
+            transaction,
+            signing_key_index: Some(key_index),
+            autofill_inputs: vec![],
+            detect_inputs: true,
+            detect_inputs_use_unversioned: true,
+            proof_ids: vec![],
+        })
+        .await?
+        .result
+        .finalize;
+    if let Some(reason) = result.full_reject() {
+        return Err(Error::TransactionRejected(TexError::from_reject_reason(
+            &reason.to_string(),
+        )));
+    }
+    Ok(result)
+}
+
 pub async fn accounts_nfts(
     account_name: &str,
     limit: u64,
@@ -193,6 +222,9 @@ pub struct Transactions(pub Vec<TransactionWithData>);
 #[derive(Debug, Display, DeriveError, From)]
 pub enum Error {
     TransactionNotFinilized,
+    #[display("{_0}")]
+    #[from(ignore)]
+    TransactionRejected(#[error(not(source))] TexError),
     ClientFailed(WalletDaemonClientError),
     AddressIncorrect(InvalidSubstateIdVariant),
 }
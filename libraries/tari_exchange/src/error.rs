@@ -0,0 +1,66 @@
+//! Typed errors of Tex transactions, recovered from reject reasons.
+//! Keep in sync with panic messages of the Tex template.
+
+use std::fmt;
+
+use serde::{Deserialize, Serialize};
+
+const EXCHANGE_PAUSED: &str = "exchange paused:";
+const POOL_PAUSED: &str = "pool paused:";
+const POOL_DELISTED: &str = "pool delisted:";
+const SLIPPAGE_EXCEEDED: &str = "slippage exceeded:";
+const POOL_NOT_AVAILABLE: &str = "liquidity pool not available";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TexError {
+    /// The whole exchange is paused by the admin, only liquidity removal is allowed.
+    ExchangePaused,
+    /// The pool is paused by the admin, only liquidity removal is allowed.
+    PoolPaused,
+    /// The pool is delisted for good, only liquidity removal is allowed.
+    PoolDelisted,
+    SlippageExceeded(String),
+    PoolNotAvailable,
+    /// Any other reject reason, kept as is.
+    Rejected(String),
+}
+
+impl TexError {
+    /// Maps a transaction reject `reason` to the error Tex failed with.
+    pub fn from_reject_reason(reason: &str) -> Self {
+        let detail = |prefix: &str| {
+            reason
+                .split_once(prefix)
+                .map(|(_, detail)| detail.trim().to_string())
+                .unwrap_or_default()
+        };
+        if reason.contains(EXCHANGE_PAUSED) {
+            TexError::ExchangePaused
+        } else if reason.contains(POOL_PAUSED) {
+            TexError::PoolPaused
+        } else if reason.contains(POOL_DELISTED) {
+            TexError::PoolDelisted
+        } else if reason.contains(SLIPPAGE_EXCEEDED) {
+            TexError::SlippageExceeded(detail(SLIPPAGE_EXCEEDED))
+        } else if reason.contains(POOL_NOT_AVAILABLE) {
+            TexError::PoolNotAvailable
+        } else {
+            TexError::Rejected(reason.to_string())
+        }
+    }
+}
+
+impl fmt::Display for TexError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TexError::ExchangePaused => write!(f, "exchange is paused"),
+            TexError::PoolPaused => write!(f, "pool is paused"),
+            TexError::PoolDelisted => write!(f, "pool is delisted"),
+            TexError::SlippageExceeded(detail) => write!(f, "slippage exceeded: {detail}"),
+            TexError::PoolNotAvailable => write!(f, "liquidity pool is not available"),
+            TexError::Rejected(reason) => write!(f, "transaction rejected: {reason}"),
+        }
+    }
+}
+
+impl std::error::Error for TexError {}
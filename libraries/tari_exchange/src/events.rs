@@ -0,0 +1,443 @@
+//! Typed decoders of events emitted by Tex liquidity pools.
+//! Keep in sync with `events` module of the Tex template.
+
+use std::{fmt, str::FromStr};
+
+use serde::{Deserialize, Serialize};
+use tari_template_lib::prelude::{Metadata, ResourceAddress};
+
+use crate::Pair;
+
+pub const POOL_CREATED: &str = "PoolCreated";
+pub const LIQUIDITY_ADDED: &str = "LiquidityAdded";
+pub const LIQUIDITY_REMOVED: &str = "LiquidityRemoved";
+pub const SWAP: &str = "Swap";
+pub const FLASH_LOAN: &str = "FlashLoan";
+pub const POSITION_OPENED: &str = "PositionOpened";
+pub const POSITION_CLOSED: &str = "PositionClosed";
+pub const WEIGHTED_POOL_CREATED: &str = "WeightedPoolCreated";
+pub const WEIGHTED_LIQUIDITY_ADDED: &str = "WeightedLiquidityAdded";
+pub const WEIGHTED_LIQUIDITY_REMOVED: &str = "WeightedLiquidityRemoved";
+pub const WEIGHTED_SWAP: &str = "WeightedSwap";
+pub const FARM_STAKED: &str = "FarmStaked";
+pub const FARM_UNSTAKED: &str = "FarmUnstaked";
+pub const FARM_REWARDS_CLAIMED: &str = "FarmRewardsClaimed";
+pub const ORDER_PLACED: &str = "OrderPlaced";
+pub const ORDER_FILLED: &str = "OrderFilled";
+pub const ORDER_CANCELLED: &str = "OrderCancelled";
+pub const ORDER_CLAIMED: &str = "OrderClaimed";
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TexEvent {
+    PoolCreated(PoolCreated),
+    LiquidityAdded(LiquidityAdded),
+    LiquidityRemoved(LiquidityRemoved),
+    Swap(Swap),
+    FlashLoan(FlashLoan),
+    PositionOpened(PositionOpened),
+    PositionClosed(PositionClosed),
+    WeightedPoolCreated(WeightedPoolCreated),
+    WeightedLiquidityAdded(WeightedLiquidityAdded),
+    WeightedLiquidityRemoved(WeightedLiquidityRemoved),
+    WeightedSwap(WeightedSwap),
+    FarmStaked(FarmEvent),
+    FarmUnstaked(FarmEvent),
+    FarmRewardsClaimed(FarmEvent),
+    OrderPlaced(OrderEvent),
+    OrderFilled(OrderEvent),
+    OrderCancelled(OrderEvent),
+    OrderClaimed(OrderEvent),
+}
+
+/// Pool identity and reserves right after the event, common to events of pools of a pair.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolState {
+    pub lp_resource: ResourceAddress,
+    pub pair: Pair,
+    /// Fee of the pool, in hundredths of a basis point.
+    pub fee_tier: u32,
+    pub reserve_a: i64,
+    pub reserve_b: i64,
+    /// Public key of the transaction signer.
+    pub caller: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PoolCreated {
+    pub pool: PoolState,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LiquidityAdded {
+    pub pool: PoolState,
+    pub amount_a: i64,
+    pub amount_b: i64,
+    pub lp_amount: i64,
+    pub refund_resource: ResourceAddress,
+    pub refund_amount: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LiquidityRemoved {
+    pub pool: PoolState,
+    pub amount_a: i64,
+    pub amount_b: i64,
+    pub lp_amount: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Swap {
+    pub pool: PoolState,
+    pub input_resource: ResourceAddress,
+    pub input_amount: i64,
+    pub output_resource: ResourceAddress,
+    pub output_amount: i64,
+    pub fee: i64,
+    /// Part of the `fee` taken by the treasury.
+    pub protocol_fee: i64,
+    /// Fee charged, in hundredths of a basis point. Differs from the fee tier of pools with a dynamic fee.
+    pub fee_rate: u32,
+}
+
+/// Flash loan repaid to the pool, reserves are the same as before the loan.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FlashLoan {
+    pub pool: PoolState,
+    pub resource: ResourceAddress,
+    pub amount: i64,
+    pub fee: i64,
+    /// Part of the `fee` taken by the treasury.
+    pub protocol_fee: i64,
+}
+
+/// Position opened in a concentrated liquidity pool, whose `lp_resource` is the position token.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionOpened {
+    pub pool: PoolState,
+    /// Id of the position token.
+    pub position: String,
+    pub lower_tick: i32,
+    pub upper_tick: i32,
+    pub liquidity: u128,
+    pub amount_a: i64,
+    pub amount_b: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PositionClosed {
+    pub pool: PoolState,
+    /// Id of the position token.
+    pub position: String,
+    pub lower_tick: i32,
+    pub upper_tick: i32,
+    pub liquidity: u128,
+    pub amount_a: i64,
+    pub amount_b: i64,
+    /// Fees earned by the position, paid out on top of `amount_a` and `amount_b`.
+    pub fees_a: i64,
+    pub fees_b: i64,
+}
+
+/// Weighted pool identity and reserves right after the event, common to weighted pool events.
+/// Lists follow the sorted order of `resources`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WeightedPoolState {
+    pub lp_resource: ResourceAddress,
+    /// Fee of the pool, in hundredths of a basis point.
+    pub fee_tier: u32,
+    pub resources: Vec<ResourceAddress>,
+    /// Weights of resources, scaled by `10^18`, summing up to about `10^18`.
+    pub weights: Vec<u128>,
+    pub reserves: Vec<i64>,
+    /// Public key of the transaction signer.
+    pub caller: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WeightedPoolCreated {
+    pub pool: WeightedPoolState,
+}
+
+/// Liquidity added to a weighted pool, `amounts` are zero except one for single asset joins.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WeightedLiquidityAdded {
+    pub pool: WeightedPoolState,
+    pub amounts: Vec<i64>,
+    pub lp_amount: i64,
+}
+
+/// Liquidity removed from a weighted pool, `amounts` are zero except one for single asset exits.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WeightedLiquidityRemoved {
+    pub pool: WeightedPoolState,
+    pub amounts: Vec<i64>,
+    pub lp_amount: i64,
+}
+
+/// Swap in a weighted pool, whose `fee` stays in the reserves.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WeightedSwap {
+    pub pool: WeightedPoolState,
+    pub input_resource: ResourceAddress,
+    pub input_amount: i64,
+    pub output_resource: ResourceAddress,
+    pub output_amount: i64,
+    pub fee: i64,
+    /// Part of the `fee` taken by the treasury, the rest stays in the reserves.
+    pub protocol_fee: i64,
+}
+
+/// Stake, unstake or claim of rewards in the farm of an LP resource.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FarmEvent {
+    pub lp_resource: ResourceAddress,
+    pub reward_resource: ResourceAddress,
+    /// LP tokens staked in the farm right after the event.
+    pub total_staked: i64,
+    /// Id of the stake receipt, a new one when rewards are claimed.
+    pub stake: String,
+    /// LP tokens of the stake.
+    pub amount: i64,
+    /// Rewards paid out.
+    pub rewards: i64,
+    /// Public key of the transaction signer.
+    pub caller: String,
+}
+
+/// Placement, fill, cancellation or claim of a limit order resting on a pool.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderEvent {
+    pub pool: PoolState,
+    /// Id of the order receipt.
+    pub order: u64,
+    pub input_resource: ResourceAddress,
+    pub amount: i64,
+    /// Least output per unit of input, scaled by `PRICE_SCALE`.
+    pub limit_price: u64,
+    /// Output of the fill, 0 while the order is open.
+    pub output_amount: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum EventError {
+    MissingField(&'static str),
+    InvalidField { field: &'static str, value: String },
+}
+
+impl TexEvent {
+    /// Decodes an event by its `topic` and `payload`.
+    /// Returns `Ok(None)` for events not emitted by Tex.
+    pub fn decode(topic: &str, payload: &Metadata) -> Result<Option<Self>, EventError> {
+        let event = match topic {
+            POOL_CREATED => TexEvent::PoolCreated(PoolCreated {
+                pool: PoolState::decode(payload)?,
+            }),
+            LIQUIDITY_ADDED => TexEvent::LiquidityAdded(LiquidityAdded {
+                pool: PoolState::decode(payload)?,
+                amount_a: field(payload, "amount_a")?,
+                amount_b: field(payload, "amount_b")?,
+                lp_amount: field(payload, "lp_amount")?,
+                refund_resource: field(payload, "refund_resource")?,
+                refund_amount: field(payload, "refund_amount")?,
+            }),
+            LIQUIDITY_REMOVED => TexEvent::LiquidityRemoved(LiquidityRemoved {
+                pool: PoolState::decode(payload)?,
+                amount_a: field(payload, "amount_a")?,
+                amount_b: field(payload, "amount_b")?,
+                lp_amount: field(payload, "lp_amount")?,
+            }),
+            SWAP => TexEvent::Swap(Swap {
+                pool: PoolState::decode(payload)?,
+                input_resource: field(payload, "input_resource")?,
+                input_amount: field(payload, "input_amount")?,
+                output_resource: field(payload, "output_resource")?,
+                output_amount: field(payload, "output_amount")?,
+                fee: field(payload, "fee")?,
+                protocol_fee: field(payload, "protocol_fee")?,
+                fee_rate: field(payload, "fee_rate")?,
+            }),
+            FLASH_LOAN => TexEvent::FlashLoan(FlashLoan {
+                pool: PoolState::decode(payload)?,
+                resource: field(payload, "resource")?,
+                amount: field(payload, "amount")?,
+                fee: field(payload, "fee")?,
+                protocol_fee: field(payload, "protocol_fee")?,
+            }),
+            POSITION_OPENED => TexEvent::PositionOpened(PositionOpened {
+                pool: PoolState::decode(payload)?,
+                position: field(payload, "position")?,
+                lower_tick: field(payload, "lower_tick")?,
+                upper_tick: field(payload, "upper_tick")?,
+                liquidity: field(payload, "position_liquidity")?,
+                amount_a: field(payload, "amount_a")?,
+                amount_b: field(payload, "amount_b")?,
+            }),
+            POSITION_CLOSED => TexEvent::PositionClosed(PositionClosed {
+                pool: PoolState::decode(payload)?,
+                position: field(payload, "position")?,
+                lower_tick: field(payload, "lower_tick")?,
+                upper_tick: field(payload, "upper_tick")?,
+                liquidity: field(payload, "position_liquidity")?,
+                amount_a: field(payload, "amount_a")?,
+                amount_b: field(payload, "amount_b")?,
+                fees_a: field(payload, "fees_a")?,
+                fees_b: field(payload, "fees_b")?,
+            }),
+            WEIGHTED_POOL_CREATED => TexEvent::WeightedPoolCreated(WeightedPoolCreated {
+                pool: WeightedPoolState::decode(payload)?,
+            }),
+            WEIGHTED_LIQUIDITY_ADDED => TexEvent::WeightedLiquidityAdded(WeightedLiquidityAdded {
+                pool: WeightedPoolState::decode(payload)?,
+                amounts: list_field(payload, "amounts")?,
+                lp_amount: field(payload, "lp_amount")?,
+            }),
+            WEIGHTED_LIQUIDITY_REMOVED => {
+                TexEvent::WeightedLiquidityRemoved(WeightedLiquidityRemoved {
+                    pool: WeightedPoolState::decode(payload)?,
+                    amounts: list_field(payload, "amounts")?,
+                    lp_amount: field(payload, "lp_amount")?,
+                })
+            }
+            WEIGHTED_SWAP => TexEvent::WeightedSwap(WeightedSwap {
+                pool: WeightedPoolState::decode(payload)?,
+                input_resource: field(payload, "input_resource")?,
+                input_amount: field(payload, "input_amount")?,
+                output_resource: field(payload, "output_resource")?,
+                output_amount: field(payload, "output_amount")?,
+                fee: field(payload, "fee")?,
+                protocol_fee: field(payload, "protocol_fee")?,
+            }),
+            FARM_STAKED => TexEvent::FarmStaked(FarmEvent::decode(payload)?),
+            FARM_UNSTAKED => TexEvent::FarmUnstaked(FarmEvent::decode(payload)?),
+            FARM_REWARDS_CLAIMED => TexEvent::FarmRewardsClaimed(FarmEvent::decode(payload)?),
+            ORDER_PLACED => TexEvent::OrderPlaced(OrderEvent::decode(payload)?),
+            ORDER_FILLED => TexEvent::OrderFilled(OrderEvent::decode(payload)?),
+            ORDER_CANCELLED => TexEvent::OrderCancelled(OrderEvent::decode(payload)?),
+            ORDER_CLAIMED => TexEvent::OrderClaimed(OrderEvent::decode(payload)?),
+            _ => return Ok(None),
+        };
+        Ok(Some(event))
+    }
+
+    /// State of the pool of a pair, `None` for weighted pool and farm events.
+    pub fn pool(&self) -> Option<&PoolState> {
+        match self {
+            TexEvent::PoolCreated(event) => Some(&event.pool),
+            TexEvent::LiquidityAdded(event) => Some(&event.pool),
+            TexEvent::LiquidityRemoved(event) => Some(&event.pool),
+            TexEvent::Swap(event) => Some(&event.pool),
+            TexEvent::FlashLoan(event) => Some(&event.pool),
+            TexEvent::PositionOpened(event) => Some(&event.pool),
+            TexEvent::PositionClosed(event) => Some(&event.pool),
+            TexEvent::OrderPlaced(event)
+            | TexEvent::OrderFilled(event)
+            | TexEvent::OrderCancelled(event)
+            | TexEvent::OrderClaimed(event) => Some(&event.pool),
+            _ => None,
+        }
+    }
+
+    /// State of the weighted pool, `None` for other events.
+    pub fn weighted_pool(&self) -> Option<&WeightedPoolState> {
+        match self {
+            TexEvent::WeightedPoolCreated(event) => Some(&event.pool),
+            TexEvent::WeightedLiquidityAdded(event) => Some(&event.pool),
+            TexEvent::WeightedLiquidityRemoved(event) => Some(&event.pool),
+            TexEvent::WeightedSwap(event) => Some(&event.pool),
+            _ => None,
+        }
+    }
+}
+
+impl PoolState {
+    fn decode(payload: &Metadata) -> Result<Self, EventError> {
+        let resource_a = field(payload, "resource_a")?;
+        let resource_b = field(payload, "resource_b")?;
+        Ok(Self {
+            lp_resource: field(payload, "lp_resource")?,
+            fee_tier: field(payload, "fee_tier")?,
+            pair: Pair::new(resource_a, resource_b).map_err(|_| EventError::InvalidField {
+                field: "resource_b",
+                value: resource_b.to_string(),
+            })?,
+            reserve_a: field(payload, "reserve_a")?,
+            reserve_b: field(payload, "reserve_b")?,
+            caller: field(payload, "caller")?,
+        })
+    }
+}
+
+impl WeightedPoolState {
+    fn decode(payload: &Metadata) -> Result<Self, EventError> {
+        Ok(Self {
+            lp_resource: field(payload, "lp_resource")?,
+            fee_tier: field(payload, "fee_tier")?,
+            resources: list_field(payload, "resources")?,
+            weights: list_field(payload, "weights")?,
+            reserves: list_field(payload, "reserves")?,
+            caller: field(payload, "caller")?,
+        })
+    }
+}
+
+impl FarmEvent {
+    fn decode(payload: &Metadata) -> Result<Self, EventError> {
+        Ok(Self {
+            lp_resource: field(payload, "lp_resource")?,
+            reward_resource: field(payload, "reward_resource")?,
+            total_staked: field(payload, "total_staked")?,
+            stake: field(payload, "stake")?,
+            amount: field(payload, "amount")?,
+            rewards: field(payload, "rewards")?,
+            caller: field(payload, "caller")?,
+        })
+    }
+}
+
+impl OrderEvent {
+    fn decode(payload: &Metadata) -> Result<Self, EventError> {
+        Ok(Self {
+            pool: PoolState::decode(payload)?,
+            order: field(payload, "order")?,
+            input_resource: field(payload, "input_resource")?,
+            amount: field(payload, "amount")?,
+            limit_price: field(payload, "limit_price")?,
+            output_amount: field(payload, "output_amount")?,
+        })
+    }
+}
+
+/// Comma separated list of values.
+fn list_field<T: FromStr>(payload: &Metadata, field: &'static str) -> Result<Vec<T>, EventError> {
+    let value = payload.get(field).ok_or(EventError::MissingField(field))?;
+    value
+        .split(',')
+        .map(|item| {
+            item.parse().map_err(|_| EventError::InvalidField {
+                field,
+                value: value.to_string(),
+            })
+        })
+        .collect()
+}
+
+fn field<T: FromStr>(payload: &Metadata, field: &'static str) -> Result<T, EventError> {
+    let value = payload.get(field).ok_or(EventError::MissingField(field))?;
+    value.parse().map_err(|_| EventError::InvalidField {
+        field,
+        value: value.to_string(),
+    })
+}
+
+impl fmt::Display for EventError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            EventError::MissingField(field) => write!(f, "event field '{field}' is missing"),
+            EventError::InvalidField { field, value } => {
+                write!(f, "event field '{field}' has invalid value '{value}'")
+            }
+        }
+    }
+}
+
+impl std::error::Error for EventError {}
@@ -1,8 +1,18 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt, str::FromStr};
 
 use serde::{Deserialize, Serialize};
 use tari_template_lib::prelude::ResourceAddress;
 
+use crate::{oracle::PriceOracle, orders::LimitOrder, stable::Amplification};
+
+pub mod error;
+pub mod events;
+pub mod lp_token;
+pub mod oracle;
+pub mod orders;
+pub mod router;
+pub mod stable;
+
 pub const ADMIN: &str = "GreatOotle";
 pub const LIQUIDITY_PROVIDER: &str = "HumbleLiquidityProvider";
 pub const TRADER: &str = "CuriousTrader";
@@ -14,6 +24,14 @@ pub const TEX_COMPONENT_INSTANCE_ADDRESS_STR: &str =
     "component_39806234aba484d9806ab8f2372f44d84ad090c4431b196d4dc82db1cb213697";
 pub const COIN_COMPONENT_INSTANCE_ADDRESS_STR: &str =
     "component_69e41614dcc9444854a9b541bc81094dee8bf42f383cbf58f5817725b40230d7";
+/// Fees are expressed in hundredths of a basis point, so `FEE_DENOMINATOR` is a 100% fee.
+pub const FEE_DENOMINATOR: u32 = 1_000_000;
+/// Fee tiers Tex is created with: 0.05%, 0.3% and 1%.
+pub const DEFAULT_FEE_TIERS: [u32; 3] = [500, 3_000, 10_000];
+/// Share of swap fees Tex is created to divert to its treasury, about 1/6, in the units of fees.
+pub const DEFAULT_PROTOCOL_FEE_SHARE: u32 = 166_667;
+/// Scale of fixed point prices reported by Tex, i.e. prices have 9 decimal places.
+pub const PRICE_SCALE: u64 = 1_000_000_000;
 
 #[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
 pub struct TariCoin {
@@ -36,26 +54,365 @@ pub struct LiquidityPool {
     pub a: i64,
     pub b: i64,
     pub lp_resource: ResourceAddress,
-    pub fees_collected: HashMap<String, f64>,
+    /// Fee charged on swap inputs, in hundredths of a basis point.
+    pub fee: u32,
+    pub curve: Curve,
+    /// Collected fees keyed by resource address.
+    pub fees_collected: HashMap<String, i64>,
+    /// Whether the treasury takes `Exchange::protocol_fee_share` of swap fees.
+    pub protocol_fee_on: bool,
+    /// Paused pools allow only liquidity removal.
+    pub paused: bool,
+    /// Delisted pools allow only liquidity removal, for good.
+    pub delisted: bool,
+    pub oracle: PriceOracle,
+    /// Limit orders resting on the pool, filled ones until claimed.
+    pub orders: Vec<LimitOrder>,
+    /// Set when swaps are charged by recent volatility instead of the `fee`, see `swap_fee`.
+    pub dynamic_fee: Option<DynamicFee>,
+}
+
+/// Bounds of the fee of a pool charging swaps by recent volatility, mirrors the Tex template `DynamicFee`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DynamicFee {
+    pub min: u32,
+    pub max: u32,
+}
+
+/// Pricing curve of a pool, mirrors the Tex template `Curve`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Curve {
+    ConstantProduct,
+    /// StableSwap curve of pegged resources, see `stable`.
+    Stable(Amplification),
 }
 
 #[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LiquidityPools {
-    pub inner: HashMap<Pair, LiquidityPool>,
+    /// Pools of each pair, at most one per fee tier.
+    pub inner: HashMap<Pair, Vec<LiquidityPool>>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Exchange {
     pub liquidity_pools: LiquidityPools,
-    pub fee: i64,
+    /// Fees new pools may be created with.
+    pub fee_tiers: Vec<u32>,
+    /// Share of swap fees taken by the treasury from pools with the protocol fee on.
+    pub protocol_fee_share: u32,
+    /// Protocol fees collected by the treasury keyed by resource address.
+    pub treasury: HashMap<String, i64>,
+    /// When paused, all pools allow only liquidity removal.
+    pub paused: bool,
+    /// Resource a proof of which is required to trade or provide liquidity, if any.
+    pub access_pass: Option<ResourceAddress>,
+    pub listing: ListingPolicy,
+    /// Epoch of the network the state was read at, quotes and prices are computed for it.
+    pub epoch: u64,
+}
+
+/// Resources new pools may list, mirrors the Tex template `ListingPolicy`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ListingPolicy {
+    /// Whether any resource not `denied` may be listed, otherwise only `allowed` ones may.
+    pub permissionless: bool,
+    pub allowed: Vec<ResourceAddress>,
+    pub denied: Vec<ResourceAddress>,
+}
+
+/// Client side mirror of the Tex template `Pair`.
+/// Resources are kept sorted, so both orders of the same resources produce the same key.
+/// Serialized as `"{a}:{b}"` to stay usable as a JSON object key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(into = "String", try_from = "String")]
+pub struct Pair(ResourceAddress, ResourceAddress);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PairError {
+    SameResource(ResourceAddress),
+    Malformed(String),
+}
+
+impl Pair {
+    pub fn new(a: ResourceAddress, b: ResourceAddress) -> Result<Self, PairError> {
+        if a == b {
+            return Err(PairError::SameResource(a));
+        }
+        Ok(if a < b { Pair(a, b) } else { Pair(b, a) })
+    }
+
+    pub fn a(&self) -> ResourceAddress {
+        self.0
+    }
+
+    pub fn b(&self) -> ResourceAddress {
+        self.1
+    }
+
+    pub fn contains(&self, resource: &ResourceAddress) -> bool {
+        self.0 == *resource || self.1 == *resource
+    }
+}
+
+impl fmt::Display for Pair {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}", self.0, self.1)
+    }
+}
+
+impl FromStr for Pair {
+    type Err = PairError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (a, b) = s
+            .split_once(':')
+            .ok_or_else(|| PairError::Malformed(s.to_string()))?;
+        let a = ResourceAddress::from_str(a).map_err(|_| PairError::Malformed(s.to_string()))?;
+        let b = ResourceAddress::from_str(b).map_err(|_| PairError::Malformed(s.to_string()))?;
+        Pair::new(a, b)
+    }
+}
+
+impl From<Pair> for String {
+    fn from(value: Pair) -> Self {
+        value.to_string()
+    }
+}
+
+impl TryFrom<String> for Pair {
+    type Error = PairError;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl fmt::Display for PairError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PairError::SameResource(resource) => {
+                write!(
+                    f,
+                    "pair requires two different resources, got {resource} twice"
+                )
+            }
+            PairError::Malformed(value) => write!(f, "malformed pair '{value}'"),
+        }
+    }
 }
 
-pub type Pair = String;
+impl std::error::Error for PairError {}
 
 impl Exchange {
     pub fn pools(&self) -> Vec<LiquidityPool> {
-        self.liquidity_pools.inner.values().cloned().collect()
+        self.liquidity_pools
+            .inner
+            .values()
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    /// Whether a new pool may list the `resource`.
+    pub fn is_listable(&self, resource: &ResourceAddress) -> bool {
+        !self.listing.denied.contains(resource)
+            && (self.listing.permissionless || self.listing.allowed.contains(resource))
+    }
+
+    pub fn pool(&self, pair: &Pair, fee: u32) -> Option<&LiquidityPool> {
+        self.liquidity_pools
+            .inner
+            .get(pair)?
+            .iter()
+            .find(|pool| pool.fee == fee)
+    }
+
+    /// Expected output of swapping `amount` of `input` to `output` in the pool with the `fee`,
+    /// computed the same way Tex does.
+    /// Returns `None` if there is no such pool or it has no liquidity.
+    pub fn quote_swap(
+        &self,
+        input: ResourceAddress,
+        amount: i64,
+        output: ResourceAddress,
+        fee: u32,
+    ) -> Option<i64> {
+        let (input_reserve, output_reserve) = self.reserves(input, output, fee)?;
+        let fee_rate = self.fee_rate(input, output, fee)?;
+        let amount = i128::from(amount);
+        let effective_amount = amount - fee_amount(amount, fee_rate);
+        let output_amount = match self.amplification(input, output, fee)? {
+            None => effective_amount * output_reserve / (input_reserve + effective_amount),
+            Some(amplification) => stable::amount_out(
+                input_reserve as u128,
+                output_reserve as u128,
+                effective_amount as u128,
+                amplification,
+            )? as i128,
+        };
+        i64::try_from(output_amount).ok()
+    }
+
+    /// Input required to receive exactly `output_amount` of `output` from the pool with the `fee`,
+    /// computed the same way Tex does.
+    /// Returns `None` if there is no such pool or it can't provide requested amount.
+    pub fn quote_swap_for_exact(
+        &self,
+        input: ResourceAddress,
+        output: ResourceAddress,
+        fee: u32,
+        output_amount: i64,
+    ) -> Option<i64> {
+        let (input_reserve, output_reserve) = self.reserves(input, output, fee)?;
+        let fee_rate = self.fee_rate(input, output, fee)?;
+        let new_output_reserve = output_reserve - i128::from(output_amount);
+        if new_output_reserve <= 0 || fee_rate >= FEE_DENOMINATOR {
+            return None;
+        }
+        let net_amount = match self.amplification(input, output, fee)? {
+            None => {
+                let k = input_reserve * output_reserve;
+                let new_input_reserve = (k + new_output_reserve - 1) / new_output_reserve;
+                new_input_reserve - input_reserve
+            }
+            Some(amplification) => stable::amount_in(
+                input_reserve as u128,
+                output_reserve as u128,
+                output_amount as u128,
+                amplification,
+            )? as i128,
+        };
+        let denominator = i128::from(FEE_DENOMINATOR);
+        let net_share = denominator - i128::from(fee_rate);
+        let mut gross_amount = (net_amount * denominator + net_share - 1) / net_share;
+        while gross_amount - fee_amount(gross_amount, fee_rate) < net_amount {
+            gross_amount += 1;
+        }
+        i64::try_from(gross_amount).ok()
+    }
+
+    /// Amplification of the pool for `input` and `output` with the `fee` at the current `epoch`,
+    /// `None` inside for constant product pools.
+    fn amplification(
+        &self,
+        input: ResourceAddress,
+        output: ResourceAddress,
+        fee: u32,
+    ) -> Option<Option<u64>> {
+        let pool = self.pool(&Pair::new(input, output).ok()?, fee)?;
+        Some(match &pool.curve {
+            Curve::ConstantProduct => None,
+            Curve::Stable(amplification) => Some(amplification.at(self.epoch)),
+        })
+    }
+
+    /// Fee a swap in the pool for `input` and `output` with the `fee` pays at the current `epoch`.
+    fn fee_rate(&self, input: ResourceAddress, output: ResourceAddress, fee: u32) -> Option<u32> {
+        let pool = self.pool(&Pair::new(input, output).ok()?, fee)?;
+        Some(pool.swap_fee(self.epoch))
     }
+
+    /// Reserves of the pool for `input` and `output` with the `fee`, in that order.
+    fn reserves(
+        &self,
+        input: ResourceAddress,
+        output: ResourceAddress,
+        fee: u32,
+    ) -> Option<(i128, i128)> {
+        let pair = Pair::new(input, output).ok()?;
+        let pool = self.pool(&pair, fee)?;
+        let (input_reserve, output_reserve) = if pair.a() == input {
+            (pool.a, pool.b)
+        } else {
+            (pool.b, pool.a)
+        };
+        if input_reserve == 0 || output_reserve == 0 {
+            return None;
+        }
+        Some((i128::from(input_reserve), i128::from(output_reserve)))
+    }
+}
+
+/// Fee charged by Tex on the `amount`, `fee` being in hundredths of a basis point.
+/// Rounded up like on-chain.
+fn fee_amount(amount: i128, fee: u32) -> i128 {
+    let denominator = i128::from(FEE_DENOMINATOR);
+    (amount * i128::from(fee) + denominator - 1) / denominator
+}
+
+impl LiquidityPool {
+    /// Fee liquidity providers actually earn on swaps at the `epoch`, i.e. the swap fee less
+    /// the treasury share, in hundredths of a basis point.
+    pub fn lp_fee(&self, protocol_fee_share: u32, epoch: u64) -> u32 {
+        let fee = self.swap_fee(epoch);
+        if !self.protocol_fee_on {
+            return fee;
+        }
+        let protocol_fee =
+            u64::from(fee) * u64::from(protocol_fee_share) / u64::from(FEE_DENOMINATOR);
+        fee - protocol_fee as u32
+    }
+
+    /// Fee due on top of a flash loan of `amount` from the pool, rounded up like on-chain.
+    pub fn flash_loan_fee(&self, amount: i64) -> i64 {
+        fee_amount(i128::from(amount), self.fee) as i64
+    }
+
+    /// Whether the pool accepts swaps, i.e. it's active and has liquidity.
+    pub fn is_tradable(&self) -> bool {
+        !self.paused && !self.delisted && self.a > 0 && self.b > 0
+    }
+
+    /// Spot prices of `(a, b)` at the `epoch`, each in the other resource and scaled by
+    /// `PRICE_SCALE`, `None` while the pool is empty.
+    pub fn prices(&self, epoch: u64) -> Option<(u64, u64)> {
+        if self.a <= 0 || self.b <= 0 {
+            return None;
+        }
+        let price = |base: i64, quote: i64| match &self.curve {
+            Curve::ConstantProduct => {
+                let price = i128::from(quote) * i128::from(PRICE_SCALE) / i128::from(base);
+                Some(u64::try_from(price).unwrap_or(u64::MAX))
+            }
+            Curve::Stable(amplification) => {
+                stable::price(base as u128, quote as u128, amplification.at(epoch))
+            }
+        };
+        Some((price(self.a, self.b)?, price(self.b, self.a)?))
+    }
+
+    /// Fee a swap pays at the `epoch`: the `fee`, or with `dynamic_fee` set, the `fee` raised by
+    /// volatility of recent prices within bounds, computed the same way Tex does.
+    pub fn swap_fee(&self, epoch: u64) -> u32 {
+        match &self.dynamic_fee {
+            None => self.fee,
+            Some(dynamic_fee) => {
+                let volatility = self.oracle.volatility(epoch, self.prices(epoch));
+                u64::from(self.fee)
+                    .saturating_add(volatility)
+                    .clamp(u64::from(dynamic_fee.min), u64::from(dynamic_fee.max))
+                    as u32
+            }
+        }
+    }
+
+    /// Time-weighted average prices of `(a, b)` over `window` epochs up to the `epoch`,
+    /// in the same format as `prices`. Returns `None` if the pool history does not cover it.
+    pub fn observe(&self, epoch: u64, window: u64) -> Option<(u64, u64)> {
+        self.oracle.observe(epoch, window, self.prices(epoch))
+    }
+}
+
+/// Lowest acceptable result for an `expected` amount, `slippage_tolerance` being a percentage.
+pub fn min_amount_with_slippage(expected: i64, slippage_tolerance: f64) -> i64 {
+    let tolerance = slippage_tolerance.clamp(0.0, 100.0) / 100.0;
+    (expected as f64 * (1.0 - tolerance)).floor() as i64
+}
+
+/// Highest acceptable payment for an `expected` amount, `slippage_tolerance` being a percentage.
+pub fn max_amount_with_slippage(expected: i64, slippage_tolerance: f64) -> i64 {
+    let tolerance = slippage_tolerance.max(0.0) / 100.0;
+    (expected as f64 * (1.0 + tolerance)).ceil() as i64
 }
 
 impl PartialEq for LiquidityPool {
@@ -0,0 +1,89 @@
+//! Recognition of Tex LP resources by their metadata.
+//! Keep in sync with `lp_token` module of the Tex template, which sets it on pool creation.
+
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+use tari_template_lib::prelude::{ComponentAddress, Metadata, ResourceAddress};
+
+use crate::{Exchange, LiquidityPool, Pair};
+
+pub const TEX_COMPONENT: &str = "tex_component";
+pub const POOL_KIND: &str = "pool_kind";
+pub const RESOURCES: &str = "resources";
+pub const FEE_TIER: &str = "fee_tier";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PoolKind {
+    ConstantProduct,
+    Stable,
+    /// LP tokens of concentrated liquidity pools are non-fungible positions.
+    Concentrated,
+    Weighted,
+}
+
+/// Pool identity carried by an LP resource.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LpToken {
+    /// Tex component the pool belongs to.
+    pub tex_component: ComponentAddress,
+    pub kind: PoolKind,
+    /// Resources of the pool, sorted.
+    pub resources: Vec<ResourceAddress>,
+    /// Fee of the pool, in hundredths of a basis point.
+    pub fee_tier: u32,
+}
+
+impl PoolKind {
+    fn from_metadata(value: &str) -> Option<Self> {
+        match value {
+            "constant_product" => Some(PoolKind::ConstantProduct),
+            "stable" => Some(PoolKind::Stable),
+            "concentrated" => Some(PoolKind::Concentrated),
+            "weighted" => Some(PoolKind::Weighted),
+            _ => None,
+        }
+    }
+}
+
+impl LpToken {
+    /// Decodes metadata of a resource, `None` unless it is a Tex LP resource.
+    /// Pools created before LP tokens carried metadata are not recognized this way,
+    /// `Exchange::pool_by_lp_resource` resolves them as well.
+    pub fn from_metadata(metadata: &Metadata) -> Option<Self> {
+        let resources = metadata
+            .get(RESOURCES)?
+            .split(',')
+            .map(|resource| ResourceAddress::from_str(resource).ok())
+            .collect::<Option<Vec<_>>>()?;
+        Some(Self {
+            tex_component: ComponentAddress::from_str(metadata.get(TEX_COMPONENT)?).ok()?,
+            kind: PoolKind::from_metadata(metadata.get(POOL_KIND)?)?,
+            resources,
+            fee_tier: metadata.get(FEE_TIER)?.parse().ok()?,
+        })
+    }
+
+    /// Pair of a pool of two resources, `None` for weighted pools of more.
+    pub fn pair(&self) -> Option<Pair> {
+        match self.resources.as_slice() {
+            [a, b] => Pair::new(*a, *b).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl Exchange {
+    /// Pool of a pair issuing the `lp_resource`.
+    pub fn pool_by_lp_resource(&self, lp_resource: &ResourceAddress) -> Option<&LiquidityPool> {
+        self.liquidity_pools
+            .inner
+            .values()
+            .flatten()
+            .find(|pool| pool.lp_resource == *lp_resource)
+    }
+
+    pub fn is_lp_resource(&self, resource: &ResourceAddress) -> bool {
+        self.pool_by_lp_resource(resource).is_some()
+    }
+}
@@ -0,0 +1,139 @@
+//! Off-chain reading of the time-weighted average price oracle of Tex pools.
+//! Keep in sync with `oracle` module of the Tex template, which accumulates prices on-chain.
+
+use serde::{Deserialize, Serialize};
+
+use crate::FEE_DENOMINATOR;
+
+/// Number of most recent changes of average prices `PriceOracle::volatility` takes the mean of.
+pub const VOLATILITY_WINDOW: usize = 8;
+
+/// Price accumulators of both resources of a pool at the start of an epoch.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Observation {
+    pub epoch: u64,
+    /// Sum of `a` prices in `b`, scaled by `PRICE_SCALE`, over all elapsed epochs.
+    pub a_price_cumulative: u128,
+    /// Sum of `b` prices in `a`, scaled by `PRICE_SCALE`, over all elapsed epochs.
+    pub b_price_cumulative: u128,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceOracle {
+    /// Oldest first, at most one per epoch.
+    pub observations: Vec<Observation>,
+}
+
+impl Observation {
+    fn advanced(&self, epoch: u64, prices: Option<(u64, u64)>) -> Self {
+        let elapsed = epoch - self.epoch;
+        let (a_price, b_price) = prices.unwrap_or_default();
+        Observation {
+            epoch,
+            a_price_cumulative: self
+                .a_price_cumulative
+                .wrapping_add(u128::from(a_price) * u128::from(elapsed)),
+            b_price_cumulative: self
+                .b_price_cumulative
+                .wrapping_add(u128::from(b_price) * u128::from(elapsed)),
+        }
+    }
+
+    fn interpolated(&self, next: &Observation, epoch: u64) -> Self {
+        let elapsed = u128::from(epoch - self.epoch);
+        let span = u128::from(next.epoch - self.epoch);
+        let interpolate = |from: u128, to: u128| {
+            let delta = to.wrapping_sub(from);
+            // `delta * elapsed / span` split, so the product can't overflow
+            let delta = delta / span * elapsed + delta % span * elapsed / span;
+            from.wrapping_add(delta)
+        };
+        Observation {
+            epoch,
+            a_price_cumulative: interpolate(self.a_price_cumulative, next.a_price_cumulative),
+            b_price_cumulative: interpolate(self.b_price_cumulative, next.b_price_cumulative),
+        }
+    }
+}
+
+impl PriceOracle {
+    /// Accumulators at the `epoch`, `prices` being current prices of `(a, b)`.
+    /// Returns `None` if there are no observations as old as the `epoch`.
+    pub fn cumulative_at(&self, epoch: u64, prices: Option<(u64, u64)>) -> Option<Observation> {
+        let last = self.observations.last()?;
+        if epoch >= last.epoch {
+            return Some(last.advanced(epoch, prices));
+        }
+        let index = self
+            .observations
+            .partition_point(|observation| observation.epoch <= epoch);
+        if index == 0 {
+            return None;
+        }
+        Some(self.observations[index - 1].interpolated(&self.observations[index], epoch))
+    }
+
+    /// Average prices of `(a, b)` over `window` epochs up to the `epoch`, computed the same way
+    /// Tex `observe` does. Returns `None` if the history does not cover the `window`.
+    pub fn observe(
+        &self,
+        epoch: u64,
+        window: u64,
+        prices: Option<(u64, u64)>,
+    ) -> Option<(u64, u64)> {
+        if window == 0 {
+            return None;
+        }
+        let from = self.cumulative_at(epoch.checked_sub(window)?, prices)?;
+        let to = self.cumulative_at(epoch, prices)?;
+        Some((
+            average(from.a_price_cumulative, to.a_price_cumulative, window),
+            average(from.b_price_cumulative, to.b_price_cumulative, window),
+        ))
+    }
+
+    /// Mean relative change of the average `a` price from one interval between observations
+    /// to the next, over the last `VOLATILITY_WINDOW` changes up to the `epoch`, in hundredths
+    /// of a basis point like fees, computed the same way Tex does for dynamic fees.
+    pub fn volatility(&self, epoch: u64, prices: Option<(u64, u64)>) -> u64 {
+        let Some(last) = self.observations.last() else {
+            return 0;
+        };
+        let current = (epoch > last.epoch).then(|| last.advanced(epoch, prices));
+        let observations: Vec<&Observation> = self.observations.iter().chain(&current).collect();
+        let start = observations.len().saturating_sub(VOLATILITY_WINDOW + 2);
+        let averages: Vec<u128> = observations[start..]
+            .windows(2)
+            .map(|pair| {
+                let elapsed = pair[1].epoch - pair[0].epoch;
+                let sum = pair[1]
+                    .a_price_cumulative
+                    .wrapping_sub(pair[0].a_price_cumulative);
+                sum / u128::from(elapsed)
+            })
+            .collect();
+        let changes: Vec<u128> = averages
+            .windows(2)
+            .filter(|pair| pair[0] > 0 && pair[1] > 0)
+            .map(|pair| pair[0].abs_diff(pair[1]) * u128::from(FEE_DENOMINATOR) / pair[0])
+            .collect();
+        if changes.is_empty() {
+            return 0;
+        }
+        let mean = changes.iter().sum::<u128>() / changes.len() as u128;
+        u64::try_from(mean).unwrap_or(u64::MAX)
+    }
+
+    /// Epoch of the latest pool activity.
+    pub fn latest_epoch(&self) -> Option<u64> {
+        self.observations
+            .last()
+            .map(|observation| observation.epoch)
+    }
+}
+
+/// Average price between accumulators `from` and `to` over `window` epochs. Prices are at most
+/// `u64::MAX`, so is their average.
+fn average(from: u128, to: u128, window: u64) -> u64 {
+    u64::try_from(to.wrapping_sub(from) / u128::from(window)).unwrap_or(u64::MAX)
+}
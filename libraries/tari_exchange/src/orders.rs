@@ -0,0 +1,89 @@
+//! Off-chain view of limit orders resting on Tex pools.
+//! Keep in sync with `orders` module of the Tex template, which fills orders on-chain.
+
+use serde::{Deserialize, Serialize};
+use tari_template_lib::prelude::ResourceAddress;
+
+use crate::{Exchange, Pair, PRICE_SCALE};
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LimitOrder {
+    /// Id of the order receipt.
+    pub id: u64,
+    pub input_resource: ResourceAddress,
+    pub amount: i64,
+    /// Least output per unit of input, scaled by `PRICE_SCALE`.
+    pub limit_price: u64,
+    /// Output received, `None` while the order is open.
+    pub filled: Option<i64>,
+}
+
+/// Open orders of a limit price, aggregated.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PriceLevel {
+    /// Price of `a` in `b` of the `Pair`, scaled by `PRICE_SCALE`.
+    pub price: u64,
+    /// Input of the orders, in `a` for asks and in `b` for bids.
+    pub amount: i64,
+    pub orders: usize,
+}
+
+/// Open orders of a pool, by the price of `a` in `b` of its `Pair`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderBook {
+    /// Orders selling `a`, lowest price first.
+    pub asks: Vec<PriceLevel>,
+    /// Orders buying `a` with `b`, highest price first.
+    pub bids: Vec<PriceLevel>,
+}
+
+impl LimitOrder {
+    pub fn is_open(&self) -> bool {
+        self.filled.is_none()
+    }
+}
+
+impl Exchange {
+    /// Open limit orders of the pool of the `pair` with the `fee`, `None` if there is no such pool.
+    pub fn order_book(&self, pair: &Pair, fee: u32) -> Option<OrderBook> {
+        let pool = self.pool(pair, fee)?;
+        let mut book = OrderBook::default();
+        for order in pool.orders.iter().filter(|order| order.is_open()) {
+            let (levels, price) = if order.input_resource == pair.a() {
+                (&mut book.asks, order.limit_price)
+            } else {
+                // bids are limited by the price of `b` in `a`, inverted here, rounded down
+                let price = u128::from(PRICE_SCALE) * u128::from(PRICE_SCALE)
+                    / u128::from(order.limit_price.max(1));
+                (&mut book.bids, u64::try_from(price).unwrap_or(u64::MAX))
+            };
+            match levels.iter_mut().find(|level| level.price == price) {
+                Some(level) => {
+                    level.amount += order.amount;
+                    level.orders += 1;
+                }
+                None => levels.push(PriceLevel {
+                    price,
+                    amount: order.amount,
+                    orders: 1,
+                }),
+            }
+        }
+        book.asks.sort_by_key(|level| level.price);
+        book.bids
+            .sort_by_key(|level| std::cmp::Reverse(level.price));
+        Some(book)
+    }
+
+    /// Limit order with the receipt `id`, together with the pair and the fee of its pool.
+    pub fn limit_order(&self, id: u64) -> Option<(Pair, u32, &LimitOrder)> {
+        self.liquidity_pools
+            .inner
+            .iter()
+            .flat_map(|(pair, pools)| pools.iter().map(move |pool| (*pair, pool)))
+            .find_map(|(pair, pool)| {
+                let order = pool.orders.iter().find(|order| order.id == id)?;
+                Some((pair, pool.fee, order))
+            })
+    }
+}
@@ -0,0 +1,117 @@
+//! Off-chain routing of multi-hop swaps over Tex pools.
+//! Each hop picks the pool of the pair the same way `Tex::swap_route` does on-chain,
+//! so the expected output of a route matches what the transaction returns.
+
+use serde::{Deserialize, Serialize};
+use tari_template_lib::prelude::ResourceAddress;
+
+use crate::{Exchange, Pair};
+
+/// Longest route `best_route` looks for by default.
+pub const DEFAULT_MAX_HOPS: usize = 3;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Route {
+    /// Resources to swap through, excluding the input and ending with the output,
+    /// i.e. the `path` argument of `Tex::swap_route`.
+    pub path: Vec<ResourceAddress>,
+    /// Fee of the pool used for each hop.
+    pub fees: Vec<u32>,
+    pub amount_out: i64,
+}
+
+impl Exchange {
+    /// Route of `amount` of `input` to `output` through at most `max_hops` pools returning the most.
+    /// Returns `None` if the exchange is paused or resources are not connected by tradable pools.
+    pub fn best_route(
+        &self,
+        input: ResourceAddress,
+        amount: i64,
+        output: ResourceAddress,
+        max_hops: usize,
+    ) -> Option<Route> {
+        if self.paused || input == output {
+            return None;
+        }
+        let mut best = None;
+        let mut route = Route {
+            path: Vec::with_capacity(max_hops),
+            fees: Vec::with_capacity(max_hops),
+            amount_out: amount,
+        };
+        self.explore(input, output, max_hops, &mut route, &mut best);
+        best
+    }
+
+    /// Output of the best pool of the pair for a single hop together with its fee,
+    /// skipping pools which are paused, delisted or empty.
+    pub fn best_hop(
+        &self,
+        input: ResourceAddress,
+        amount: i64,
+        output: ResourceAddress,
+    ) -> Option<(u32, i64)> {
+        let pair = Pair::new(input, output).ok()?;
+        self.liquidity_pools
+            .inner
+            .get(&pair)?
+            .iter()
+            .filter(|pool| pool.is_tradable())
+            .filter_map(|pool| {
+                self.quote_swap(input, amount, output, pool.fee)
+                    .map(|amount_out| (pool.fee, amount_out))
+            })
+            .max_by_key(|(_, amount_out)| *amount_out)
+    }
+
+    fn explore(
+        &self,
+        current: ResourceAddress,
+        output: ResourceAddress,
+        hops_left: usize,
+        route: &mut Route,
+        best: &mut Option<Route>,
+    ) {
+        if hops_left == 0 {
+            return;
+        }
+        let amount = route.amount_out;
+        let neighbours = self
+            .liquidity_pools
+            .inner
+            .keys()
+            .filter(|pair| pair.contains(&current))
+            .map(|pair| {
+                if pair.a() == current {
+                    pair.b()
+                } else {
+                    pair.a()
+                }
+            });
+        for next in neighbours {
+            // simple paths only, revisiting a resource never improves the output
+            if route.path.contains(&next) || next == current {
+                continue;
+            }
+            let Some((fee, amount_out)) = self.best_hop(current, amount, next) else {
+                continue;
+            };
+            route.path.push(next);
+            route.fees.push(fee);
+            route.amount_out = amount_out;
+            if next == output {
+                if best
+                    .as_ref()
+                    .is_none_or(|best| best.amount_out < route.amount_out)
+                {
+                    *best = Some(route.clone());
+                }
+            } else {
+                self.explore(next, output, hops_left - 1, route, best);
+            }
+            route.path.pop();
+            route.fees.pop();
+            route.amount_out = amount;
+        }
+    }
+}
@@ -0,0 +1,147 @@
+//! Off-chain StableSwap curve of Tex stable pools, see `Curve::Stable`.
+//! Keep in sync with `stable` module of the Tex template, which prices stable pools on-chain.
+
+use serde::{Deserialize, Serialize};
+
+const MAX_ITERATIONS: usize = 255;
+
+/// Amplification changing linearly from `initial` to `target` between two epochs.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Amplification {
+    pub initial: u64,
+    pub initial_epoch: u64,
+    pub target: u64,
+    pub target_epoch: u64,
+}
+
+impl Amplification {
+    /// Amplification at the `epoch`.
+    pub fn at(&self, epoch: u64) -> u64 {
+        if epoch >= self.target_epoch {
+            return self.target;
+        }
+        let elapsed = u128::from(epoch.saturating_sub(self.initial_epoch));
+        let duration = u128::from(self.target_epoch - self.initial_epoch);
+        let (initial, target) = (u128::from(self.initial), u128::from(self.target));
+        let amplification = if target > initial {
+            initial + (target - initial) * elapsed / duration
+        } else {
+            initial - (initial - target) * elapsed / duration
+        };
+        amplification as u64
+    }
+}
+
+/// Invariant `D` of `x` and `y` reserves, `None` if it does not converge.
+pub fn invariant(x: u128, y: u128, amplification: u64) -> Option<u128> {
+    if x == 0 || y == 0 {
+        return Some(0);
+    }
+    let ann = 4 * u128::from(amplification);
+    let sum = x + y;
+    let mut d = sum;
+    for _ in 0..MAX_ITERATIONS {
+        // the smaller reserve divides first, like in Tex
+        let d_product = mul_div(mul_div(d, d, 2 * x.min(y))?, d, 2 * x.max(y))?;
+        let previous = d;
+        d = mul_div(ann * sum + 2 * d_product, d, (ann - 1) * d + 3 * d_product)?;
+        if d.abs_diff(previous) <= 1 {
+            return Some(d);
+        }
+    }
+    None
+}
+
+/// Output of the curve for a `net_input` (after fees), computed the same way Tex does.
+pub fn amount_out(
+    input_reserve: u128,
+    output_reserve: u128,
+    net_input: u128,
+    amplification: u64,
+) -> Option<u128> {
+    let d = invariant(input_reserve, output_reserve, amplification)?;
+    let new_output_reserve = reserve(input_reserve + net_input, d, amplification)?;
+    Some(
+        output_reserve
+            .saturating_sub(new_output_reserve)
+            .saturating_sub(1),
+    )
+}
+
+/// Net input (before fees) the curve requires for the `output`, computed the same way Tex does.
+pub fn amount_in(
+    input_reserve: u128,
+    output_reserve: u128,
+    output: u128,
+    amplification: u64,
+) -> Option<u128> {
+    let new_output_reserve = output_reserve
+        .checked_sub(output)
+        .filter(|remaining| *remaining > 0)?;
+    let d = invariant(input_reserve, output_reserve, amplification)?;
+    let new_input_reserve = reserve(new_output_reserve, d, amplification)?;
+    Some(new_input_reserve.saturating_sub(input_reserve) + 1)
+}
+
+/// Marginal price of the `base` reserve in the `quote` reserve, scaled by `PRICE_SCALE`.
+/// Saturates at `u64::MAX` like Tex does.
+pub fn price(base_reserve: u128, quote_reserve: u128, amplification: u64) -> Option<u64> {
+    let d = invariant(base_reserve, quote_reserve, amplification)?;
+    let ann = 4 * u128::from(amplification);
+    let common = mul_div(4 * ann * base_reserve, quote_reserve, d)?;
+    let price = mul_div(
+        common + mul_div(d, d, base_reserve)?,
+        u128::from(crate::PRICE_SCALE),
+        common + mul_div(d, d, quote_reserve)?,
+    )?;
+    Some(u64::try_from(price).unwrap_or(u64::MAX))
+}
+
+fn reserve(other: u128, d: u128, amplification: u64) -> Option<u128> {
+    let ann = 4 * u128::from(amplification);
+    let c = mul_div(mul_div(d, d, 2 * other)?, d, 2 * ann)?;
+    let b = other + d / ann;
+    let mut y = d;
+    for _ in 0..MAX_ITERATIONS {
+        let previous = y;
+        let denominator = (2 * y + b)
+            .checked_sub(d)
+            .filter(|denominator| *denominator > 0)?;
+        y = mul_div(y, y, denominator)? + c / denominator + 1;
+        if y.abs_diff(previous) <= 1 {
+            return Some(y);
+        }
+    }
+    None
+}
+
+/// `a * b / denominator` rounded down, `None` if the result does not fit 128 bits.
+fn mul_div(a: u128, b: u128, denominator: u128) -> Option<u128> {
+    if denominator == 0 {
+        return None;
+    }
+    const MASK: u128 = u64::MAX as u128;
+    let (a_high, a_low) = (a >> 64, a & MASK);
+    let (b_high, b_low) = (b >> 64, b & MASK);
+    let low_low = a_low * b_low;
+    let high_low = a_high * b_low;
+    let low_high = a_low * b_high;
+    let middle = (low_low >> 64) + (high_low & MASK) + (low_high & MASK);
+    let low = (low_low & MASK) | (middle << 64);
+    let high = a_high * b_high + (high_low >> 64) + (low_high >> 64) + (middle >> 64);
+    if high >= denominator {
+        return None;
+    }
+    let mut quotient = 0u128;
+    let mut remainder = high;
+    for bit in (0..128).rev() {
+        let carry = remainder >> 127;
+        remainder = (remainder << 1) | ((low >> bit) & 1);
+        quotient <<= 1;
+        if carry == 1 || remainder >= denominator {
+            remainder = remainder.wrapping_sub(denominator);
+            quotient |= 1;
+        }
+    }
+    Some(quotient)
+}